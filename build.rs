@@ -1,7 +1,8 @@
-use std::env;
-use std::path::PathBuf;
-
+#[cfg(feature = "c-sbe")]
 fn main() {
+    use std::env;
+    use std::path::PathBuf;
+
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     let c_sbe_decoder_dir = PathBuf::from(&manifest_dir).join("c_sbe_decoder");
 
@@ -16,3 +17,9 @@ fn main() {
     println!("cargo:rerun-if-changed=c_sbe_decoder/decoder.c");
     println!("cargo:rerun-if-changed=c_sbe_decoder/decoder.h");
 }
+
+#[cfg(not(feature = "c-sbe"))]
+fn main() {
+    // Pure-Rust decoder only: nothing to compile
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -1,40 +1,288 @@
 #include "decoder.h"
+#include <stdlib.h>
 #include <string.h>
 
 /**
- * Stub implementation of SBE decoder
- * In a real implementation, this would decode from actual SBE wire format
- * For now, it returns synthetic data for testing
+ * SBE decoder for the Binance-style trade stream described in decoder.h
+ * All state lives in the caller-owned SbeCursor or SbeDecoder context;
+ * nothing is static, so independent decoders never interfere.
  */
 
-static uint64_t call_count = 0;
-static const uint32_t NUM_SYMBOLS = 10;
-static const uint64_t BASE_TIMESTAMP = 1700000000000ULL;
-
-int sbe_decode_next(struct RawTick* out) {
-    if (out == NULL) {
-        return -1;  /* Error: null pointer */
-    }
-
-    /* Generate synthetic tick data for testing */
-    /* In real implementation, this would read from SBE wire buffer */
-    
-    /* Simple pseudo-random generation for testing */
-    call_count++;
-    
-    /* Generate symbol_id (0 to NUM_SYMBOLS-1) */
-    out->symbol_id = (uint32_t)(call_count % NUM_SYMBOLS);
-    
-    /* Generate price (e.g., between 10 and 100 USDT in e8 format) */
-    uint64_t base_price = (10ULL + (call_count * 7) % 90) * 100000000ULL;
-    /* Add some variation */
-    int64_t variation = ((int64_t)(call_count * 13) % 1000000000LL) - 500000000LL;
-    out->px_e8 = (uint64_t)((int64_t)base_price + variation);
-    
-    /* Generate timestamp (incrementing) */
-    out->ts_unix_ms = BASE_TIMESTAMP + call_count;
-    
-    /* Return 1 to indicate success */
-    /* In a real implementation, would return 0 when no more data available */
+struct SbeDecoder {
+    uint8_t* buf;            /* Owned byte buffer */
+    size_t len;              /* Number of valid bytes in buf */
+    size_t capacity;         /* Allocated size of buf */
+    struct SbeCursor cursor; /* Decoding position within buf */
+};
+
+static const uint64_t POW10[17] = {
+    1ULL,
+    10ULL,
+    100ULL,
+    1000ULL,
+    10000ULL,
+    100000ULL,
+    1000000ULL,
+    10000000ULL,
+    100000000ULL,
+    1000000000ULL,
+    10000000000ULL,
+    100000000000ULL,
+    1000000000000ULL,
+    10000000000000ULL,
+    100000000000000ULL,
+    1000000000000000ULL,
+    10000000000000000ULL,
+};
+
+static uint16_t read_u16(const uint8_t* p) {
+    return (uint16_t)(p[0] | ((uint16_t)p[1] << 8));
+}
+
+static uint32_t read_u32(const uint8_t* p) {
+    return (uint32_t)p[0] | ((uint32_t)p[1] << 8) | ((uint32_t)p[2] << 16) |
+           ((uint32_t)p[3] << 24);
+}
+
+static int64_t read_i64(const uint8_t* p) {
+    uint64_t v = 0;
+    for (int i = 7; i >= 0; i--) {
+        v = (v << 8) | p[i];
+    }
+    return (int64_t)v;
+}
+
+/**
+ * Report an error and resume decoding at resume_at: the end of the bad
+ * message when it can be sized, otherwise the end of the buffer
+ */
+static int fail(struct SbeCursor* cursor, uint64_t resume_at, int32_t error) {
+    cursor->offset = resume_at;
+    cursor->remaining = 0;
+    cursor->error = error;
+    return -1;
+}
+
+void sbe_cursor_init(struct SbeCursor* cursor) {
+    if (cursor != NULL) {
+        memset(cursor, 0, sizeof(*cursor));
+    }
+}
+
+/**
+ * Enter the message at cursor->offset
+ * Returns 1 if entered, 0 if the message is not complete yet, -1 on error
+ */
+static int enter_message(const uint8_t* buf, size_t len, struct SbeCursor* cursor) {
+    uint64_t start = cursor->offset;
+    if (len - start < SBE_MESSAGE_HEADER_LENGTH) {
+        return 0;
+    }
+
+    const uint8_t* header = buf + start;
+    uint16_t block_length = read_u16(header);
+    uint16_t template_id = read_u16(header + 2);
+    uint16_t schema_id = read_u16(header + 4);
+    uint16_t version = read_u16(header + 6);
+
+    /* Neither a foreign schema nor a root block too short to hold the
+     * group header can be sized, so the rest of the buffer is dropped */
+    if (schema_id != SBE_SCHEMA_ID || version > SBE_SCHEMA_VERSION) {
+        return fail(cursor, len, SBE_ERR_SCHEMA_MISMATCH);
+    }
+    int is_trades = template_id == SBE_TEMPLATE_TRADES;
+    if (is_trades && block_length < SBE_TRADES_BLOCK_LENGTH) {
+        return fail(cursor, len, SBE_ERR_TRUNCATED);
+    }
+
+    uint64_t group_header = start + SBE_MESSAGE_HEADER_LENGTH + block_length;
+    if (len < group_header + SBE_GROUP_HEADER_LENGTH) {
+        return 0;
+    }
+
+    const uint8_t* group = buf + group_header;
+    uint16_t entry_length = read_u16(group);
+    uint32_t num_in_group = read_u32(group + 2);
+    uint64_t entries = group_header + SBE_GROUP_HEADER_LENGTH;
+    uint64_t end = entries + (uint64_t)entry_length * num_in_group;
+    if (len < end) {
+        return 0;
+    }
+
+    /* Every message of the schema shares this layout, so from here on a
+     * bad message is skipped and the frames behind it still decode */
+    if (!is_trades) {
+        return fail(cursor, end, SBE_ERR_UNKNOWN_TEMPLATE);
+    }
+    if (entry_length < SBE_TRADE_ENTRY_LENGTH) {
+        return fail(cursor, end, SBE_ERR_TRUNCATED);
+    }
+
+    const uint8_t* block = header + SBE_MESSAGE_HEADER_LENGTH;
+    int64_t transact_time_us = read_i64(block + 8);
+    int8_t price_exponent = (int8_t)block[16];
+    if (price_exponent < -8 || price_exponent > 8) {
+        return fail(cursor, end, SBE_ERR_BAD_PRICE);
+    }
+
+    cursor->offset = entries;
+    cursor->group_end = end;
+    cursor->ts_unix_ms = transact_time_us > 0 ? (uint64_t)transact_time_us / 1000 : 0;
+    cursor->remaining = num_in_group;
+    cursor->symbol_id = read_u32(block + 20);
+    cursor->entry_length = entry_length;
+    cursor->price_exponent = price_exponent;
     return 1;
 }
+
+int sbe_decode_next(const uint8_t* buf, size_t len, struct SbeCursor* cursor, struct RawTick* out) {
+    if (cursor == NULL) {
+        return -1;  /* Error: null pointer, nowhere to report it */
+    }
+    if (out == NULL || (buf == NULL && len > 0)) {
+        cursor->error = SBE_ERR_NULL_POINTER;
+        return -1;
+    }
+    if (cursor->offset > len) {
+        /* Buffer shrank underneath the cursor; treat as exhausted */
+        return 0;
+    }
+
+    /* Skip over messages whose group has been fully consumed (or is empty) */
+    while (cursor->remaining == 0) {
+        int entered = enter_message(buf, len, cursor);
+        if (entered <= 0) {
+            return entered;
+        }
+        if (cursor->remaining == 0) {
+            cursor->offset = cursor->group_end;
+        }
+    }
+
+    const uint8_t* entry = buf + cursor->offset;
+    int64_t price_mantissa = read_i64(entry + 8);
+    if (price_mantissa <= 0) {
+        return fail(cursor, cursor->group_end, SBE_ERR_BAD_PRICE);
+    }
+
+    uint64_t scale = POW10[cursor->price_exponent + 8];
+    if ((uint64_t)price_mantissa > UINT64_MAX / scale) {
+        return fail(cursor, cursor->group_end, SBE_ERR_BAD_PRICE);
+    }
+
+    out->symbol_id = cursor->symbol_id;
+    out->px_e8 = (uint64_t)price_mantissa * scale;
+    out->ts_unix_ms = cursor->ts_unix_ms;
+
+    cursor->offset += cursor->entry_length;
+    cursor->remaining--;
+    cursor->error = SBE_ERR_NONE;
+    return 1;
+}
+
+struct SbeDecoder* sbe_decoder_create(size_t initial_capacity) {
+    struct SbeDecoder* decoder = (struct SbeDecoder*)calloc(1, sizeof(struct SbeDecoder));
+    if (decoder == NULL) {
+        return NULL;
+    }
+
+    if (initial_capacity > 0) {
+        decoder->buf = (uint8_t*)malloc(initial_capacity);
+        if (decoder->buf == NULL) {
+            free(decoder);
+            return NULL;
+        }
+        decoder->capacity = initial_capacity;
+    }
+
+    sbe_cursor_init(&decoder->cursor);
+    return decoder;
+}
+
+void sbe_decoder_destroy(struct SbeDecoder* decoder) {
+    if (decoder != NULL) {
+        free(decoder->buf);
+        free(decoder);
+    }
+}
+
+int sbe_decoder_feed(struct SbeDecoder* decoder, const uint8_t* bytes, size_t len) {
+    if (decoder == NULL) {
+        return -1;
+    }
+    if (bytes == NULL && len > 0) {
+        decoder->cursor.error = SBE_ERR_NULL_POINTER;
+        return -1;
+    }
+
+    /* Drop bytes the cursor has already moved past */
+    size_t consumed = (size_t)decoder->cursor.offset;
+    if (consumed > decoder->len) {
+        consumed = decoder->len;
+    }
+    if (consumed > 0) {
+        memmove(decoder->buf, decoder->buf + consumed, decoder->len - consumed);
+        decoder->len -= consumed;
+        decoder->cursor.offset = 0;
+        decoder->cursor.group_end =
+            decoder->cursor.group_end > consumed ? decoder->cursor.group_end - consumed : 0;
+    }
+
+    if (decoder->len + len > decoder->capacity) {
+        size_t capacity = decoder->capacity > 0 ? decoder->capacity : 64;
+        while (capacity < decoder->len + len) {
+            capacity *= 2;
+        }
+        uint8_t* grown = (uint8_t*)realloc(decoder->buf, capacity);
+        if (grown == NULL) {
+            return -1;
+        }
+        decoder->buf = grown;
+        decoder->capacity = capacity;
+    }
+
+    if (len > 0) {
+        memcpy(decoder->buf + decoder->len, bytes, len);
+        decoder->len += len;
+    }
+    return 0;
+}
+
+int sbe_decoder_next(struct SbeDecoder* decoder, struct RawTick* out) {
+    if (decoder == NULL) {
+        return -1;
+    }
+    return sbe_decode_next(decoder->buf, decoder->len, &decoder->cursor, out);
+}
+
+size_t sbe_decoder_next_many(struct SbeDecoder* decoder, struct RawTick* out, size_t max_ticks, int* status) {
+    size_t count = 0;
+    int result = 1;
+
+    if (decoder == NULL) {
+        result = -1;
+    } else if (out == NULL && max_ticks > 0) {
+        decoder->cursor.error = SBE_ERR_NULL_POINTER;
+        result = -1;
+    } else {
+        while (count < max_ticks) {
+            result = sbe_decode_next(decoder->buf, decoder->len, &decoder->cursor, &out[count]);
+            if (result != 1) {
+                break;
+            }
+            count++;
+        }
+    }
+
+    if (status != NULL) {
+        *status = result;
+    }
+    return count;
+}
+
+int32_t sbe_decoder_last_error(const struct SbeDecoder* decoder) {
+    if (decoder == NULL) {
+        return SBE_ERR_NULL_POINTER;
+    }
+    return decoder->cursor.error;
+}
@@ -1,12 +1,65 @@
 #ifndef SBE_DECODER_H
 #define SBE_DECODER_H
 
+#include <stddef.h>
 #include <stdint.h>
 
 #ifdef __cplusplus
 extern "C" {
 #endif
 
+/**
+ * Binance-style SBE trade stream schema
+ *
+ * Every message starts with the standard 8-byte SBE message header
+ * (all fields little-endian):
+ *
+ *   uint16 block_length   Length of the root block that follows
+ *   uint16 template_id    SBE_TEMPLATE_TRADES
+ *   uint16 schema_id      SBE_SCHEMA_ID
+ *   uint16 version        <= SBE_SCHEMA_VERSION
+ *
+ * Root block (at least SBE_TRADES_BLOCK_LENGTH bytes):
+ *
+ *   int64  event_time_us     Event time in microseconds
+ *   int64  transact_time_us  Matching engine time in microseconds
+ *   int8   price_exponent    Decimal exponent applied to price mantissas
+ *   int8   qty_exponent      Decimal exponent applied to qty mantissas
+ *   uint16 (padding)
+ *   uint32 symbol_id         Dense symbol identifier assigned by the feed
+ *
+ * Repeating group "trades" (groupSize32Encoding):
+ *
+ *   uint16 block_length   Length of each entry (>= SBE_TRADE_ENTRY_LENGTH)
+ *   uint32 num_in_group   Number of entries
+ *
+ * Each entry:
+ *
+ *   int64  trade_id
+ *   int64  price_mantissa
+ *   int64  qty_mantissa
+ *   uint8  is_buyer_maker
+ *
+ * Block lengths larger than the ones above are accepted and the extra
+ * bytes skipped, as SBE allows for forward-compatible schema extensions.
+ */
+#define SBE_SCHEMA_ID 1
+#define SBE_SCHEMA_VERSION 0
+#define SBE_TEMPLATE_TRADES 10000
+
+#define SBE_MESSAGE_HEADER_LENGTH 8
+#define SBE_TRADES_BLOCK_LENGTH 24
+#define SBE_GROUP_HEADER_LENGTH 6
+#define SBE_TRADE_ENTRY_LENGTH 25
+
+/* Error codes reported through SbeCursor.error */
+#define SBE_ERR_NONE 0
+#define SBE_ERR_NULL_POINTER 1     /* A required pointer argument was NULL */
+#define SBE_ERR_TRUNCATED 2        /* Block length shorter than the schema requires */
+#define SBE_ERR_UNKNOWN_TEMPLATE 3 /* Template id is not SBE_TEMPLATE_TRADES */
+#define SBE_ERR_SCHEMA_MISMATCH 4  /* Schema id or version not supported */
+#define SBE_ERR_BAD_PRICE 5        /* Price exponent out of range or price not representable */
+
 /**
  * RawTick structure matching Rust's #[repr(C)] layout
  * Represents a decoded trade tick from SBE format
@@ -17,13 +70,99 @@ struct RawTick {
     uint64_t ts_unix_ms;     /* Unix timestamp in milliseconds */
 };
 
+/**
+ * Decoding position within a caller-supplied buffer
+ * Matches Rust's #[repr(C)] SbeCursor; owned by the caller so that
+ * independent decoders never share state.
+ */
+struct SbeCursor {
+    uint64_t offset;         /* Offset of the next unread byte */
+    uint64_t group_end;      /* Offset just past the current message */
+    uint64_t ts_unix_ms;     /* Transact time of the current message */
+    uint32_t remaining;      /* Trades left in the current group */
+    uint32_t symbol_id;      /* Symbol of the current message */
+    uint16_t entry_length;   /* Block length of each group entry */
+    int8_t price_exponent;   /* Price exponent of the current message */
+    uint8_t _pad;
+    int32_t error;           /* Last error code (SBE_ERR_*) */
+};
+
+/**
+ * Reset a cursor to the start of a buffer
+ *
+ * @param cursor Cursor to reset
+ */
+void sbe_cursor_init(struct SbeCursor* cursor);
+
 /**
  * Decode the next tick from SBE wire format
- * 
+ *
+ * A message is only entered once all of its bytes are present in the
+ * buffer, so a partial frame leaves the cursor untouched and can be
+ * retried after more bytes have been appended. On error the cursor is
+ * moved past the bad message, so frames queued behind it still decode;
+ * only a header that cannot be sized (foreign schema, or a trades root
+ * block too short to locate the group) drops the rest of the buffer.
+ *
+ * @param buf Buffer holding zero or more SBE messages
+ * @param len Number of valid bytes in buf
+ * @param cursor Decoding position within buf
  * @param out Pointer to RawTick structure to fill
- * @return 1 if a tick was decoded successfully, 0 if no more data, -1 on error
+ * @return 1 if a tick was decoded successfully, 0 if no more data
+ *         (or only a partial frame), -1 on error (see cursor->error)
+ */
+int sbe_decode_next(const uint8_t* buf, size_t len, struct SbeCursor* cursor, struct RawTick* out);
+
+/**
+ * Opaque decoder context
+ * Owns its byte buffer and cursor; independent contexts share no state,
+ * so each thread (or shard) can own one without synchronisation.
+ */
+struct SbeDecoder;
+
+/**
+ * Create a decoder context
+ *
+ * @param initial_capacity Initial byte buffer capacity (grown on demand)
+ * @return New context, or NULL if allocation failed
+ */
+struct SbeDecoder* sbe_decoder_create(size_t initial_capacity);
+
+/**
+ * Destroy a decoder context (NULL is ignored)
+ */
+void sbe_decoder_destroy(struct SbeDecoder* decoder);
+
+/**
+ * Append bytes to the context's buffer (may end in a partial frame)
+ * Bytes already decoded are discarded before the buffer grows.
+ *
+ * @return 0 on success, -1 on error (see sbe_decoder_last_error)
+ */
+int sbe_decoder_feed(struct SbeDecoder* decoder, const uint8_t* bytes, size_t len);
+
+/**
+ * Decode the next tick from the context's buffer
+ *
+ * @return 1 if a tick was decoded successfully, 0 if no more data
+ *         (or only a partial frame), -1 on error (see sbe_decoder_last_error)
+ */
+int sbe_decoder_next(struct SbeDecoder* decoder, struct RawTick* out);
+
+/**
+ * Decode up to max_ticks ticks in one call
+ *
+ * @param out Array of at least max_ticks RawTick structures
+ * @param status Receives the result of the call that ended the batch
+ *               (1 if the batch filled up, 0 if out of data, -1 on error)
+ * @return Number of ticks written to out
+ */
+size_t sbe_decoder_next_many(struct SbeDecoder* decoder, struct RawTick* out, size_t max_ticks, int* status);
+
+/**
+ * Error code of the last failed call on this context (SBE_ERR_*)
  */
-int sbe_decode_next(struct RawTick* out);
+int32_t sbe_decoder_last_error(const struct SbeDecoder* decoder);
 
 #ifdef __cplusplus
 }
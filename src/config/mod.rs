@@ -1,28 +1,1235 @@
+use crate::cpu::MAX_CPUS;
+use crate::data_feed::DecodeErrorPolicy;
+use crate::hotpath::{MAX_RING_CAPACITY, RING_SLOTS_PER_SEC};
+use crate::risk::MAX_POSITION_SLOTS;
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Prefix of environment variables overriding config fields
+/// (e.g. ALTBOT_RETURN_THRESHOLD_PCT=3.5)
+pub const ENV_PREFIX: &str = "ALTBOT_";
+
 /// Configuration for the ultra-fast altbot
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     /// Target p95 latency in milliseconds
-    #[allow(dead_code)]
     pub target_p95_ms: u64,
+    /// Target p99 latency in milliseconds
+    pub target_p99_ms: u64,
     /// Shadow mode enabled (no real orders)
-    #[allow(dead_code)]
     pub shadow_mode: bool,
     /// Return threshold for triggering (e.g., 5.0 = 5%)
     pub return_threshold_pct: f64,
+    /// After a trigger, a symbol stays quiet for this long (0 = re-trigger
+    /// on every tick over the threshold)
+    pub retrigger_cooldown_secs: u64,
+    /// Re-arm a suppressed symbol early once its return falls below this
+    /// level (%, below the threshold; None = cool-down only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rearm_below_pct: Option<f64>,
     /// Maximum number of symbols to track
     pub max_symbols: usize,
     /// Price ring buffer duration in seconds
     pub price_window_secs: u64,
+    /// Take-profit distance above the BUY fill price (e.g., 10.0 = +10%)
+    pub take_profit_pct: f64,
+    /// Quote-asset amount spent per BUY in e8 (e.g., 20 USDT); the
+    /// allocation of position slots that do not set their own
+    pub buy_quote_qty_e8: u64,
+    /// Send a LIMIT IOC BUY capped at this % above the trigger price
+    /// instead of a MARKET BUY (None = MARKET)
+    pub buy_ioc_slippage_pct: Option<f64>,
+    /// Shadow mode: adverse slippage applied to simulated BUY fills (%)
+    pub shadow_slippage_pct: f64,
+    /// Shadow mode: fee per filled leg (%)
+    pub shadow_fee_pct: f64,
+    /// Reaction of the SBE feeds (benchmark and shards) to a malformed frame
+    pub decode_error_policy: DecodeErrorPolicy,
+    /// Per-symbol overrides keyed by exchange symbol (e.g. [symbols.BTCUSDT])
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub symbols: BTreeMap<String, SymbolOverride>,
+    /// Exchange symbols that never trigger (e.g. ["LUNAUSDT"])
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub banned_symbols: Vec<String>,
+    /// Core pinning and scheduling per thread role (Linux; applied at startup)
+    #[serde(skip_serializing_if = "ThreadConfig::is_default")]
+    pub threads: ThreadConfig,
+    /// Background maintenance intervals (aggregates, eviction, health)
+    pub maintenance: MaintenanceConfig,
+    /// Alt Market Gate: alt health index thresholds driving can_buy
+    pub gate: GateConfig,
+    /// Position slots, buying freeze and automatic symbol bans
+    pub risk: RiskConfig,
+}
+
+/// Per-symbol trigger parameters; unset fields fall back to the global ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SymbolOverride {
+    /// Return threshold for this symbol (%)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_threshold_pct: Option<f64>,
+    /// Return window for this symbol, at most the global price_window_secs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_window_secs: Option<u64>,
+    /// false = never trigger on this symbol
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Cap on the quote amount held in this symbol, in e8 (limits the BUY size)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_position_quote_e8: Option<u64>,
+}
+
+/// Intervals of the off-hot-path maintenance task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaintenanceConfig {
+    /// Recompute the 5m/15m/1h/24h returns of every symbol this often
+    pub aggregates_interval_ms: u64,
+    /// Look for stale symbols this often
+    pub eviction_interval_ms: u64,
+    /// Publish a health snapshot this often
+    pub health_interval_ms: u64,
+    /// A symbol without trades for this long has its history evicted
+    /// (at least 60, one 1m history bucket)
+    pub stale_after_secs: u64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            aggregates_interval_ms: 1_000,
+            eviction_interval_ms: 10_000,
+            health_interval_ms: 5_000,
+            stale_after_secs: 300,
+        }
+    }
+}
+
+impl MaintenanceConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (field, value) in [
+            (
+                "maintenance.aggregates_interval_ms",
+                self.aggregates_interval_ms,
+            ),
+            (
+                "maintenance.eviction_interval_ms",
+                self.eviction_interval_ms,
+            ),
+            ("maintenance.health_interval_ms", self.health_interval_ms),
+        ] {
+            if value == 0 {
+                return Err(ConfigError::NonPositive { field, value: 0.0 });
+            }
+        }
+        if self.stale_after_secs < 60 {
+            return Err(ConfigError::StaleAfter {
+                secs: self.stale_after_secs,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Alt Market Gate settings. Each condition closes the gate below its `off`
+/// level and must be back above its `on` level before the gate reopens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GateConfig {
+    /// Drive can_buy from the alt health index (false = always allowed)
+    pub enabled: bool,
+    /// Market reference symbol (excluded from breadth)
+    pub reference_symbol: String,
+    /// Symbols with a 15m return needed before the gate decides anything
+    pub min_symbols: usize,
+    /// Close when the alt health index (0-100) drops below this
+    pub ahi_off_below: f64,
+    /// Reopen once the alt health index is at least this
+    pub ahi_on_above: f64,
+    /// Close when the median 15m alt return (%) drops below this
+    pub median_ret_15m_off_below_pct: f64,
+    /// Reopen once the median 15m alt return (%) is at least this
+    pub median_ret_15m_on_above_pct: f64,
+    /// Close when the reference symbol's 1h return (%) drops below this
+    pub reference_ret_1h_off_below_pct: f64,
+    /// Reopen once the reference symbol's 1h return (%) is at least this
+    pub reference_ret_1h_on_above_pct: f64,
+}
+
+impl Default for GateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            reference_symbol: "BTCUSDT".to_string(),
+            min_symbols: 10,
+            ahi_off_below: 40.0,
+            ahi_on_above: 55.0,
+            median_ret_15m_off_below_pct: -1.0,
+            median_ret_15m_on_above_pct: -0.5,
+            reference_ret_1h_off_below_pct: -2.0,
+            reference_ret_1h_on_above_pct: -1.0,
+        }
+    }
+}
+
+impl GateConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.min_symbols == 0 {
+            return Err(ConfigError::NonPositive {
+                field: "gate.min_symbols",
+                value: 0.0,
+            });
+        }
+        for (field, off, on) in [
+            ("gate.ahi", self.ahi_off_below, self.ahi_on_above),
+            (
+                "gate.median_ret_15m",
+                self.median_ret_15m_off_below_pct,
+                self.median_ret_15m_on_above_pct,
+            ),
+            (
+                "gate.reference_ret_1h",
+                self.reference_ret_1h_off_below_pct,
+                self.reference_ret_1h_on_above_pct,
+            ),
+        ] {
+            if off.is_nan() || on.is_nan() || on < off {
+                return Err(ConfigError::GateHysteresis { field, off, on });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Position limits enforced before a trigger becomes an order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskConfig {
+    /// Position slots; each holds one symbol's position at a time
+    pub slots: Vec<SlotConfig>,
+    /// Buying freeze after losses
+    pub freeze: FreezeConfig,
+    /// Ban a symbol this long after one of its trades closes without
+    /// profit (0 = off)
+    pub loss_ban_secs: u64,
+}
+
+/// When to freeze all buying, and for how long
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FreezeConfig {
+    /// Freeze after this many closed trades in a row without profit (0 = off)
+    pub max_consecutive_losses: u32,
+    /// Freeze once the UTC day's realized PnL is this far below its peak,
+    /// in quote e8 (None = off)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_drawdown_limit_e8: Option<u64>,
+    /// Length of a freeze from the closing trade
+    pub freeze_secs: u64,
+}
+
+impl Default for FreezeConfig {
+    fn default() -> Self {
+        Self {
+            max_consecutive_losses: 3,
+            daily_drawdown_limit_e8: None,
+            freeze_secs: 3_600,
+        }
+    }
+}
+
+/// One position slot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlotConfig {
+    /// Name in logs (e.g. "A")
+    pub name: String,
+    /// Quote amount a BUY through this slot spends, in e8
+    /// (None = buy_quote_qty_e8)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_qty_e8: Option<u64>,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            slots: ["A", "B"]
+                .into_iter()
+                .map(|name| SlotConfig {
+                    name: name.to_string(),
+                    quote_qty_e8: None,
+                })
+                .collect(),
+            freeze: FreezeConfig::default(),
+            loss_ban_secs: 86_400,
+        }
+    }
+}
+
+impl RiskConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.slots.is_empty() || self.slots.len() > MAX_POSITION_SLOTS {
+            return Err(ConfigError::SlotCount {
+                count: self.slots.len(),
+                max: MAX_POSITION_SLOTS,
+            });
+        }
+        for (index, slot) in self.slots.iter().enumerate() {
+            if slot.name.is_empty()
+                || self.slots[..index]
+                    .iter()
+                    .any(|other| other.name == slot.name)
+            {
+                return Err(ConfigError::SlotName {
+                    name: slot.name.clone(),
+                });
+            }
+            if slot.quote_qty_e8 == Some(0) {
+                return Err(ConfigError::NonPositive {
+                    field: "risk.slots.quote_qty_e8",
+                    value: 0.0,
+                });
+            }
+        }
+        for (field, value) in [
+            ("risk.freeze.freeze_secs", Some(self.freeze.freeze_secs)),
+            (
+                "risk.freeze.daily_drawdown_limit_e8",
+                self.freeze.daily_drawdown_limit_e8,
+            ),
+        ] {
+            if value == Some(0) {
+                return Err(ConfigError::NonPositive { field, value: 0.0 });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Placement of the bot's threads; roles left empty run wherever the
+/// scheduler puts them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThreadConfig {
+    /// Lock all process memory in RAM (mlockall) before the hot path starts
+    pub mlockall: bool,
+    /// Sharded benchmark: thread splitting frames across the shards
+    #[serde(skip_serializing_if = "ThreadRole::is_unset")]
+    pub router: ThreadRole,
+    /// Sharded benchmark: shard i is pinned to `cores[i % cores.len()]`
+    #[serde(skip_serializing_if = "ThreadRole::is_unset")]
+    pub shard: ThreadRole,
+    /// Thread running the hot path and execution (unsharded)
+    #[serde(skip_serializing_if = "ThreadRole::is_unset")]
+    pub hotpath: ThreadRole,
+    /// Normal mode: WebSocket ingest thread
+    #[serde(skip_serializing_if = "ThreadRole::is_unset")]
+    pub ingest: ThreadRole,
+}
+
+/// Core list and scheduling policy for one thread role
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThreadRole {
+    /// Cores the thread may run on (empty = no pinning)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cores: Vec<usize>,
+    /// SCHED_FIFO priority 1..=99 (None = default time-sharing policy)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fifo_priority: Option<u8>,
+}
+
+impl ThreadConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Roles with their config names
+    fn roles(&self) -> [(&'static str, &ThreadRole); 4] {
+        [
+            ("router", &self.router),
+            ("shard", &self.shard),
+            ("hotpath", &self.hotpath),
+            ("ingest", &self.ingest),
+        ]
+    }
+}
+
+impl ThreadRole {
+    fn is_unset(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Cores for the `index`-th thread of a role that pins one thread per core
+    pub fn core_for(&self, index: usize) -> Vec<usize> {
+        if self.cores.is_empty() {
+            return Vec::new();
+        }
+        vec![self.cores[index % self.cores.len()]]
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(&core) = self.cores.iter().find(|&&core| core >= MAX_CPUS) {
+            return Err(ConfigError::CoreOutOfRange {
+                core,
+                max_cpus: MAX_CPUS,
+            });
+        }
+        match self.fifo_priority {
+            Some(priority) if !(1..=99).contains(&priority) => {
+                Err(ConfigError::FifoPriority { priority })
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             target_p95_ms: 15,
+            target_p99_ms: 25,
             shadow_mode: true,
             return_threshold_pct: 5.0,
+            retrigger_cooldown_secs: 300,
+            rearm_below_pct: None,
             max_symbols: 300,
             price_window_secs: 60,
+            take_profit_pct: 10.0,
+            buy_quote_qty_e8: 20 * 100_000_000,
+            buy_ioc_slippage_pct: None,
+            shadow_slippage_pct: 0.05,
+            shadow_fee_pct: 0.1,
+            decode_error_policy: DecodeErrorPolicy::default(),
+            symbols: BTreeMap::new(),
+            banned_symbols: Vec::new(),
+            threads: ThreadConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            gate: GateConfig::default(),
+            risk: RiskConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Load a config file; the format follows the extension (.toml or .json).
+    /// Fields missing from the file keep their defaults.
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&text)
+                .map_err(|e| format!("Invalid TOML in {}: {}", path.display(), e)),
+            Some("json") => serde_json::from_str(&text)
+                .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e)),
+            _ => Err(format!(
+                "Unsupported config format {} (expected .toml or .json)",
+                path.display()
+            )),
+        }
+    }
+
+    /// Apply `field = value` overrides on top of this config.
+    /// Values are parsed as JSON scalars and fall back to plain strings
+    /// ("3.5", "true", "null", "BTCUSDT").
+    pub fn with_overrides<K, V>(
+        self,
+        overrides: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<Self, String>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut value = serde_json::to_value(&self).map_err(|e| e.to_string())?;
+        let fields = value
+            .as_object_mut()
+            .ok_or("Config does not serialize to a map")?;
+
+        let mut changed = false;
+        for (key, raw) in overrides {
+            let (key, raw) = (key.as_ref(), raw.as_ref());
+            let Some(field) = fields.get_mut(key) else {
+                return Err(format!("Unknown config field '{}'", key));
+            };
+            *field = serde_json::from_str(raw)
+                .unwrap_or_else(|_| serde_json::Value::String(raw.to_string()));
+            changed = true;
+        }
+        if !changed {
+            return Ok(self);
+        }
+
+        serde_json::from_value(value).map_err(|e| format!("Invalid config override: {}", e))
+    }
+
+    /// Check invariants the hot path relies on; `num_symbols` is the number
+    /// of symbols the run will track
+    pub fn validate(&self, num_symbols: usize) -> Result<(), ConfigError> {
+        for (field, value) in [
+            ("return_threshold_pct", self.return_threshold_pct),
+            ("take_profit_pct", self.take_profit_pct),
+            ("buy_quote_qty_e8", self.buy_quote_qty_e8 as f64),
+        ] {
+            if value.is_nan() || value <= 0.0 {
+                return Err(ConfigError::NonPositive { field, value });
+            }
+        }
+        for (field, value) in [
+            (
+                "buy_ioc_slippage_pct",
+                self.buy_ioc_slippage_pct.unwrap_or(0.0),
+            ),
+            ("shadow_slippage_pct", self.shadow_slippage_pct),
+            ("shadow_fee_pct", self.shadow_fee_pct),
+        ] {
+            if value.is_nan() || value < 0.0 {
+                return Err(ConfigError::Negative { field, value });
+            }
+        }
+
+        if let Some(rearm_pct) = self.rearm_below_pct {
+            if rearm_pct.is_nan() || rearm_pct >= self.return_threshold_pct {
+                return Err(ConfigError::RearmLevel {
+                    rearm_pct,
+                    threshold_pct: self.return_threshold_pct,
+                });
+            }
         }
+
+        let max_window_secs = MAX_RING_CAPACITY / RING_SLOTS_PER_SEC;
+        if self.price_window_secs == 0 || self.price_window_secs > max_window_secs {
+            return Err(ConfigError::WindowExceedsRing {
+                window_secs: self.price_window_secs,
+                max_window_secs,
+            });
+        }
+        if self.max_symbols < num_symbols {
+            return Err(ConfigError::TooManySymbols {
+                num_symbols,
+                max_symbols: self.max_symbols,
+            });
+        }
+        for (symbol, overrides) in &self.symbols {
+            overrides
+                .validate(self.price_window_secs, self.rearm_below_pct)
+                .map_err(|error| ConfigError::Symbol {
+                    symbol: symbol.clone(),
+                    error: Box::new(error),
+                })?;
+        }
+        for (role, thread) in self.threads.roles() {
+            thread.validate().map_err(|error| ConfigError::Thread {
+                role,
+                error: Box::new(error),
+            })?;
+        }
+        self.maintenance.validate()?;
+        self.gate.validate()?;
+        self.risk.validate()?;
+        if self.target_p99_ms < self.target_p95_ms {
+            return Err(ConfigError::LatencyTargets {
+                p95_ms: self.target_p95_ms,
+                p99_ms: self.target_p99_ms,
+            });
+        }
+        Ok(())
+    }
+
+    /// Effective config as TOML (unset optional fields are omitted)
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string(self).map_err(|e| format!("Failed to serialize config: {}", e))
+    }
+}
+
+impl SymbolOverride {
+    /// Check the override against the global ring window and re-arm level
+    fn validate(
+        &self,
+        ring_window_secs: u64,
+        rearm_below_pct: Option<f64>,
+    ) -> Result<(), ConfigError> {
+        if let Some(value) = self.return_threshold_pct {
+            if value.is_nan() || value <= 0.0 {
+                return Err(ConfigError::NonPositive {
+                    field: "return_threshold_pct",
+                    value,
+                });
+            }
+            // Re-arming at or above the symbol's threshold would never suppress
+            if let Some(rearm_pct) = rearm_below_pct.filter(|&rearm_pct| rearm_pct >= value) {
+                return Err(ConfigError::RearmLevel {
+                    rearm_pct,
+                    threshold_pct: value,
+                });
+            }
+        }
+        if let Some(window_secs) = self.price_window_secs {
+            if window_secs == 0 || window_secs > ring_window_secs {
+                return Err(ConfigError::WindowExceedsRing {
+                    window_secs,
+                    max_window_secs: ring_window_secs,
+                });
+            }
+        }
+        if self.max_position_quote_e8 == Some(0) {
+            return Err(ConfigError::NonPositive {
+                field: "max_position_quote_e8",
+                value: 0.0,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Fields a running bot picks up on reload; the rest are sized or captured
+/// at startup (ring buffers, symbol tables, execution engine) and need a restart
+pub const HOT_RELOADABLE: &[&str] = &[
+    "return_threshold_pct",
+    "retrigger_cooldown_secs",
+    "rearm_below_pct",
+    "symbols",
+    "banned_symbols",
+    "gate",
+];
+
+/// Outcome of a successful reload
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadSummary {
+    /// Fields whose new value was published
+    pub applied: Vec<String>,
+    /// Changed fields that keep their running value until restart
+    pub needs_restart: Vec<String>,
+}
+
+/// Validates reloaded configs and publishes them to the shared `ArcSwap`
+pub struct ConfigReloader {
+    /// Config read by the hot path
+    shared: Arc<ArcSwap<Config>>,
+    /// Number of symbols in use, for validation
+    num_symbols: usize,
+}
+
+impl ConfigReloader {
+    /// Create a reloader publishing into `shared`
+    pub fn new(shared: Arc<ArcSwap<Config>>, num_symbols: usize) -> Self {
+        Self {
+            shared,
+            num_symbols,
+        }
+    }
+
+    /// Validate `next` and publish its hot-reloadable fields. On error the
+    /// running config is left untouched.
+    pub fn apply(&self, next: Config) -> Result<ReloadSummary, String> {
+        let current = self.shared.load_full();
+        let mut running = serde_json::to_value(&*current).map_err(|e| e.to_string())?;
+        let next = serde_json::to_value(&next).map_err(|e| e.to_string())?;
+        let (Some(fields), Some(next_fields)) = (running.as_object_mut(), next.as_object()) else {
+            return Err("Config does not serialize to a map".to_string());
+        };
+
+        // Empty and unset fields are not serialized, so a field dropped
+        // from the file is only missing on one side
+        let keys: BTreeSet<String> = fields.keys().chain(next_fields.keys()).cloned().collect();
+        let mut summary = ReloadSummary::default();
+        for key in keys {
+            let value = next_fields.get(&key);
+            if fields.get(&key) == value {
+                continue;
+            }
+            if HOT_RELOADABLE.contains(&key.as_str()) {
+                match value {
+                    Some(value) => fields.insert(key.clone(), value.clone()),
+                    None => fields.remove(&key),
+                };
+                summary.applied.push(key);
+            } else {
+                summary.needs_restart.push(key);
+            }
+        }
+
+        let merged: Config = serde_json::from_value(running).map_err(|e| e.to_string())?;
+        merged
+            .validate(self.num_symbols)
+            .map_err(|e| e.to_string())?;
+        if !summary.applied.is_empty() {
+            self.shared.store(Arc::new(merged));
+        }
+        Ok(summary)
+    }
+}
+
+/// `ALTBOT_*` variables from `vars` as (field, value) overrides
+pub fn env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> Vec<(String, String)> {
+    vars.into_iter()
+        .filter_map(|(key, value)| {
+            let field = key.strip_prefix(ENV_PREFIX)?;
+            Some((field.to_ascii_lowercase(), value))
+        })
+        .collect()
+}
+
+/// Config validation failure
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// Field must be > 0
+    NonPositive { field: &'static str, value: f64 },
+    /// Field must be >= 0
+    Negative { field: &'static str, value: f64 },
+    /// Price window is zero or longer than the ring can hold
+    WindowExceedsRing {
+        window_secs: u64,
+        max_window_secs: u64,
+    },
+    /// More symbols requested than max_symbols allows
+    TooManySymbols {
+        num_symbols: usize,
+        max_symbols: usize,
+    },
+    /// p99 target below the p95 target
+    LatencyTargets { p95_ms: u64, p99_ms: u64 },
+    /// Re-arm level not below the trigger threshold
+    RearmLevel { rearm_pct: f64, threshold_pct: f64 },
+    /// Invalid per-symbol override
+    Symbol {
+        symbol: String,
+        error: Box<ConfigError>,
+    },
+    /// Core index beyond what an affinity mask can hold
+    CoreOutOfRange { core: usize, max_cpus: usize },
+    /// SCHED_FIFO priority outside 1..=99
+    FifoPriority { priority: u8 },
+    /// Stale-symbol eviction finer than a history bucket
+    StaleAfter { secs: u64 },
+    /// Gate reopen level below its close level (no hysteresis band)
+    GateHysteresis {
+        field: &'static str,
+        off: f64,
+        on: f64,
+    },
+    /// No position slots, or more than the slot table holds
+    SlotCount { count: usize, max: usize },
+    /// Empty or repeated position slot name
+    SlotName { name: String },
+    /// Invalid thread role settings
+    Thread {
+        role: &'static str,
+        error: Box<ConfigError>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::NonPositive { field, value } => {
+                write!(f, "{} must be > 0 (got {})", field, value)
+            }
+            ConfigError::Negative { field, value } => {
+                write!(f, "{} must be >= 0 (got {})", field, value)
+            }
+            ConfigError::WindowExceedsRing {
+                window_secs,
+                max_window_secs,
+            } => write!(
+                f,
+                "price_window_secs must be in 1..={} to fit the price ring (got {})",
+                max_window_secs, window_secs
+            ),
+            ConfigError::TooManySymbols {
+                num_symbols,
+                max_symbols,
+            } => write!(
+                f,
+                "max_symbols ({}) is below the number of symbols in use ({})",
+                max_symbols, num_symbols
+            ),
+            ConfigError::LatencyTargets { p95_ms, p99_ms } => write!(
+                f,
+                "target_p99_ms ({}) must be >= target_p95_ms ({})",
+                p99_ms, p95_ms
+            ),
+            ConfigError::RearmLevel {
+                rearm_pct,
+                threshold_pct,
+            } => write!(
+                f,
+                "rearm_below_pct ({}) must be below return_threshold_pct ({})",
+                rearm_pct, threshold_pct
+            ),
+            ConfigError::Symbol { symbol, error } => write!(f, "symbols.{}: {}", symbol, error),
+            ConfigError::CoreOutOfRange { core, max_cpus } => {
+                write!(f, "cores must be < {} (got {})", max_cpus, core)
+            }
+            ConfigError::FifoPriority { priority } => {
+                write!(f, "fifo_priority must be in 1..=99 (got {})", priority)
+            }
+            ConfigError::StaleAfter { secs } => write!(
+                f,
+                "maintenance.stale_after_secs must be >= 60 (got {})",
+                secs
+            ),
+            ConfigError::GateHysteresis { field, off, on } => write!(
+                f,
+                "{} reopen level ({}) must be >= its close level ({})",
+                field, on, off
+            ),
+            ConfigError::SlotCount { count, max } => write!(
+                f,
+                "risk.slots must declare 1..={} slots (got {})",
+                max, count
+            ),
+            ConfigError::SlotName { name } => write!(
+                f,
+                "risk.slots names must be unique and non-empty (got '{}')",
+                name
+            ),
+            ConfigError::Thread { role, error } => write!(f, "threads.{}: {}", role, error),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("altbot-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_toml_and_json() {
+        let toml_path = write_temp(
+            "config.toml",
+            "return_threshold_pct = 3.5\nprice_window_secs = 120\nbuy_ioc_slippage_pct = 0.2\n",
+        );
+        let config = Config::load_file(&toml_path).unwrap();
+        assert_eq!(config.return_threshold_pct, 3.5);
+        assert_eq!(config.price_window_secs, 120);
+        assert_eq!(config.buy_ioc_slippage_pct, Some(0.2));
+        assert_eq!(config.max_symbols, Config::default().max_symbols);
+
+        let json_path = write_temp("config.json", r#"{"max_symbols": 50, "shadow_mode": true}"#);
+        let config = Config::load_file(&json_path).unwrap();
+        assert_eq!(config.max_symbols, 50);
+        assert_eq!(config.return_threshold_pct, 5.0);
+
+        let unknown = write_temp("unknown.toml", "return_treshold_pct = 3.5\n");
+        assert!(Config::load_file(&unknown)
+            .unwrap_err()
+            .contains("return_treshold_pct"));
+        let yaml = write_temp("config.yaml", "max_symbols: 1\n");
+        assert!(Config::load_file(&yaml).is_err());
+
+        for path in [toml_path, json_path, unknown, yaml] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_overrides() {
+        let env = env_overrides([
+            ("ALTBOT_RETURN_THRESHOLD_PCT".to_string(), "2.5".to_string()),
+            ("ALTBOT_BUY_IOC_SLIPPAGE_PCT".to_string(), "0.1".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ]);
+        assert_eq!(env.len(), 2);
+
+        let config = Config::default()
+            .with_overrides(env)
+            .unwrap()
+            .with_overrides([("return_threshold_pct", "4"), ("shadow_mode", "true")])
+            .unwrap();
+        assert_eq!(config.return_threshold_pct, 4.0);
+        assert_eq!(config.buy_ioc_slippage_pct, Some(0.1));
+
+        let cleared = config
+            .with_overrides([("buy_ioc_slippage_pct", "null")])
+            .unwrap();
+        assert_eq!(cleared.buy_ioc_slippage_pct, None);
+
+        let halting = Config::default()
+            .with_overrides([("decode_error_policy", "halt")])
+            .unwrap();
+        assert_eq!(halting.decode_error_policy, DecodeErrorPolicy::Halt);
+
+        assert!(Config::default()
+            .with_overrides([("no_such_field", "1")])
+            .unwrap_err()
+            .contains("no_such_field"));
+        assert!(Config::default()
+            .with_overrides([("max_symbols", "lots")])
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Config::default().validate(300), Ok(()));
+
+        let config = Config {
+            return_threshold_pct: 0.0,
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate(3),
+            Err(ConfigError::NonPositive {
+                field: "return_threshold_pct",
+                value: 0.0
+            })
+        );
+
+        let config = Config {
+            price_window_secs: 601,
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate(3),
+            Err(ConfigError::WindowExceedsRing {
+                window_secs: 601,
+                max_window_secs: 600
+            })
+        );
+
+        let config = Config {
+            rearm_below_pct: Some(5.0),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate(3),
+            Err(ConfigError::RearmLevel {
+                rearm_pct: 5.0,
+                threshold_pct: 5.0
+            })
+        );
+        let mut config = Config {
+            rearm_below_pct: Some(2.0),
+            ..Config::default()
+        };
+        config.symbols.insert(
+            "SOLUSDT".to_string(),
+            SymbolOverride {
+                return_threshold_pct: Some(2.0),
+                ..SymbolOverride::default()
+            },
+        );
+        assert_eq!(
+            config.validate(3),
+            Err(ConfigError::Symbol {
+                symbol: "SOLUSDT".to_string(),
+                error: Box::new(ConfigError::RearmLevel {
+                    rearm_pct: 2.0,
+                    threshold_pct: 2.0
+                })
+            })
+        );
+
+        assert_eq!(
+            Config::default().validate(301),
+            Err(ConfigError::TooManySymbols {
+                num_symbols: 301,
+                max_symbols: 300
+            })
+        );
+
+        let config = Config {
+            shadow_fee_pct: -0.1,
+            ..Config::default()
+        };
+        assert!(matches!(
+            config.validate(3),
+            Err(ConfigError::Negative {
+                field: "shadow_fee_pct",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_symbol_overrides() {
+        let path = write_temp(
+            "symbols.toml",
+            "[symbols.PEPEUSDT]\nreturn_threshold_pct = 12.0\nprice_window_secs = 30\nmax_position_quote_e8 = 1000000000\n\n[symbols.BTCUSDT]\nenabled = false\n",
+        );
+        let config = Config::load_file(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+        assert_eq!(config.validate(3), Ok(()));
+        assert_eq!(
+            config.symbols["PEPEUSDT"],
+            SymbolOverride {
+                return_threshold_pct: Some(12.0),
+                price_window_secs: Some(30),
+                enabled: None,
+                max_position_quote_e8: Some(1_000_000_000),
+            }
+        );
+        assert_eq!(config.symbols["BTCUSDT"].enabled, Some(false));
+        assert_eq!(
+            toml::from_str::<Config>(&config.to_toml().unwrap()).unwrap(),
+            config
+        );
+
+        let mut config = config;
+        config
+            .symbols
+            .get_mut("PEPEUSDT")
+            .unwrap()
+            .price_window_secs = Some(61);
+        assert_eq!(
+            config.validate(3),
+            Err(ConfigError::Symbol {
+                symbol: "PEPEUSDT".to_string(),
+                error: Box::new(ConfigError::WindowExceedsRing {
+                    window_secs: 61,
+                    max_window_secs: 60
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_thread_settings() {
+        let path = write_temp(
+            "threads.toml",
+            "[threads]\nmlockall = true\n\n[threads.shard]\ncores = [2, 3]\nfifo_priority = 50\n\n[threads.router]\ncores = [1]\n",
+        );
+        let config = Config::load_file(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+        assert_eq!(config.validate(3), Ok(()));
+        assert!(config.threads.mlockall);
+        assert_eq!(config.threads.shard.fifo_priority, Some(50));
+        assert_eq!(config.threads.shard.core_for(2), vec![2]);
+        assert_eq!(config.threads.hotpath.core_for(0), Vec::<usize>::new());
+        assert_eq!(
+            toml::from_str::<Config>(&config.to_toml().unwrap()).unwrap(),
+            config
+        );
+        assert!(!Config::default().to_toml().unwrap().contains("threads"));
+
+        let mut config = config;
+        config.threads.shard.fifo_priority = Some(0);
+        assert_eq!(
+            config.validate(3),
+            Err(ConfigError::Thread {
+                role: "shard",
+                error: Box::new(ConfigError::FifoPriority { priority: 0 }),
+            })
+        );
+        config.threads.shard.fifo_priority = None;
+        config.threads.router.cores.push(MAX_CPUS);
+        assert!(config
+            .validate(3)
+            .unwrap_err()
+            .to_string()
+            .starts_with("threads.router: cores"));
+    }
+
+    #[test]
+    fn test_maintenance_settings() {
+        let config = Config::default()
+            .with_overrides([("maintenance", r#"{"health_interval_ms": 1000}"#)])
+            .unwrap();
+        assert_eq!(config.maintenance.health_interval_ms, 1_000);
+        assert_eq!(config.maintenance.stale_after_secs, 300);
+        assert_eq!(config.validate(3), Ok(()));
+
+        let mut invalid = config.clone();
+        invalid.maintenance.aggregates_interval_ms = 0;
+        assert_eq!(
+            invalid.validate(3),
+            Err(ConfigError::NonPositive {
+                field: "maintenance.aggregates_interval_ms",
+                value: 0.0
+            })
+        );
+        let mut invalid = config;
+        invalid.maintenance.stale_after_secs = 30;
+        assert_eq!(
+            invalid.validate(3),
+            Err(ConfigError::StaleAfter { secs: 30 })
+        );
+    }
+
+    #[test]
+    fn test_gate_hysteresis_validation() {
+        let mut config = Config::default();
+        config.gate.ahi_on_above = 30.0;
+        assert_eq!(
+            config.validate(3),
+            Err(ConfigError::GateHysteresis {
+                field: "gate.ahi",
+                off: 40.0,
+                on: 30.0
+            })
+        );
+        config.gate.ahi_on_above = 40.0;
+        assert_eq!(config.validate(3), Ok(()));
+    }
+
+    #[test]
+    fn test_risk_slots() {
+        let path = write_temp(
+            "risk.toml",
+            "[[risk.slots]]\nname = \"A\"\nquote_qty_e8 = 5000000000\n\n[[risk.slots]]\nname = \"B\"\n",
+        );
+        let config = Config::load_file(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+        assert_eq!(config.validate(3), Ok(()));
+        assert_eq!(config.risk.slots[0].quote_qty_e8, Some(5_000_000_000));
+        assert_eq!(config.risk.slots[1].name, "B");
+        assert_eq!(
+            toml::from_str::<Config>(&config.to_toml().unwrap()).unwrap(),
+            config
+        );
+        assert_eq!(Config::default().risk.slots.len(), 2);
+        assert_eq!(config.risk.freeze, FreezeConfig::default());
+
+        let frozen = Config::default()
+            .with_overrides([("risk", r#"{"freeze": {"daily_drawdown_limit_e8": 0}}"#)])
+            .unwrap();
+        assert_eq!(
+            frozen.validate(3),
+            Err(ConfigError::NonPositive {
+                field: "risk.freeze.daily_drawdown_limit_e8",
+                value: 0.0
+            })
+        );
+
+        let mut invalid = config.clone();
+        invalid.risk.slots[1].name = "A".to_string();
+        assert_eq!(
+            invalid.validate(3),
+            Err(ConfigError::SlotName {
+                name: "A".to_string()
+            })
+        );
+        let mut invalid = config;
+        invalid.risk.slots.clear();
+        assert_eq!(
+            invalid.validate(3),
+            Err(ConfigError::SlotCount {
+                count: 0,
+                max: MAX_POSITION_SLOTS
+            })
+        );
+    }
+
+    #[test]
+    fn test_reloader_publishes_hot_fields_only() {
+        let shared = Arc::new(ArcSwap::from_pointee(Config::default()));
+        let reloader = ConfigReloader::new(Arc::clone(&shared), 3);
+
+        let next = Config {
+            return_threshold_pct: 2.0,
+            price_window_secs: 120,
+            ..Config::default()
+        };
+        let summary = reloader.apply(next).unwrap();
+        assert_eq!(summary.applied, vec!["return_threshold_pct".to_string()]);
+        assert_eq!(summary.needs_restart, vec!["price_window_secs".to_string()]);
+        assert_eq!(shared.load().return_threshold_pct, 2.0);
+        assert_eq!(shared.load().price_window_secs, 60);
+
+        let invalid = Config {
+            return_threshold_pct: -1.0,
+            ..Config::default()
+        };
+        assert!(reloader.apply(invalid).is_err());
+        assert_eq!(shared.load().return_threshold_pct, 2.0);
+
+        let mut bans = (*shared.load_full()).clone();
+        bans.banned_symbols = vec!["LUNAUSDT".to_string()];
+        bans.risk.loss_ban_secs = 0;
+        bans.gate.ahi_off_below = 30.0;
+        let summary = reloader.apply(bans).unwrap();
+        assert_eq!(
+            summary.applied,
+            vec!["banned_symbols".to_string(), "gate".to_string()]
+        );
+        assert_eq!(shared.load().gate.ahi_off_below, 30.0);
+        assert_eq!(summary.needs_restart, vec!["risk".to_string()]);
+        assert_eq!(shared.load().banned_symbols, vec!["LUNAUSDT".to_string()]);
+        assert_eq!(shared.load().risk.loss_ban_secs, 86_400);
+
+        let before = shared.load_full();
+        let unchanged = reloader.apply((*before).clone()).unwrap();
+        assert_eq!(unchanged, ReloadSummary::default());
+        assert!(Arc::ptr_eq(&before, &shared.load_full()));
+    }
+
+    #[test]
+    fn test_reloader_applies_removed_fields() {
+        let shared = Arc::new(ArcSwap::from_pointee(Config::default()));
+        let reloader = ConfigReloader::new(Arc::clone(&shared), 3);
+        let mut set = Config {
+            rearm_below_pct: Some(2.0),
+            banned_symbols: vec!["AAAUSDT".to_string()],
+            ..Config::default()
+        };
+        set.symbols.insert(
+            "SOLUSDT".to_string(),
+            SymbolOverride {
+                enabled: Some(false),
+                ..SymbolOverride::default()
+            },
+        );
+        reloader.apply(set.clone()).unwrap();
+
+        // Dropping each one from the file restores its default
+        for (field, cleared) in [
+            (
+                "rearm_below_pct",
+                Config {
+                    rearm_below_pct: None,
+                    ..set.clone()
+                },
+            ),
+            (
+                "banned_symbols",
+                Config {
+                    banned_symbols: Vec::new(),
+                    ..set.clone()
+                },
+            ),
+            (
+                "symbols",
+                Config {
+                    symbols: BTreeMap::new(),
+                    ..set.clone()
+                },
+            ),
+        ] {
+            reloader.apply(set.clone()).unwrap();
+            let summary = reloader.apply(cleared.clone()).unwrap();
+            assert_eq!(summary.applied, vec![field.to_string()]);
+            assert_eq!(**shared.load(), cleared);
+        }
+        assert_eq!(
+            reloader.apply(Config::default()).unwrap().applied,
+            vec!["banned_symbols".to_string(), "rearm_below_pct".to_string()]
+        );
+        assert_eq!(**shared.load(), Config::default());
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let config = Config {
+            buy_ioc_slippage_pct: Some(0.3),
+            ..Config::default()
+        };
+        let text = config.to_toml().unwrap();
+        assert_eq!(toml::from_str::<Config>(&text).unwrap(), config);
+        assert!(!Config::default()
+            .to_toml()
+            .unwrap()
+            .contains("buy_ioc_slippage_pct"));
     }
 }
@@ -0,0 +1,303 @@
+//! Thread placement for hot-path threads (Linux `sched_setaffinity`,
+//! `SCHED_FIFO`, `mlockall`)
+
+use std::fmt;
+
+/// Highest CPU index + 1 an affinity mask can hold (glibc `CPU_SETSIZE`)
+pub const MAX_CPUS: usize = 1024;
+
+/// Kernel list of cores removed from the scheduler (`isolcpus=`)
+const ISOLATED_CPUS_PATH: &str = "/sys/devices/system/cpu/isolated";
+
+/// What one thread asked for and what it ended up with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacementReport {
+    /// Thread name, e.g. "shard-0"
+    pub thread: String,
+    /// Requested cores (empty = leave affinity alone)
+    pub requested: Vec<usize>,
+    /// Cores the thread may run on after placement (empty if unknown)
+    pub effective: Vec<usize>,
+    /// Requested cores that are not isolated from the scheduler
+    pub not_isolated: Vec<usize>,
+    /// SCHED_FIFO priority now in effect
+    pub fifo_priority: Option<u8>,
+    /// Placement steps that failed
+    pub errors: Vec<String>,
+}
+
+impl PlacementReport {
+    /// The effective affinity is exactly the requested core list
+    /// (trivially true when nothing was requested)
+    pub fn honoured(&self) -> bool {
+        if self.requested.is_empty() {
+            return true;
+        }
+        let mut requested = self.requested.clone();
+        requested.sort_unstable();
+        requested.dedup();
+        requested == self.effective
+    }
+}
+
+impl fmt::Display for PlacementReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: effective cores {:?}", self.thread, self.effective)?;
+        if !self.requested.is_empty() {
+            let verdict = if self.honoured() {
+                "honoured"
+            } else {
+                "NOT honoured"
+            };
+            write!(f, ", requested {:?} {}", self.requested, verdict)?;
+        }
+        if !self.not_isolated.is_empty() {
+            write!(f, ", not isolated {:?}", self.not_isolated)?;
+        }
+        match self.fifo_priority {
+            Some(priority) => write!(f, ", SCHED_FIFO {}", priority)?,
+            None => write!(f, ", SCHED_OTHER")?,
+        }
+        for error in &self.errors {
+            write!(f, "; {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pin the calling thread to `cores` (if any), raise it to SCHED_FIFO
+/// `fifo_priority` (if set) and report the result. Failures are recorded
+/// in the report rather than returned so startup can continue unpinned.
+pub fn place_current_thread(
+    thread: &str,
+    cores: &[usize],
+    fifo_priority: Option<u8>,
+) -> PlacementReport {
+    let mut errors = Vec::new();
+    if !cores.is_empty() {
+        if let Err(e) = pin_current_thread(cores) {
+            errors.push(e);
+        }
+    }
+    let fifo_priority = match fifo_priority.map(set_fifo_priority) {
+        Some(Err(e)) => {
+            errors.push(e);
+            None
+        }
+        Some(Ok(())) => fifo_priority,
+        None => None,
+    };
+    let effective = current_affinity().unwrap_or_else(|e| {
+        errors.push(e);
+        Vec::new()
+    });
+    let not_isolated = match isolated_cores() {
+        Ok(isolated) => cores
+            .iter()
+            .copied()
+            .filter(|core| !isolated.contains(core))
+            .collect(),
+        Err(_) => cores.to_vec(),
+    };
+
+    PlacementReport {
+        thread: thread.to_string(),
+        requested: cores.to_vec(),
+        effective,
+        not_isolated,
+        fifo_priority,
+        errors,
+    }
+}
+
+/// Cores isolated from the scheduler by the kernel command line
+pub fn isolated_cores() -> Result<Vec<usize>, String> {
+    let text = std::fs::read_to_string(ISOLATED_CPUS_PATH)
+        .map_err(|e| format!("Failed to read {}: {}", ISOLATED_CPUS_PATH, e))?;
+    parse_cpu_list(&text)
+}
+
+/// Parse a kernel CPU list ("0-3,8,10-11"; empty = no CPUs)
+pub fn parse_cpu_list(text: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for part in text.trim().split(',').filter(|part| !part.is_empty()) {
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid CPU list entry '{}'", part))
+        };
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("Invalid CPU range '{}'", part));
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    Ok(cpus)
+}
+
+/// CPUs the calling thread may run on
+#[cfg(target_os = "linux")]
+pub fn current_affinity() -> Result<Vec<usize>, String> {
+    // SAFETY: cpu_set_t is plain data; zeroed is the empty set
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    // SAFETY: pid 0 = calling thread, set is a valid cpu_set_t of the given size
+    let rc =
+        unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) };
+    if rc != 0 {
+        return Err(format!(
+            "sched_getaffinity failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok((0..libc::CPU_SETSIZE as usize)
+        // SAFETY: cpu < CPU_SETSIZE and set is initialized
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect())
+}
+
+/// Restrict the calling thread to `cpus`
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(cpus: &[usize]) -> Result<(), String> {
+    if cpus.is_empty() {
+        return Err("empty CPU list".to_string());
+    }
+    // SAFETY: cpu_set_t is plain data; zeroed is the empty set
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        if cpu >= MAX_CPUS {
+            return Err(format!("CPU {} out of range", cpu));
+        }
+        // SAFETY: cpu < CPU_SETSIZE checked above
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    // SAFETY: pid 0 = calling thread, set is a valid cpu_set_t of the given size
+    let rc = unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+    if rc != 0 {
+        return Err(format!(
+            "sched_setaffinity({:?}) failed: {}",
+            cpus,
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+/// Run the calling thread under SCHED_FIFO at `priority` (1..=99);
+/// needs CAP_SYS_NICE or an RLIMIT_RTPRIO allowance
+#[cfg(target_os = "linux")]
+pub fn set_fifo_priority(priority: u8) -> Result<(), String> {
+    let param = libc::sched_param {
+        sched_priority: priority as libc::c_int,
+    };
+    // SAFETY: pthread_self is the calling thread, param is a valid sched_param
+    let rc = unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) };
+    if rc != 0 {
+        return Err(format!(
+            "SCHED_FIFO priority {} failed: {}",
+            priority,
+            std::io::Error::from_raw_os_error(rc)
+        ));
+    }
+    Ok(())
+}
+
+/// Lock all current and future pages of the process in RAM so the hot
+/// path never takes a major page fault
+#[cfg(target_os = "linux")]
+pub fn lock_memory() -> Result<(), String> {
+    // SAFETY: mlockall takes only flags and has no memory-safety preconditions
+    let rc = unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) };
+    if rc != 0 {
+        return Err(format!(
+            "mlockall failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+/// CPUs the calling thread may run on
+#[cfg(not(target_os = "linux"))]
+pub fn current_affinity() -> Result<Vec<usize>, String> {
+    Err("CPU affinity is only supported on Linux".to_string())
+}
+
+/// Restrict the calling thread to `cpus`
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_cpus: &[usize]) -> Result<(), String> {
+    Err("CPU affinity is only supported on Linux".to_string())
+}
+
+/// Run the calling thread under SCHED_FIFO at `priority`
+#[cfg(not(target_os = "linux"))]
+pub fn set_fifo_priority(_priority: u8) -> Result<(), String> {
+    Err("SCHED_FIFO is only supported on Linux".to_string())
+}
+
+/// Lock all current and future pages of the process in RAM
+#[cfg(not(target_os = "linux"))]
+pub fn lock_memory() -> Result<(), String> {
+    Err("mlockall is only supported on Linux".to_string())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_current_thread() {
+        std::thread::spawn(|| {
+            let allowed = current_affinity().unwrap();
+            assert!(!allowed.is_empty());
+
+            pin_current_thread(&allowed[..1]).unwrap();
+            assert_eq!(current_affinity().unwrap(), vec![allowed[0]]);
+
+            assert!(pin_current_thread(&[]).is_err());
+            assert!(pin_current_thread(&[MAX_CPUS]).is_err());
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_place_current_thread_reports() {
+        std::thread::spawn(|| {
+            let allowed = current_affinity().unwrap();
+
+            let report = place_current_thread("test", &allowed[..1], None);
+            assert!(report.honoured(), "{}", report);
+            assert!(report.errors.is_empty());
+            assert_eq!(report.effective, vec![allowed[0]]);
+
+            // Failed pinning keeps the previous mask and says so
+            let report = place_current_thread("test", &[MAX_CPUS], None);
+            assert!(!report.honoured());
+            assert_eq!(report.effective, vec![allowed[0]]);
+            assert_eq!(report.errors.len(), 1);
+            assert!(report.to_string().contains("NOT honoured"));
+
+            let unpinned = place_current_thread("test", &[], None);
+            assert!(unpinned.honoured());
+            assert!(unpinned.to_string().contains("SCHED_OTHER"));
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11\n").unwrap(),
+            vec![0, 1, 2, 3, 8, 10, 11]
+        );
+        assert_eq!(parse_cpu_list("\n").unwrap(), Vec::<usize>::new());
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+    }
+}
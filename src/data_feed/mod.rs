@@ -1,8 +1,15 @@
-use crate::sbe_decoder_ffi::SbeDecoderFfi;
+use crate::metrics::{monotonic_ns, DecodeErrorCounters};
+use crate::sbe_decoder_ffi::DecodeError;
+#[cfg(not(feature = "rust-sbe"))]
+use crate::sbe_decoder_ffi::SbeDecoderFfi as SbeDecoder;
+#[cfg(feature = "rust-sbe")]
+use crate::sbe_decoder_rust::SbeDecoderRust as SbeDecoder;
 use crossbeam_channel::{bounded, Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 /// Trade tick data structure with zero-allocation design
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TradeTick {
     /// Symbol ID (integer representation)
     pub symbol_id: u32,
@@ -10,6 +17,10 @@ pub struct TradeTick {
     pub px_e8: u64,
     /// Unix timestamp in milliseconds
     pub ts_unix_ms: u64,
+    /// `metrics::monotonic_ns` when the frame/message was received (0 = unknown)
+    pub recv_ns: u64,
+    /// `metrics::monotonic_ns` when decoding finished (0 = unknown)
+    pub decoded_ns: u64,
 }
 
 impl TradeTick {
@@ -19,6 +30,8 @@ impl TradeTick {
             symbol_id,
             px_e8,
             ts_unix_ms,
+            recv_ns: 0,
+            decoded_ns: 0,
         }
     }
 
@@ -37,11 +50,33 @@ pub struct TickGenerator {
     base_prices: Vec<u64>,
 }
 
+/// Ticks decoded per batch in `DataFeed::decode_and_send`
+const SEND_BATCH: usize = 64;
+
+/// What `DataFeed` does when the decoder reports a malformed frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeErrorPolicy {
+    /// Count the error, drop the bad frame and keep decoding
+    #[default]
+    SkipAndContinue,
+    /// Count the error and stop decoding until `resume` is called
+    Halt,
+}
+
 /// Data feed manager that can use SBE decoder or synthetic data
 pub struct DataFeed {
-    decoder: Option<SbeDecoderFfi>,
+    decoder: Option<SbeDecoder>,
     tx: Option<Sender<TradeTick>>,
     rx: Option<Receiver<TradeTick>>,
+    /// Reaction to decode errors
+    error_policy: DecodeErrorPolicy,
+    /// Decode error counters (shared with metrics)
+    decode_errors: Arc<DecodeErrorCounters>,
+    /// Error that halted the feed under `DecodeErrorPolicy::Halt`
+    halted_on: Option<DecodeError>,
+    /// `monotonic_ns` of the last `feed_bytes` call, stamped as `recv_ns`
+    last_feed_ns: u64,
 }
 
 impl DataFeed {
@@ -49,7 +84,7 @@ impl DataFeed {
     pub fn new(use_sbe_decoder: bool, channel_capacity: usize) -> Self {
         let (tx, rx) = bounded(channel_capacity);
         let decoder = if use_sbe_decoder {
-            Some(SbeDecoderFfi::new())
+            Some(SbeDecoder::new())
         } else {
             None
         };
@@ -58,31 +93,106 @@ impl DataFeed {
             decoder,
             tx: Some(tx),
             rx: Some(rx),
+            error_policy: DecodeErrorPolicy::default(),
+            decode_errors: Arc::new(DecodeErrorCounters::default()),
+            halted_on: None,
+            last_feed_ns: 0,
         }
     }
 
+    /// Set the reaction to decode errors
+    pub fn set_error_policy(&mut self, policy: DecodeErrorPolicy) {
+        self.error_policy = policy;
+    }
+
+    /// Count decode errors into shared counters (e.g. `MetricsCollector::decode_errors`)
+    pub fn set_error_counters(&mut self, counters: Arc<DecodeErrorCounters>) {
+        self.decode_errors = counters;
+    }
+
+    /// Decode error counters in use
+    #[cfg(test)]
+    pub fn error_counters(&self) -> Arc<DecodeErrorCounters> {
+        Arc::clone(&self.decode_errors)
+    }
+
+    /// Error that halted the feed, if any
+    pub fn halted_on(&self) -> Option<DecodeError> {
+        self.halted_on
+    }
+
+    /// Resume decoding after a halt
+    #[cfg(test)]
+    pub fn resume(&mut self) {
+        self.halted_on = None;
+    }
+
     /// Get the receiver end of the channel
     pub fn get_receiver(&mut self) -> Option<Receiver<TradeTick>> {
         self.rx.take()
     }
 
+    /// Get the sending end of the channel for an external producer
+    /// (e.g. the WebSocket ingester); `decode_and_send` is a no-op afterwards
+    pub fn get_sender(&mut self) -> Option<Sender<TradeTick>> {
+        self.tx.take()
+    }
+
+    /// Append raw SBE bytes to the decoder's buffer
+    pub fn feed_bytes(&mut self, bytes: &[u8]) {
+        self.feed_bytes_at(bytes, monotonic_ns());
+    }
+
+    /// Append raw SBE bytes received at `recv_ns` (e.g. by a shard router)
+    pub fn feed_bytes_at(&mut self, bytes: &[u8], recv_ns: u64) {
+        self.last_feed_ns = recv_ns;
+        if let Some(decoder) = &mut self.decoder {
+            decoder.feed(bytes);
+        }
+    }
+
     /// Decode and send ticks through the channel
-    /// Returns number of ticks decoded
+    /// Returns number of ticks decoded; decode errors are counted and
+    /// handled according to the feed's `DecodeErrorPolicy`. Ticks are stamped
+    /// with the last `feed_bytes` time and the time their batch was decoded.
     pub fn decode_and_send(&mut self, max_ticks: usize) -> usize {
+        if self.halted_on.is_some() {
+            return 0;
+        }
+
         if let (Some(decoder), Some(tx)) = (&mut self.decoder, &self.tx) {
             let mut count = 0;
-            let mut tick = TradeTick::new(0, 0, 0);
+            let mut batch = [TradeTick::new(0, 0, 0); SEND_BATCH];
 
             while count < max_ticks {
-                if decoder.decode_into(&mut tick) {
-                    if tx.send(tick).is_err() {
-                        // Channel closed
+                let want = (max_ticks - count).min(SEND_BATCH);
+                let decoded = match decoder.decode_many(&mut batch[..want]) {
+                    Ok(0) => {
+                        // No more data
                         break;
                     }
+                    Ok(decoded) => decoded,
+                    Err(error) => {
+                        self.decode_errors.record(error);
+                        match self.error_policy {
+                            DecodeErrorPolicy::SkipAndContinue => continue,
+                            DecodeErrorPolicy::Halt => {
+                                self.halted_on = Some(error);
+                                break;
+                            }
+                        }
+                    }
+                };
+
+                let decoded_ns = monotonic_ns();
+                for tick in &mut batch[..decoded] {
+                    tick.recv_ns = self.last_feed_ns;
+                    tick.decoded_ns = decoded_ns;
+                    if tx.send(*tick).is_err() {
+                        // Channel closed
+                        return count;
+                    }
                     count += 1;
-                } else {
-                    // No more data
-                    break;
                 }
             }
 
@@ -93,8 +203,6 @@ impl DataFeed {
     }
 }
 
-/// Synthetic tick generator for benchmarking
-
 impl TickGenerator {
     /// Create a new tick generator
     pub fn new(num_symbols: u32, num_ticks: usize) -> Self {
@@ -145,11 +253,17 @@ impl TickGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sbe_decoder_ffi::fixtures::{
+        TRADE_GROUP, TRADE_SINGLE, TRADE_STREAM, TRADE_UNKNOWN_TEMPLATE,
+    };
 
     #[test]
     fn test_data_feed_with_sbe_decoder() {
         let mut feed = DataFeed::new(true, 100);
         let rx = feed.get_receiver().unwrap();
+        for _ in 0..4 {
+            feed.feed_bytes(TRADE_GROUP);
+        }
 
         // Decode some ticks in the background
         let decoded = feed.decode_and_send(10);
@@ -163,14 +277,27 @@ mod tests {
         assert_eq!(received, 10);
     }
 
+    #[test]
+    fn test_data_feed_stamps_ticks() {
+        let mut feed = DataFeed::new(true, 10);
+        let rx = feed.get_receiver().unwrap();
+        feed.feed_bytes(TRADE_SINGLE);
+        feed.decode_and_send(10);
+
+        let tick = rx.try_recv().unwrap();
+        assert!(tick.recv_ns > 0);
+        assert!(tick.decoded_ns >= tick.recv_ns);
+    }
+
     #[test]
     fn test_data_feed_channel() {
         let mut feed = DataFeed::new(true, 50);
         let rx = feed.get_receiver().unwrap();
+        feed.feed_bytes(TRADE_STREAM);
 
         // Send ticks through the channel
         let decoded = feed.decode_and_send(5);
-        assert!(decoded > 0);
+        assert_eq!(decoded, 2);
 
         // Verify we can receive
         let mut count = 0;
@@ -179,4 +306,68 @@ mod tests {
         }
         assert_eq!(count, decoded);
     }
+
+    #[test]
+    fn test_data_feed_without_bytes() {
+        let mut feed = DataFeed::new(true, 10);
+        assert_eq!(feed.decode_and_send(10), 0);
+    }
+
+    #[test]
+    fn test_data_feed_skips_decode_errors() {
+        let mut feed = DataFeed::new(true, 10);
+        let rx = feed.get_receiver().unwrap();
+        feed.feed_bytes(TRADE_SINGLE);
+        feed.feed_bytes(TRADE_UNKNOWN_TEMPLATE);
+
+        assert_eq!(feed.decode_and_send(10), 1);
+        assert_eq!(feed.error_counters().summary().unknown_template, 1);
+        assert_eq!(feed.halted_on(), None);
+
+        // Next frame decodes normally
+        feed.feed_bytes(TRADE_GROUP);
+        assert_eq!(feed.decode_and_send(10), 3);
+        assert_eq!(rx.try_iter().count(), 4);
+    }
+
+    #[test]
+    fn test_data_feed_keeps_frames_queued_behind_a_bad_one() {
+        let mut feed = DataFeed::new(true, 10);
+        let rx = feed.get_receiver().unwrap();
+        let mut bad_exponent = TRADE_SINGLE.to_vec();
+        bad_exponent[24] = 9; // price exponent outside [-8, 8]
+
+        // Every frame is buffered before the first decode
+        feed.feed_bytes(TRADE_SINGLE);
+        feed.feed_bytes(TRADE_UNKNOWN_TEMPLATE);
+        feed.feed_bytes(&bad_exponent);
+        feed.feed_bytes(TRADE_GROUP);
+
+        assert_eq!(feed.decode_and_send(10), 4);
+        let summary = feed.error_counters().summary();
+        assert_eq!((summary.unknown_template, summary.invalid_price), (1, 1));
+        assert_eq!(
+            rx.try_iter().map(|tick| tick.symbol_id).collect::<Vec<_>>(),
+            vec![7, 42, 42, 42]
+        );
+    }
+
+    #[test]
+    fn test_data_feed_halts_on_decode_error() {
+        let mut feed = DataFeed::new(true, 10);
+        let _rx = feed.get_receiver().unwrap();
+        feed.set_error_policy(DecodeErrorPolicy::Halt);
+        feed.feed_bytes(TRADE_SINGLE);
+        feed.feed_bytes(TRADE_UNKNOWN_TEMPLATE);
+
+        assert_eq!(feed.decode_and_send(10), 1);
+        assert_eq!(feed.halted_on(), Some(DecodeError::UnknownTemplate));
+
+        // Stays halted until resumed
+        feed.feed_bytes(TRADE_GROUP);
+        assert_eq!(feed.decode_and_send(10), 0);
+        feed.resume();
+        assert_eq!(feed.decode_and_send(10), 3);
+        assert_eq!(feed.error_counters().summary().total(), 1);
+    }
 }
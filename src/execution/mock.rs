@@ -0,0 +1,147 @@
+//! In-process mock exchange for tests
+//!
+//! MARKET and IOC orders fill immediately at the last trade price passed to
+//! `on_trade`; GTC LIMIT orders rest until a later trade crosses them.
+
+use super::{mul_div, ExchangeClient, ExecutionReport, Order, OrderKind, OrderStatus, Side, E8};
+
+/// Mock exchange matching orders against the last trade price per symbol
+pub struct MockExchange {
+    /// Last trade price by symbol_id (0 = no trade yet)
+    last_px_e8: Vec<u64>,
+    /// Resting GTC LIMIT orders
+    resting: Vec<Order>,
+    /// Every order received, with its request payload
+    received: Vec<(Order, String)>,
+    /// Error returned by the next `submit`
+    reject_next: Option<String>,
+    /// Report every SELL as Rejected (e.g. a price filter)
+    reject_sells: bool,
+}
+
+impl MockExchange {
+    /// Create a mock exchange for symbol ids below `max_symbols`
+    pub fn new(max_symbols: usize) -> Self {
+        Self {
+            last_px_e8: vec![0; max_symbols],
+            resting: Vec::new(),
+            received: Vec::new(),
+            reject_next: None,
+            reject_sells: false,
+        }
+    }
+
+    /// Orders received so far, with their request payloads
+    pub fn received(&self) -> &[(Order, String)] {
+        &self.received
+    }
+
+    /// Fail the next `submit` with `reason` (e.g. a transport error)
+    pub fn reject_next(&mut self, reason: &str) {
+        self.reject_next = Some(reason.to_string());
+    }
+
+    /// Reject SELLs until switched off again
+    pub fn reject_sells(&mut self, reject: bool) {
+        self.reject_sells = reject;
+    }
+
+    /// Cancel a resting order; its report, or None if nothing rests under
+    /// `client_order_id`
+    pub fn cancel(&mut self, client_order_id: u64) -> Option<ExecutionReport> {
+        let index = self
+            .resting
+            .iter()
+            .position(|order| order.client_order_id == client_order_id)?;
+        let mut report = fill(&self.resting.remove(index), 0, 0);
+        report.status = OrderStatus::Canceled;
+        Some(report)
+    }
+
+    /// Record a trade and fill the resting orders it crosses
+    pub fn on_trade(&mut self, symbol_id: u32, px_e8: u64) -> Vec<ExecutionReport> {
+        if let Some(last) = self.last_px_e8.get_mut(symbol_id as usize) {
+            *last = px_e8;
+        }
+
+        let mut fills = Vec::new();
+        self.resting.retain(|order| {
+            let crossed = order.symbol_id == symbol_id
+                && match order.side {
+                    Side::Buy => px_e8 <= order.px_e8,
+                    Side::Sell => px_e8 >= order.px_e8,
+                };
+            if crossed {
+                fills.push(fill(order, order.qty_e8, order.px_e8));
+            }
+            !crossed
+        });
+        fills
+    }
+}
+
+/// Report for `order` filled with `qty_e8` at `px_e8`
+fn fill(order: &Order, qty_e8: u64, px_e8: u64) -> ExecutionReport {
+    ExecutionReport {
+        client_order_id: order.client_order_id,
+        symbol_id: order.symbol_id,
+        side: order.side,
+        status: if qty_e8 > 0 {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::Expired
+        },
+        executed_qty_e8: qty_e8,
+        cum_quote_e8: mul_div(qty_e8, px_e8, E8),
+    }
+}
+
+impl ExchangeClient for MockExchange {
+    fn submit(
+        &mut self,
+        order: &Order,
+        payload: &[u8],
+        on_sent: &mut dyn FnMut(),
+    ) -> Result<ExecutionReport, String> {
+        if let Some(reason) = self.reject_next.take() {
+            return Err(reason);
+        }
+        self.received
+            .push((*order, String::from_utf8_lossy(payload).into_owned()));
+        on_sent();
+
+        let last_px_e8 = self
+            .last_px_e8
+            .get(order.symbol_id as usize)
+            .copied()
+            .unwrap_or(0);
+        let mut report = fill(order, 0, 0);
+        if last_px_e8 == 0 || (self.reject_sells && order.side == Side::Sell) {
+            report.status = OrderStatus::Rejected;
+            return Ok(report);
+        }
+
+        let crosses = match order.side {
+            Side::Buy => last_px_e8 <= order.px_e8,
+            Side::Sell => last_px_e8 >= order.px_e8,
+        };
+        match order.kind {
+            OrderKind::Market => {
+                let qty_e8 = if order.quote_qty_e8 > 0 {
+                    mul_div(order.quote_qty_e8, E8, last_px_e8)
+                } else {
+                    order.qty_e8
+                };
+                Ok(fill(order, qty_e8, last_px_e8))
+            }
+            OrderKind::LimitIoc if crosses => Ok(fill(order, order.qty_e8, last_px_e8)),
+            OrderKind::LimitIoc => Ok(report),
+            OrderKind::LimitGtc if crosses => Ok(fill(order, order.qty_e8, last_px_e8)),
+            OrderKind::LimitGtc => {
+                self.resting.push(*order);
+                report.status = OrderStatus::New;
+                Ok(report)
+            }
+        }
+    }
+}
@@ -0,0 +1,1134 @@
+//! Order execution: trigger -> BUY -> LIMIT take-profit
+//!
+//! Orders are plain `Copy` structs and requests are encoded into a buffer
+//! owned by the engine, so sending an order does not allocate.
+
+use crate::config::Config;
+use crate::hotpath::TriggerEvent;
+use crate::metrics::{Stage, StageTimeline};
+use crate::risk::{Risk, SlotReservation};
+use crate::symbols::{SymbolInfo, SymbolRegistry};
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+
+#[cfg(test)]
+pub mod mock;
+pub mod shadow;
+
+/// Fixed-point scale of prices and quantities
+pub const E8: u64 = 100_000_000;
+
+/// Initial capacity of the request payload buffer (no request comes close)
+const PAYLOAD_CAPACITY: usize = 512;
+
+/// Order side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// Order type and time in force
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderKind {
+    /// MARKET; a BUY is sized in quote asset (`quote_qty_e8`)
+    Market,
+    /// LIMIT IOC: fill at `px_e8` or better, cancel the rest
+    LimitIoc,
+    /// LIMIT GTC: rest on the book until filled or canceled
+    LimitGtc,
+}
+
+/// Order as handed to the exchange client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Order {
+    pub client_order_id: u64,
+    pub symbol_id: u32,
+    pub side: Side,
+    pub kind: OrderKind,
+    /// Limit price (0 for MARKET)
+    pub px_e8: u64,
+    /// Base quantity (0 for a quote-sized MARKET BUY)
+    pub qty_e8: u64,
+    /// Quote amount for a MARKET BUY (0 otherwise)
+    pub quote_qty_e8: u64,
+}
+
+/// Exchange order status
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Expired,
+    Rejected,
+}
+
+impl OrderStatus {
+    /// Whether the order can no longer fill
+    pub fn is_final(self) -> bool {
+        !matches!(self, OrderStatus::New | OrderStatus::PartiallyFilled)
+    }
+}
+
+/// Exchange response or update for one order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionReport {
+    pub client_order_id: u64,
+    pub symbol_id: u32,
+    pub side: Side,
+    pub status: OrderStatus,
+    /// Cumulative filled base quantity
+    pub executed_qty_e8: u64,
+    /// Cumulative filled quote amount
+    pub cum_quote_e8: u64,
+}
+
+impl ExecutionReport {
+    /// Average fill price (0 if nothing filled)
+    pub fn avg_px_e8(&self) -> u64 {
+        if self.executed_qty_e8 == 0 {
+            return 0;
+        }
+        mul_div(self.cum_quote_e8, E8, self.executed_qty_e8)
+    }
+}
+
+/// Transport to an exchange (REST/WebSocket API, mock, simulator)
+pub trait ExchangeClient {
+    /// Send `order`, whose encoded request is `payload`, and return the
+    /// exchange's immediate response. `on_sent` is called once the request
+    /// is written, before waiting for the response (not if sending failed).
+    fn submit(
+        &mut self,
+        order: &Order,
+        payload: &[u8],
+        on_sent: &mut dyn FnMut(),
+    ) -> Result<ExecutionReport, String>;
+}
+
+/// Why a trigger or report could not be executed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecError {
+    /// Symbol id not in the registry
+    UnknownSymbol(u32),
+    /// Symbol is not in TRADING status
+    NotTrading(u32),
+    /// A BUY or take-profit is already working for the symbol
+    PositionOpen(u32),
+    /// Configured BUY size is below the symbol's minimum notional or step
+    BelowMinimum(u32),
+    /// The BUY could not be sent
+    Exchange(String),
+    /// The BUY filled but no take-profit could be placed; the position is
+    /// left unprotected until `flatten` sells it
+    TakeProfitFailed {
+        buy: ExecutionReport,
+        reason: String,
+    },
+    /// The MARKET SELL flattening a position could not be sent
+    FlattenFailed { symbol_id: u32, reason: String },
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::UnknownSymbol(id) => write!(f, "unknown symbol #{}", id),
+            ExecError::NotTrading(id) => write!(f, "symbol #{} is not trading", id),
+            ExecError::PositionOpen(id) => write!(f, "symbol #{} already has a working order", id),
+            ExecError::BelowMinimum(id) => {
+                write!(f, "BUY size is below the minimum for symbol #{}", id)
+            }
+            ExecError::Exchange(reason) => write!(f, "BUY failed: {}", reason),
+            ExecError::TakeProfitFailed { buy, reason } => write!(
+                f,
+                "take-profit for symbol #{} not placed after BUY fill: {}",
+                buy.symbol_id, reason
+            ),
+            ExecError::FlattenFailed { symbol_id, reason } => {
+                write!(
+                    f,
+                    "flatten SELL for symbol #{} failed: {}",
+                    symbol_id, reason
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+/// What the engine did in response to a trigger or report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionEvent {
+    /// BUY sent, fill not reported yet
+    BuyPending(Order),
+    /// BUY ended without any fill (IOC expired, rejected)
+    BuyUnfilled(ExecutionReport),
+    /// BUY filled and the take-profit LIMIT SELL is resting
+    TakeProfitPlaced {
+        buy: ExecutionReport,
+        take_profit: Order,
+        /// Stages from the trigger's tick through LIMIT sent
+        timeline: StageTimeline,
+    },
+    /// BUY filled and its take-profit filled as soon as it was sent; the
+    /// symbol and its position slot are free again
+    TakeProfitFilled {
+        buy: ExecutionReport,
+        take_profit: ExecutionReport,
+        /// Stages from the trigger's tick through LIMIT sent
+        timeline: StageTimeline,
+    },
+    /// Take-profit ended with nothing sellable left (filled); the symbol and
+    /// its position slot are free again
+    TakeProfitDone(ExecutionReport),
+    /// A SELL ended with part of the position unsold and no take-profit
+    /// working; the position keeps its slot until `flatten` sells it
+    Unprotected(ExecutionReport),
+    /// MARKET SELL of an unprotected position sent, fill not reported yet
+    FlattenPending(Order),
+    /// Unprotected position sold; the symbol and its position slot are free
+    /// again
+    Flattened(ExecutionReport),
+}
+
+/// Per-symbol order state; working orders keep their position slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolState {
+    Idle,
+    /// BUY awaiting its fill, with the stages reached so far
+    BuyPending(Order, StageTimeline, SlotReservation),
+    TakeProfitOpen(Order, SlotReservation),
+    /// Position without a working SELL
+    Unprotected(SlotReservation),
+    /// MARKET SELL of an unprotected position awaiting its fill
+    Flattening(Order, SlotReservation),
+}
+
+/// Encode `order` as a Binance `POST /api/v3/order` query string into `out`
+/// (signature and timestamp are appended by the live client)
+pub fn encode_order(out: &mut Vec<u8>, order: &Order, symbol: &str) {
+    out.clear();
+    let side = match order.side {
+        Side::Buy => "BUY",
+        Side::Sell => "SELL",
+    };
+    // Writes into a Vec cannot fail
+    let _ = write!(out, "symbol={}&side={}", symbol, side);
+    match order.kind {
+        OrderKind::Market => {
+            let _ = write!(out, "&type=MARKET");
+            if order.quote_qty_e8 > 0 {
+                out.extend_from_slice(b"&quoteOrderQty=");
+                write_e8(out, order.quote_qty_e8);
+            } else {
+                out.extend_from_slice(b"&quantity=");
+                write_e8(out, order.qty_e8);
+            }
+        }
+        OrderKind::LimitIoc | OrderKind::LimitGtc => {
+            let tif = if order.kind == OrderKind::LimitIoc {
+                "IOC"
+            } else {
+                "GTC"
+            };
+            let _ = write!(out, "&type=LIMIT&timeInForce={}&quantity=", tif);
+            write_e8(out, order.qty_e8);
+            out.extend_from_slice(b"&price=");
+            write_e8(out, order.px_e8);
+        }
+    }
+    let _ = write!(
+        out,
+        "&newClientOrderId=ufa-{}&newOrderRespType=FULL",
+        order.client_order_id
+    );
+}
+
+fn write_e8(out: &mut Vec<u8>, value_e8: u64) {
+    let _ = write!(out, "{}.{:08}", value_e8 / E8, value_e8 % E8);
+}
+
+/// `a * b / c` without intermediate overflow
+pub fn mul_div(a: u64, b: u64, c: u64) -> u64 {
+    (a as u128 * b as u128 / c as u128) as u64
+}
+
+/// Percentage as basis points
+fn pct_to_bps(pct: f64) -> u64 {
+    (pct * 100.0).round().max(0.0) as u64
+}
+
+/// Turns triggers into a BUY followed by a LIMIT SELL take-profit
+pub struct ExecutionEngine<C: ExchangeClient> {
+    client: C,
+    /// Tick/step sizes and exchange symbol names
+    registry: Arc<SymbolRegistry>,
+    /// Take-profit distance above the average BUY fill
+    take_profit_bps: u64,
+    /// LIMIT IOC cap above the trigger price (None = MARKET BUY)
+    ioc_slippage_bps: Option<u64>,
+    /// Position slots released when a position ends (the hot path
+    /// reserves them)
+    risk: Arc<Risk>,
+    next_client_order_id: u64,
+    /// Reused request payload buffer
+    payload: Vec<u8>,
+    /// Indexed by symbol_id, sized for `max_symbols` up front
+    states: Vec<SymbolState>,
+}
+
+impl<C: ExchangeClient> ExecutionEngine<C> {
+    /// Create an engine sending orders through `client` and releasing the
+    /// triggers' slots back to `risk`
+    pub fn new(client: C, registry: Arc<SymbolRegistry>, risk: Arc<Risk>, config: &Config) -> Self {
+        Self {
+            client,
+            registry,
+            take_profit_bps: pct_to_bps(config.take_profit_pct),
+            ioc_slippage_bps: config.buy_ioc_slippage_pct.map(pct_to_bps),
+            risk,
+            next_client_order_id: 1,
+            payload: Vec::with_capacity(PAYLOAD_CAPACITY),
+            states: vec![SymbolState::Idle; config.max_symbols],
+        }
+    }
+
+    /// Risk state shared with the hot path
+    pub fn risk(&self) -> &Arc<Risk> {
+        &self.risk
+    }
+
+    /// Exchange client
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Exchange client (e.g. to feed trades into a simulator)
+    pub fn client_mut(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    /// Resting take-profit for a symbol
+    #[cfg(test)]
+    pub fn open_take_profit(&self, symbol_id: u32) -> Option<Order> {
+        match self.states.get(symbol_id as usize) {
+            Some(SymbolState::TakeProfitOpen(order, _)) => Some(*order),
+            _ => None,
+        }
+    }
+
+    /// Whether `symbol_id` holds a position without a working SELL
+    pub fn is_unprotected(&self, symbol_id: u32) -> bool {
+        matches!(
+            self.states.get(symbol_id as usize),
+            Some(SymbolState::Unprotected(_))
+        )
+    }
+
+    /// `on_trigger_timed` without stage timing (tests)
+    #[cfg(test)]
+    pub fn on_trigger(&mut self, trigger: &TriggerEvent) -> Result<ExecutionEvent, ExecError> {
+        self.on_trigger_timed(trigger, StageTimeline::default())
+    }
+
+    /// Send a BUY for `trigger`; places the take-profit right away if the
+    /// exchange reports the fill in its response. The BUY and LIMIT stages
+    /// are marked on `timeline` (which carries the trigger tick's earlier
+    /// stages); the completed timeline is
+    /// returned with `ExecutionEvent::TakeProfitPlaced` or
+    /// `ExecutionEvent::TakeProfitFilled`. The trigger's slot
+    /// is released unless a position was opened.
+    pub fn on_trigger_timed(
+        &mut self,
+        trigger: &TriggerEvent,
+        timeline: StageTimeline,
+    ) -> Result<ExecutionEvent, ExecError> {
+        let result = self.send_buy(trigger, timeline);
+        match &result {
+            // The BUY filled: the slot went with the position
+            Ok(_) | Err(ExecError::TakeProfitFailed { .. }) => {}
+            Err(_) => {
+                self.risk.slots.release(&trigger.slot);
+            }
+        }
+        result
+    }
+
+    fn send_buy(
+        &mut self,
+        trigger: &TriggerEvent,
+        mut timeline: StageTimeline,
+    ) -> Result<ExecutionEvent, ExecError> {
+        let symbol_id = trigger.symbol_id;
+        match self.states.get(symbol_id as usize) {
+            None => return Err(ExecError::UnknownSymbol(symbol_id)),
+            Some(SymbolState::Idle) => {}
+            Some(_) => return Err(ExecError::PositionOpen(symbol_id)),
+        }
+        let info = self
+            .registry
+            .info(symbol_id)
+            .ok_or(ExecError::UnknownSymbol(symbol_id))?;
+        if !info.is_trading() {
+            return Err(ExecError::NotTrading(symbol_id));
+        }
+        let allocation = trigger.slot.quote_qty_e8;
+        let quote_qty_e8 = trigger
+            .max_position_quote_e8
+            .map_or(allocation, |cap| cap.min(allocation));
+        if quote_qty_e8 < info.min_notional_e8 {
+            return Err(ExecError::BelowMinimum(symbol_id));
+        }
+
+        let mut buy = Order {
+            client_order_id: 0,
+            symbol_id,
+            side: Side::Buy,
+            kind: OrderKind::Market,
+            px_e8: 0,
+            qty_e8: 0,
+            quote_qty_e8,
+        };
+        if let Some(bps) = self.ioc_slippage_bps {
+            let px_e8 = info.round_price_down(mul_div(trigger.price_e8, 10_000 + bps, 10_000));
+            let qty_e8 = if px_e8 == 0 {
+                0
+            } else {
+                info.round_qty_down(mul_div(quote_qty_e8, E8, px_e8))
+            };
+            if qty_e8 == 0 {
+                return Err(ExecError::BelowMinimum(symbol_id));
+            }
+            buy.kind = OrderKind::LimitIoc;
+            buy.px_e8 = px_e8;
+            buy.qty_e8 = qty_e8;
+            buy.quote_qty_e8 = 0;
+        }
+
+        let report = self
+            .submit(&mut buy, &info, &mut timeline)
+            .map_err(ExecError::Exchange)?;
+        self.states[symbol_id as usize] = SymbolState::BuyPending(buy, timeline, trigger.slot);
+        self.on_buy_report(buy, timeline, trigger.slot, &report, &info)
+    }
+
+    /// Apply an asynchronous order update (fills arriving after the response)
+    /// Returns None for reports that do not change any working order
+    pub fn on_report(
+        &mut self,
+        report: &ExecutionReport,
+    ) -> Result<Option<ExecutionEvent>, ExecError> {
+        let Some(state) = self.states.get(report.symbol_id as usize).copied() else {
+            return Ok(None);
+        };
+
+        match state {
+            SymbolState::BuyPending(buy, timeline, slot)
+                if buy.client_order_id == report.client_order_id =>
+            {
+                let info = self
+                    .registry
+                    .info(report.symbol_id)
+                    .ok_or(ExecError::UnknownSymbol(report.symbol_id))?;
+                self.on_buy_report(buy, timeline, slot, report, &info)
+                    .map(Some)
+            }
+            SymbolState::TakeProfitOpen(tp, slot)
+                if tp.client_order_id == report.client_order_id =>
+            {
+                self.risk.book.on_take_profit_report(report);
+                if !report.status.is_final() {
+                    return Ok(None);
+                }
+                // Canceled or expired with quantity left: still exposed
+                let info = self
+                    .registry
+                    .info(report.symbol_id)
+                    .ok_or(ExecError::UnknownSymbol(report.symbol_id))?;
+                Ok(Some(if self.settle(slot, &info) {
+                    ExecutionEvent::TakeProfitDone(*report)
+                } else {
+                    ExecutionEvent::Unprotected(*report)
+                }))
+            }
+            SymbolState::Flattening(sell, slot)
+                if sell.client_order_id == report.client_order_id =>
+            {
+                self.on_flatten_report(sell, slot, report).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Sell an unprotected position at the market; None if `symbol_id` has
+    /// no unprotected position. The slot is released once nothing sellable
+    /// is left.
+    pub fn flatten(&mut self, symbol_id: u32) -> Result<Option<ExecutionEvent>, ExecError> {
+        let Some(&SymbolState::Unprotected(slot)) = self.states.get(symbol_id as usize) else {
+            return Ok(None);
+        };
+        let info = self
+            .registry
+            .info(symbol_id)
+            .ok_or(ExecError::UnknownSymbol(symbol_id))?;
+        let mut sell = Order {
+            client_order_id: 0,
+            symbol_id,
+            side: Side::Sell,
+            kind: OrderKind::Market,
+            px_e8: 0,
+            qty_e8: self.sellable_qty_e8(symbol_id, &info),
+            quote_qty_e8: 0,
+        };
+        let report = self
+            .submit(&mut sell, &info, &mut StageTimeline::default())
+            .map_err(|reason| ExecError::FlattenFailed { symbol_id, reason })?;
+        self.states[symbol_id as usize] = SymbolState::Flattening(sell, slot);
+        self.on_flatten_report(sell, slot, &report).map(Some)
+    }
+
+    fn on_flatten_report(
+        &mut self,
+        sell: Order,
+        slot: SlotReservation,
+        report: &ExecutionReport,
+    ) -> Result<ExecutionEvent, ExecError> {
+        if !report.status.is_final() {
+            return Ok(ExecutionEvent::FlattenPending(sell));
+        }
+        let info = self
+            .registry
+            .info(sell.symbol_id)
+            .ok_or(ExecError::UnknownSymbol(sell.symbol_id))?;
+        self.risk.book.on_sell_fill(report);
+        Ok(if self.settle(slot, &info) {
+            ExecutionEvent::Flattened(*report)
+        } else {
+            ExecutionEvent::Unprotected(*report)
+        })
+    }
+
+    /// With no SELL working for the slot's symbol: free the symbol and its
+    /// slot if the book holds nothing sellable, otherwise leave the position
+    /// unprotected with its slot. Returns whether the position ended.
+    fn settle(&mut self, slot: SlotReservation, info: &SymbolInfo) -> bool {
+        let symbol_id = slot.symbol_id;
+        if self.sellable_qty_e8(symbol_id, info) > 0 {
+            self.states[symbol_id as usize] = SymbolState::Unprotected(slot);
+            return false;
+        }
+        self.states[symbol_id as usize] = SymbolState::Idle;
+        self.risk.slots.release(&slot);
+        true
+    }
+
+    /// Held quantity of `symbol_id` that the step size lets us sell
+    fn sellable_qty_e8(&self, symbol_id: u32, info: &SymbolInfo) -> u64 {
+        let held_e8 = self.risk.book.position(symbol_id).map_or(0, |p| p.qty_e8);
+        info.round_qty_down(held_e8)
+    }
+
+    fn on_buy_report(
+        &mut self,
+        buy: Order,
+        mut timeline: StageTimeline,
+        slot: SlotReservation,
+        report: &ExecutionReport,
+        info: &SymbolInfo,
+    ) -> Result<ExecutionEvent, ExecError> {
+        let symbol_id = buy.symbol_id as usize;
+        if !report.status.is_final() {
+            return Ok(ExecutionEvent::BuyPending(buy));
+        }
+        self.states[symbol_id] = SymbolState::Idle;
+        if report.executed_qty_e8 == 0 {
+            self.risk.slots.release(&slot);
+            return Ok(ExecutionEvent::BuyUnfilled(*report));
+        }
+        self.risk.book.on_buy_fill(report);
+
+        let mut take_profit = Order {
+            client_order_id: 0,
+            symbol_id: buy.symbol_id,
+            side: Side::Sell,
+            kind: OrderKind::LimitGtc,
+            px_e8: info.round_price_up(mul_div(
+                report.avg_px_e8(),
+                10_000 + self.take_profit_bps,
+                10_000,
+            )),
+            // Everything sellable held, so dust left by earlier rounds goes too
+            qty_e8: self.sellable_qty_e8(buy.symbol_id, info),
+            quote_qty_e8: 0,
+        };
+        if take_profit.qty_e8 == 0 {
+            self.settle(slot, info);
+            return Err(ExecError::TakeProfitFailed {
+                buy: *report,
+                reason: "filled quantity is below the step size".to_string(),
+            });
+        }
+
+        let tp_report = match self.submit(&mut take_profit, info, &mut timeline) {
+            Ok(tp_report) => tp_report,
+            Err(reason) => {
+                self.settle(slot, info);
+                return Err(ExecError::TakeProfitFailed {
+                    buy: *report,
+                    reason,
+                });
+            }
+        };
+        match tp_report.status {
+            OrderStatus::Rejected | OrderStatus::Canceled | OrderStatus::Expired => {
+                self.risk.book.on_sell_fill(&tp_report);
+                self.settle(slot, info);
+                return Err(ExecError::TakeProfitFailed {
+                    buy: *report,
+                    reason: format!("take-profit {:?}", tp_report.status),
+                });
+            }
+            OrderStatus::Filled => {
+                self.risk.book.open_take_profit(&take_profit);
+                self.risk.book.on_take_profit_report(&tp_report);
+                return Ok(if self.settle(slot, info) {
+                    ExecutionEvent::TakeProfitFilled {
+                        buy: *report,
+                        take_profit: tp_report,
+                        timeline,
+                    }
+                } else {
+                    ExecutionEvent::Unprotected(tp_report)
+                });
+            }
+            OrderStatus::New | OrderStatus::PartiallyFilled => {
+                self.states[symbol_id] = SymbolState::TakeProfitOpen(take_profit, slot);
+            }
+        }
+        self.risk.book.open_take_profit(&take_profit);
+        self.risk.book.on_take_profit_report(&tp_report);
+
+        Ok(ExecutionEvent::TakeProfitPlaced {
+            buy: *report,
+            take_profit,
+            timeline,
+        })
+    }
+
+    /// Assign a client order id, encode and send, marking the BUY stages
+    /// (or LIMIT sent for a SELL) on `timeline`; the sent stage is marked by
+    /// the client when the request is out
+    fn submit(
+        &mut self,
+        order: &mut Order,
+        info: &SymbolInfo,
+        timeline: &mut StageTimeline,
+    ) -> Result<ExecutionReport, String> {
+        order.client_order_id = self.next_client_order_id;
+        self.next_client_order_id += 1;
+        encode_order(&mut self.payload, order, &info.symbol);
+
+        if order.side == Side::Sell {
+            return self.client.submit(order, &self.payload, &mut || {
+                timeline.mark(Stage::LimitSent)
+            });
+        }
+        timeline.mark(Stage::BuySerialized);
+        let report = self
+            .client
+            .submit(order, &self.payload, &mut || timeline.mark(Stage::BuySent));
+        timeline.mark(Stage::AckReceived);
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::MockExchange;
+    use super::*;
+
+    const EXCHANGE_INFO: &str = r#"{"symbols": [
+        {"symbol": "BTCUSDT", "status": "TRADING", "baseAsset": "BTC", "quoteAsset": "USDT",
+         "filters": [
+            {"filterType": "PRICE_FILTER", "tickSize": "0.01000000"},
+            {"filterType": "LOT_SIZE", "stepSize": "0.00001000"},
+            {"filterType": "NOTIONAL", "minNotional": "5.00000000"}]},
+        {"symbol": "SOLUSDT", "status": "TRADING", "baseAsset": "SOL", "quoteAsset": "USDT",
+         "filters": [
+            {"filterType": "PRICE_FILTER", "tickSize": "0.01000000"},
+            {"filterType": "LOT_SIZE", "stepSize": "0.01000000"},
+            {"filterType": "NOTIONAL", "minNotional": "5.00000000"}]},
+        {"symbol": "OLDUSDT", "status": "BREAK", "baseAsset": "OLD", "quoteAsset": "USDT"}
+    ]}"#;
+
+    fn mock_engine(config: &Config) -> ExecutionEngine<MockExchange> {
+        let registry = SymbolRegistry::new(config.max_symbols);
+        registry.reload_json(EXCHANGE_INFO, None).unwrap();
+        ExecutionEngine::new(
+            MockExchange::new(config.max_symbols),
+            Arc::new(registry),
+            Arc::new(Risk::new(config)),
+            config,
+        )
+    }
+
+    /// Trigger with a `quote_qty_e8` slot that the engine's table does not
+    /// track (shared with the shadow exchange tests)
+    pub(super) fn untracked_trigger(
+        symbol_id: u32,
+        price_e8: u64,
+        quote_qty_e8: u64,
+    ) -> TriggerEvent {
+        TriggerEvent {
+            symbol_id,
+            ts_unix_ms: 1_700_000_000_000,
+            return_pct: 5.5,
+            price_e8,
+            max_position_quote_e8: None,
+            slot: SlotReservation {
+                slot: 0,
+                symbol_id,
+                quote_qty_e8,
+            },
+        }
+    }
+
+    /// `untracked_trigger` with a 20 USDT slot
+    fn trigger(symbol_id: u32, price_e8: u64) -> TriggerEvent {
+        untracked_trigger(symbol_id, price_e8, 2_000_000_000)
+    }
+
+    /// Trigger holding a slot reserved from the engine's table
+    fn reserved_trigger(
+        engine: &ExecutionEngine<MockExchange>,
+        symbol_id: u32,
+        price_e8: u64,
+    ) -> TriggerEvent {
+        TriggerEvent {
+            slot: engine.risk.slots.reserve(symbol_id).unwrap(),
+            ..trigger(symbol_id, price_e8)
+        }
+    }
+
+    #[test]
+    fn test_encode_order() {
+        let mut out = Vec::new();
+        let order = Order {
+            client_order_id: 7,
+            symbol_id: 1,
+            side: Side::Sell,
+            kind: OrderKind::LimitGtc,
+            px_e8: 6_600_000_000,
+            qty_e8: 31_000_000,
+            quote_qty_e8: 0,
+        };
+        encode_order(&mut out, &order, "SOLUSDT");
+        assert_eq!(
+            std::str::from_utf8(&out).unwrap(),
+            "symbol=SOLUSDT&side=SELL&type=LIMIT&timeInForce=GTC&quantity=0.31000000\
+             &price=66.00000000&newClientOrderId=ufa-7&newOrderRespType=FULL"
+        );
+    }
+
+    #[test]
+    fn test_market_buy_then_take_profit() {
+        let mut engine = mock_engine(&Config::default());
+        engine.client_mut().on_trade(1, 6_000_000_000);
+
+        let event = engine.on_trigger(&trigger(1, 6_000_000_000)).unwrap();
+        let ExecutionEvent::TakeProfitPlaced {
+            buy,
+            take_profit,
+            timeline,
+        } = event
+        else {
+            panic!("unexpected event {:?}", event);
+        };
+        // BUY and LIMIT stages marked in order
+        let buy_sent = timeline.get(Stage::BuySent).unwrap();
+        assert!(timeline.get(Stage::BuySerialized).unwrap() <= buy_sent);
+        assert!(buy_sent <= timeline.get(Stage::AckReceived).unwrap());
+        assert!(timeline.get(Stage::AckReceived) <= timeline.get(Stage::LimitSent));
+        assert_eq!(timeline.get(Stage::FeedReceive), None);
+        // 20 USDT at 60.00
+        assert_eq!(buy.status, OrderStatus::Filled);
+        assert_eq!(buy.avg_px_e8(), 6_000_000_000);
+        // +10%, qty rounded down to the 0.01 step
+        assert_eq!(take_profit.px_e8, 6_600_000_000);
+        assert_eq!(take_profit.qty_e8, 33_000_000);
+        assert_eq!(engine.open_take_profit(1), Some(take_profit));
+
+        let sent = engine.client_mut().received();
+        assert_eq!(sent.len(), 2);
+        assert!(sent[0]
+            .1
+            .starts_with("symbol=SOLUSDT&side=BUY&type=MARKET&quoteOrderQty=20.00000000"));
+        assert!(sent[1]
+            .1
+            .starts_with("symbol=SOLUSDT&side=SELL&type=LIMIT&timeInForce=GTC"));
+    }
+
+    /// Mock whose requests take `WRITE` to go out and `WAIT` more to answer
+    struct SlowWire(MockExchange);
+
+    const WRITE: std::time::Duration = std::time::Duration::from_millis(2);
+    const WAIT: std::time::Duration = std::time::Duration::from_millis(3);
+
+    impl ExchangeClient for SlowWire {
+        fn submit(
+            &mut self,
+            order: &Order,
+            payload: &[u8],
+            on_sent: &mut dyn FnMut(),
+        ) -> Result<ExecutionReport, String> {
+            std::thread::sleep(WRITE);
+            let report = self.0.submit(order, payload, on_sent);
+            std::thread::sleep(WAIT);
+            report
+        }
+    }
+
+    #[test]
+    fn test_sent_stages_marked_by_the_client() {
+        let config = Config::default();
+        let registry = SymbolRegistry::new(config.max_symbols);
+        registry.reload_json(EXCHANGE_INFO, None).unwrap();
+        let mut mock = MockExchange::new(config.max_symbols);
+        mock.on_trade(1, 6_000_000_000);
+        let mut engine = ExecutionEngine::new(
+            SlowWire(mock),
+            Arc::new(registry),
+            Arc::new(Risk::new(&config)),
+            &config,
+        );
+
+        let event = engine.on_trigger(&trigger(1, 6_000_000_000)).unwrap();
+        let ExecutionEvent::TakeProfitPlaced { timeline, .. } = event else {
+            panic!("unexpected event {:?}", event);
+        };
+        let stage = |stage| timeline.get(stage).unwrap();
+        let write_ns = WRITE.as_nanos() as u64;
+        assert!(stage(Stage::BuySent) - stage(Stage::BuySerialized) >= write_ns);
+        assert!(stage(Stage::AckReceived) - stage(Stage::BuySent) >= WAIT.as_nanos() as u64);
+        assert!(stage(Stage::LimitSent) - stage(Stage::AckReceived) >= write_ns);
+    }
+
+    #[test]
+    fn test_symbol_max_position_caps_buy() {
+        let mut engine = mock_engine(&Config::default());
+        engine.client_mut().on_trade(1, 6_000_000_000);
+
+        let capped = TriggerEvent {
+            max_position_quote_e8: Some(1_200_000_000),
+            ..trigger(1, 6_000_000_000)
+        };
+        engine.on_trigger(&capped).unwrap();
+        let (buy, payload) = &engine.client().received()[0];
+        assert_eq!(buy.quote_qty_e8, 1_200_000_000);
+        assert!(payload.contains("quoteOrderQty=12.00000000"));
+    }
+
+    #[test]
+    fn test_take_profit_rounds_up_to_tick() {
+        let mut engine = mock_engine(&Config::default());
+        engine.client_mut().on_trade(0, 3_700_012_345_678);
+
+        let event = engine.on_trigger(&trigger(0, 3_700_012_345_678)).unwrap();
+        let ExecutionEvent::TakeProfitPlaced {
+            buy, take_profit, ..
+        } = event
+        else {
+            panic!("unexpected event {:?}", event);
+        };
+        assert_eq!(take_profit.px_e8 % 1_000_000, 0);
+        assert!(take_profit.px_e8 >= mul_div(buy.avg_px_e8(), 11_000, 10_000));
+        assert!(take_profit.px_e8 < mul_div(buy.avg_px_e8(), 11_000, 10_000) + 1_000_000);
+        assert_eq!(take_profit.qty_e8 % 1_000, 0);
+    }
+
+    #[test]
+    fn test_take_profit_fill_frees_symbol() {
+        let mut engine = mock_engine(&Config::default());
+        engine.client_mut().on_trade(1, 6_000_000_000);
+        engine.on_trigger(&trigger(1, 6_000_000_000)).unwrap();
+
+        // Second trigger while the TP rests is refused
+        assert_eq!(
+            engine.on_trigger(&trigger(1, 6_100_000_000)),
+            Err(ExecError::PositionOpen(1))
+        );
+
+        // Below the TP: nothing happens
+        assert!(engine.client_mut().on_trade(1, 6_500_000_000).is_empty());
+
+        let reports = engine.client_mut().on_trade(1, 6_600_000_000);
+        assert_eq!(reports.len(), 1);
+        let event = engine.on_report(&reports[0]).unwrap();
+        assert!(
+            matches!(event, Some(ExecutionEvent::TakeProfitDone(r)) if r.status == OrderStatus::Filled)
+        );
+        assert_eq!(engine.open_take_profit(1), None);
+
+        assert!(engine.on_trigger(&trigger(1, 6_600_000_000)).is_ok());
+    }
+
+    #[test]
+    fn test_take_profit_filled_on_submit() {
+        let mut engine = mock_engine(&Config {
+            take_profit_pct: 0.0,
+            ..Config::default()
+        });
+        let risk = Arc::clone(&engine.risk);
+        engine.client_mut().on_trade(1, 6_000_000_000);
+
+        // A 0% take-profit crosses the last trade and fills on the spot
+        let event = engine
+            .on_trigger(&reserved_trigger(&engine, 1, 6_000_000_000))
+            .unwrap();
+        let ExecutionEvent::TakeProfitFilled {
+            buy,
+            take_profit,
+            timeline,
+        } = event
+        else {
+            panic!("unexpected event {:?}", event);
+        };
+        assert_eq!(buy.status, OrderStatus::Filled);
+        assert_eq!(take_profit.status, OrderStatus::Filled);
+        assert_eq!(take_profit.executed_qty_e8, 33_000_000);
+        assert!(timeline.get(Stage::LimitSent).is_some());
+
+        assert_eq!(engine.open_take_profit(1), None);
+        assert_eq!(risk.slots.held(), 0);
+        assert!(risk.book.position(1).unwrap().qty_e8 < 1_000_000);
+        assert!(engine
+            .on_trigger(&reserved_trigger(&engine, 1, 6_000_000_000))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_ioc_buy() {
+        let config = Config {
+            buy_ioc_slippage_pct: Some(0.5),
+            ..Config::default()
+        };
+        let mut engine = mock_engine(&config);
+
+        // Market ran away past the IOC cap: no fill, no TP
+        engine.client_mut().on_trade(1, 6_100_000_000);
+        let event = engine.on_trigger(&trigger(1, 6_000_000_000)).unwrap();
+        assert!(
+            matches!(event, ExecutionEvent::BuyUnfilled(r) if r.status == OrderStatus::Expired)
+        );
+        assert_eq!(engine.open_take_profit(1), None);
+        assert!(engine.client_mut().received()[0]
+            .1
+            .contains("type=LIMIT&timeInForce=IOC&quantity=0.33000000&price=60.30000000"));
+
+        // Within the cap: fills at the market and places the TP
+        engine.client_mut().on_trade(1, 6_010_000_000);
+        let event = engine.on_trigger(&trigger(1, 6_000_000_000)).unwrap();
+        assert!(matches!(event, ExecutionEvent::TakeProfitPlaced { .. }));
+    }
+
+    #[test]
+    fn test_rejections() {
+        let mut engine = mock_engine(&Config::default());
+        assert_eq!(
+            engine.on_trigger(&trigger(2, 100_000_000)),
+            Err(ExecError::NotTrading(2))
+        );
+        assert_eq!(
+            engine.on_trigger(&trigger(9, 100_000_000)),
+            Err(ExecError::UnknownSymbol(9))
+        );
+        assert_eq!(
+            engine.on_trigger(&trigger(300, 100_000_000)),
+            Err(ExecError::UnknownSymbol(300))
+        );
+
+        engine.client_mut().on_trade(1, 6_000_000_000);
+        engine.client_mut().reject_next("rate limited");
+        assert_eq!(
+            engine.on_trigger(&trigger(1, 6_000_000_000)),
+            Err(ExecError::Exchange("rate limited".to_string()))
+        );
+        // Failed send leaves the symbol free
+        assert!(engine.on_trigger(&trigger(1, 6_000_000_000)).is_ok());
+
+        let mut small = trigger(0, 6_000_000_000);
+        small.slot.quote_qty_e8 = 100_000_000;
+        assert_eq!(engine.on_trigger(&small), Err(ExecError::BelowMinimum(0)));
+    }
+
+    #[test]
+    fn test_slot_allocation_sizes_buy() {
+        let mut engine = mock_engine(&Config::default());
+        engine.client_mut().on_trade(1, 6_000_000_000);
+
+        let mut sized = trigger(1, 6_000_000_000);
+        sized.slot.quote_qty_e8 = 3_000_000_000;
+        engine.on_trigger(&sized).unwrap();
+        assert_eq!(engine.client().received()[0].0.quote_qty_e8, 3_000_000_000);
+    }
+
+    #[test]
+    fn test_position_end_releases_slot() {
+        let mut engine = mock_engine(&Config::default());
+        let risk = Arc::clone(&engine.risk);
+        let slots = &risk.slots;
+        engine.client_mut().on_trade(1, 6_000_000_000);
+
+        // Held while the take-profit rests, freed by its fill
+        let held = reserved_trigger(&engine, 1, 6_000_000_000);
+        engine.on_trigger(&held).unwrap();
+        assert_eq!(slots.slot_of(1), Some(held.slot.slot));
+        let reports = engine.client_mut().on_trade(1, 6_600_000_000);
+        engine.on_report(&reports[0]).unwrap();
+        assert_eq!(slots.held(), 0);
+
+        // Failed sends, unknown symbols and unfilled BUYs free it right away
+        engine.client_mut().reject_next("rate limited");
+        assert!(engine
+            .on_trigger(&reserved_trigger(&engine, 1, 6_600_000_000))
+            .is_err());
+        assert!(engine
+            .on_trigger(&reserved_trigger(&engine, 9, 100_000_000))
+            .is_err());
+        assert_eq!(slots.held(), 0);
+
+        let mut ioc = mock_engine(&Config {
+            buy_ioc_slippage_pct: Some(0.5),
+            ..Config::default()
+        });
+        ioc.client_mut().on_trade(1, 6_100_000_000);
+        let event = ioc
+            .on_trigger(&reserved_trigger(&ioc, 1, 6_000_000_000))
+            .unwrap();
+        assert!(matches!(event, ExecutionEvent::BuyUnfilled(_)));
+        assert_eq!(ioc.risk.slots.held(), 0);
+    }
+
+    #[test]
+    fn test_rejected_take_profit_is_flattened() {
+        let mut engine = mock_engine(&Config::default());
+        let risk = Arc::clone(&engine.risk);
+        engine.client_mut().on_trade(1, 6_000_000_000);
+        engine.client_mut().reject_sells(true);
+
+        // The BUY fills, the take-profit is rejected: the slot stays with
+        // the position and the symbol gets no other
+        let held = reserved_trigger(&engine, 1, 6_000_000_000);
+        assert!(matches!(
+            engine.on_trigger(&held),
+            Err(ExecError::TakeProfitFailed { .. })
+        ));
+        assert!(engine.is_unprotected(1));
+        assert_eq!(risk.slots.slot_of(1), Some(held.slot.slot));
+        assert_eq!(risk.slots.reserve(1), None);
+
+        // A rejected flatten leaves it unprotected
+        let event = engine.flatten(1).unwrap();
+        assert!(
+            matches!(event, Some(ExecutionEvent::Unprotected(r)) if r.status == OrderStatus::Rejected)
+        );
+        assert_eq!(risk.slots.held(), 1);
+
+        engine.client_mut().reject_sells(false);
+        engine.client_mut().on_trade(1, 5_700_000_000);
+        let event = engine.flatten(1).unwrap();
+        assert!(
+            matches!(event, Some(ExecutionEvent::Flattened(r)) if r.executed_qty_e8 == 33_000_000)
+        );
+        let (sell, _) = engine.client().received().last().unwrap();
+        assert_eq!((sell.side, sell.kind), (Side::Sell, OrderKind::Market));
+
+        // Flat but for the dust below the step size: slot and symbol free
+        assert!(!engine.is_unprotected(1));
+        assert_eq!(risk.slots.held(), 0);
+        let position = risk.book.position(1).unwrap();
+        assert!(position.qty_e8 < 1_000_000);
+        assert!(position.realized_pnl_e8 < 0);
+        assert_eq!(engine.flatten(1), Ok(None));
+        assert!(engine
+            .on_trigger(&reserved_trigger(&engine, 1, 5_700_000_000))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_canceled_take_profit_keeps_slot_until_flat() {
+        let mut engine = mock_engine(&Config::default());
+        let risk = Arc::clone(&engine.risk);
+        engine.client_mut().on_trade(1, 6_000_000_000);
+        let held = reserved_trigger(&engine, 1, 6_000_000_000);
+        engine.on_trigger(&held).unwrap();
+
+        let take_profit = engine.open_take_profit(1).unwrap();
+        let canceled = engine
+            .client_mut()
+            .cancel(take_profit.client_order_id)
+            .unwrap();
+        let event = engine.on_report(&canceled).unwrap();
+        assert!(
+            matches!(event, Some(ExecutionEvent::Unprotected(r)) if r.status == OrderStatus::Canceled)
+        );
+        assert_eq!(engine.open_take_profit(1), None);
+        assert!(engine.is_unprotected(1));
+        assert_eq!(risk.slots.slot_of(1), Some(held.slot.slot));
+        assert!(risk.book.position(1).unwrap().qty_e8 >= take_profit.qty_e8);
+
+        let event = engine.flatten(1).unwrap();
+        assert!(matches!(event, Some(ExecutionEvent::Flattened(_))));
+        assert_eq!(risk.slots.held(), 0);
+    }
+
+    #[test]
+    fn test_fills_update_position_book() {
+        let mut engine = mock_engine(&Config::default());
+        let risk = Arc::clone(&engine.risk);
+        engine.client_mut().on_trade(1, 6_000_000_000);
+
+        engine
+            .on_trigger(&reserved_trigger(&engine, 1, 6_000_000_000))
+            .unwrap();
+        let bought = risk.book.position(1).unwrap();
+        assert!(bought.qty_e8 > 0);
+        assert_eq!(bought.avg_entry_px_e8, 6_000_000_000);
+        assert_eq!(
+            risk.book.exposure_e8(),
+            mul_div(bought.qty_e8, 6_000_000_000, E8)
+        );
+        // Rounded down to the lot step
+        let take_profit = bought.take_profit.unwrap();
+        assert!(take_profit.qty_e8 <= bought.qty_e8);
+
+        // The take-profit's fill sells at a profit; only the dust below
+        // the lot step stays in the book
+        let reports = engine.client_mut().on_trade(1, take_profit.px_e8);
+        engine.on_report(&reports[0]).unwrap();
+        let position = risk.book.position(1).unwrap();
+        assert_eq!(position.take_profit, None);
+        assert_eq!(position.qty_e8, bought.qty_e8 - take_profit.qty_e8);
+        assert!(position.realized_pnl_e8 > 0);
+    }
+
+    #[test]
+    fn test_payload_buffer_is_reused() {
+        let mut engine = mock_engine(&Config::default());
+        let buffer = engine.payload.as_ptr();
+        for round in 0..10u64 {
+            let px = 6_000_000_000 + round * 1_000_000;
+            engine.client_mut().on_trade(1, px);
+            engine.on_trigger(&trigger(1, px)).unwrap();
+            let reports = engine.client_mut().on_trade(1, px * 2);
+            engine.on_report(&reports[0]).unwrap();
+        }
+        assert_eq!(engine.payload.as_ptr(), buffer);
+        assert_eq!(engine.payload.capacity(), PAYLOAD_CAPACITY);
+    }
+}
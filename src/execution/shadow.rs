@@ -0,0 +1,503 @@
+//! Shadow-mode exchange simulator (no real orders)
+//!
+//! A BUY fills on the next tick of its symbol at that tick's price plus
+//! slippage; the take-profit LIMIT rests and fills at its limit price once a
+//! later tick trades at or above it. A MARKET SELL flattening a position
+//! fills on the next tick less slippage. Fees are charged on both legs and every
+//! closed round trip is appended to a JSON-lines ledger.
+
+use super::{
+    mul_div, pct_to_bps, ExchangeClient, ExecutionReport, Order, OrderKind, OrderStatus, Side, E8,
+};
+use crate::config::Config;
+use crate::data_feed::TradeTick;
+use crate::symbols::SymbolRegistry;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// One closed shadow round trip (amounts in quote asset, e8)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LedgerEntry {
+    pub symbol_id: u32,
+    pub buy_client_order_id: u64,
+    pub sell_client_order_id: u64,
+    pub entry_ts_unix_ms: u64,
+    pub exit_ts_unix_ms: u64,
+    pub qty_e8: u64,
+    pub entry_px_e8: u64,
+    pub exit_px_e8: u64,
+    /// Fees on both legs
+    pub fees_e8: u64,
+    /// Exit notional - entry notional - fees
+    pub pnl_e8: i64,
+}
+
+/// Ledger line: entry plus the exchange symbol
+#[derive(Serialize)]
+struct LedgerRecord<'a> {
+    symbol: &'a str,
+    #[serde(flatten)]
+    entry: &'a LedgerEntry,
+}
+
+/// Totals over the closed shadow trades
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShadowSummary {
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub fees_e8: u64,
+    pub pnl_e8: i64,
+    /// BUYs filled whose take-profit has not filled yet
+    pub open_positions: usize,
+}
+
+/// Filled BUY waiting for its take-profit
+#[derive(Debug, Clone, Copy)]
+struct OpenPosition {
+    buy_client_order_id: u64,
+    ts_unix_ms: u64,
+    px_e8: u64,
+    fee_e8: u64,
+}
+
+/// Per-symbol simulator state
+#[derive(Debug, Clone, Copy, Default)]
+struct SymbolBook {
+    /// BUY accepted, fills on the next tick
+    pending_buy: Option<Order>,
+    position: Option<OpenPosition>,
+    /// Resting take-profit LIMIT SELL
+    take_profit: Option<Order>,
+    /// MARKET SELL accepted, fills on the next tick
+    pending_sell: Option<Order>,
+}
+
+/// Simulated exchange driven by the trade stream
+pub struct ShadowExchange {
+    registry: Arc<SymbolRegistry>,
+    /// Adverse BUY slippage against the fill tick
+    slippage_bps: u64,
+    /// Fee per leg on the filled notional
+    fee_bps: u64,
+    /// Indexed by symbol_id
+    books: Vec<SymbolBook>,
+    /// Closed round trips
+    trades: Vec<LedgerEntry>,
+    /// JSON-lines ledger file
+    ledger: Option<BufWriter<File>>,
+}
+
+impl ShadowExchange {
+    /// Create a simulator using `Config::shadow_slippage_pct` / `shadow_fee_pct`
+    pub fn new(registry: Arc<SymbolRegistry>, config: &Config) -> Self {
+        Self {
+            registry,
+            slippage_bps: pct_to_bps(config.shadow_slippage_pct),
+            fee_bps: pct_to_bps(config.shadow_fee_pct),
+            books: vec![SymbolBook::default(); config.max_symbols],
+            trades: Vec::new(),
+            ledger: None,
+        }
+    }
+
+    /// Append closed trades to a JSON-lines file at `path` (created or truncated)
+    pub fn open_ledger(&mut self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create ledger {}: {}", path.display(), e))?;
+        self.ledger = Some(BufWriter::new(file));
+        Ok(())
+    }
+
+    /// Closed round trips so far
+    pub fn trades(&self) -> &[LedgerEntry] {
+        &self.trades
+    }
+
+    /// Totals over the closed trades
+    pub fn summary(&self) -> ShadowSummary {
+        let mut summary = ShadowSummary {
+            trades: self.trades.len(),
+            open_positions: self.books.iter().filter(|b| b.position.is_some()).count(),
+            ..ShadowSummary::default()
+        };
+        for trade in &self.trades {
+            if trade.pnl_e8 > 0 {
+                summary.wins += 1;
+            } else {
+                summary.losses += 1;
+            }
+            summary.fees_e8 += trade.fees_e8;
+            summary.pnl_e8 += trade.pnl_e8;
+        }
+        summary
+    }
+
+    /// Match the symbol's working orders against a trade
+    pub fn on_tick(&mut self, tick: &TradeTick) -> Vec<ExecutionReport> {
+        let mut reports = Vec::new();
+        let Some(book) = self.books.get(tick.symbol_id as usize).copied() else {
+            return reports;
+        };
+
+        if let Some(buy) = book.pending_buy {
+            reports.push(self.fill_buy(&buy, tick));
+        }
+        if let (Some(tp), Some(position)) = (book.take_profit, book.position) {
+            if tick.px_e8 >= tp.px_e8 {
+                reports.push(self.close(&tp, &position, tp.px_e8, tick.ts_unix_ms));
+            }
+        }
+        if let (Some(sell), Some(position)) = (book.pending_sell, book.position) {
+            let px_e8 = mul_div(tick.px_e8, 10_000 - self.slippage_bps.min(10_000), 10_000);
+            reports.push(self.close(&sell, &position, px_e8, tick.ts_unix_ms));
+        }
+
+        reports
+    }
+
+    fn fill_buy(&mut self, buy: &Order, tick: &TradeTick) -> ExecutionReport {
+        let book = &mut self.books[buy.symbol_id as usize];
+        book.pending_buy = None;
+
+        let px_e8 = mul_div(tick.px_e8, 10_000 + self.slippage_bps, 10_000);
+        let qty_e8 = match buy.kind {
+            OrderKind::Market => mul_div(buy.quote_qty_e8, E8, px_e8),
+            _ if px_e8 <= buy.px_e8 => buy.qty_e8,
+            _ => 0,
+        };
+        let notional_e8 = mul_div(qty_e8, px_e8, E8);
+
+        if qty_e8 > 0 {
+            book.position = Some(OpenPosition {
+                buy_client_order_id: buy.client_order_id,
+                ts_unix_ms: tick.ts_unix_ms,
+                px_e8,
+                fee_e8: mul_div(notional_e8, self.fee_bps, 10_000),
+            });
+        }
+
+        ExecutionReport {
+            client_order_id: buy.client_order_id,
+            symbol_id: buy.symbol_id,
+            side: Side::Buy,
+            status: if qty_e8 > 0 {
+                OrderStatus::Filled
+            } else {
+                OrderStatus::Expired
+            },
+            executed_qty_e8: qty_e8,
+            cum_quote_e8: notional_e8,
+        }
+    }
+
+    /// Fill the SELL `order` closing `position` at `px_e8` and ledger the
+    /// round trip
+    fn close(
+        &mut self,
+        order: &Order,
+        position: &OpenPosition,
+        px_e8: u64,
+        ts_unix_ms: u64,
+    ) -> ExecutionReport {
+        let book = &mut self.books[order.symbol_id as usize];
+        book.take_profit = None;
+        book.pending_sell = None;
+        book.position = None;
+
+        let entry_notional_e8 = mul_div(order.qty_e8, position.px_e8, E8);
+        let exit_notional_e8 = mul_div(order.qty_e8, px_e8, E8);
+        let fees_e8 = position.fee_e8 + mul_div(exit_notional_e8, self.fee_bps, 10_000);
+        let entry = LedgerEntry {
+            symbol_id: order.symbol_id,
+            buy_client_order_id: position.buy_client_order_id,
+            sell_client_order_id: order.client_order_id,
+            entry_ts_unix_ms: position.ts_unix_ms,
+            exit_ts_unix_ms: ts_unix_ms,
+            qty_e8: order.qty_e8,
+            entry_px_e8: position.px_e8,
+            exit_px_e8: px_e8,
+            fees_e8,
+            pnl_e8: exit_notional_e8 as i64 - entry_notional_e8 as i64 - fees_e8 as i64,
+        };
+        self.record(entry);
+
+        ExecutionReport {
+            client_order_id: order.client_order_id,
+            symbol_id: order.symbol_id,
+            side: Side::Sell,
+            status: OrderStatus::Filled,
+            executed_qty_e8: order.qty_e8,
+            cum_quote_e8: exit_notional_e8,
+        }
+    }
+
+    fn record(&mut self, entry: LedgerEntry) {
+        if let Some(ledger) = &mut self.ledger {
+            let symbol = self.registry.name_of(entry.symbol_id);
+            let record = LedgerRecord {
+                symbol: &symbol,
+                entry: &entry,
+            };
+            let written = serde_json::to_writer(&mut *ledger, &record)
+                .map_err(|e| e.to_string())
+                .and_then(|_| {
+                    ledger
+                        .write_all(b"\n")
+                        .and_then(|_| ledger.flush())
+                        .map_err(|e| e.to_string())
+                });
+            if let Err(e) = written {
+                eprintln!("Failed to write shadow ledger: {}", e);
+            }
+        }
+        self.trades.push(entry);
+    }
+}
+
+impl ExchangeClient for ShadowExchange {
+    fn submit(
+        &mut self,
+        order: &Order,
+        _payload: &[u8],
+        on_sent: &mut dyn FnMut(),
+    ) -> Result<ExecutionReport, String> {
+        let book = self
+            .books
+            .get_mut(order.symbol_id as usize)
+            .ok_or_else(|| format!("unknown symbol #{}", order.symbol_id))?;
+
+        match (order.side, order.kind) {
+            (Side::Buy, OrderKind::Market | OrderKind::LimitIoc) => book.pending_buy = Some(*order),
+            (Side::Sell, OrderKind::LimitGtc) if book.position.is_some() => {
+                book.take_profit = Some(*order)
+            }
+            (Side::Sell, OrderKind::Market) if book.position.is_some() => {
+                book.pending_sell = Some(*order)
+            }
+            (Side::Sell, OrderKind::LimitGtc | OrderKind::Market) => {
+                return Err(format!(
+                    "no shadow position for symbol #{}",
+                    order.symbol_id
+                ))
+            }
+            _ => return Err(format!("unsupported shadow order {:?}", order)),
+        }
+        // The simulator's "wire" is its order book
+        on_sent();
+
+        Ok(ExecutionReport {
+            client_order_id: order.client_order_id,
+            symbol_id: order.symbol_id,
+            side: order.side,
+            status: OrderStatus::New,
+            executed_qty_e8: 0,
+            cum_quote_e8: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::untracked_trigger;
+    use super::super::{ExecutionEngine, ExecutionEvent};
+    use super::*;
+    use crate::hotpath::TriggerEvent;
+    use crate::risk::Risk;
+
+    fn shadow_engine(config: &Config) -> ExecutionEngine<ShadowExchange> {
+        let symbols = vec!["BTCUSDT".to_string(), "SOLUSDT".to_string()];
+        let registry =
+            Arc::new(SymbolRegistry::from_symbols(&symbols, config.max_symbols).unwrap());
+        let exchange = ShadowExchange::new(Arc::clone(&registry), config);
+        ExecutionEngine::new(exchange, registry, Arc::new(Risk::new(config)), config)
+    }
+
+    /// Trigger spending the config's `buy_quote_qty_e8`
+    fn trigger(config: &Config, symbol_id: u32, price_e8: u64) -> TriggerEvent {
+        untracked_trigger(symbol_id, price_e8, config.buy_quote_qty_e8)
+    }
+
+    /// Feed a tick to the simulator and pass its reports to the engine
+    fn tick(
+        engine: &mut ExecutionEngine<ShadowExchange>,
+        px_e8: u64,
+        ts: u64,
+    ) -> Vec<ExecutionEvent> {
+        let reports = engine.client_mut().on_tick(&TradeTick::new(1, px_e8, ts));
+        reports
+            .iter()
+            .filter_map(|report| engine.on_report(report).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_buy_fills_on_next_tick_with_slippage() {
+        let config = Config {
+            shadow_slippage_pct: 0.1,
+            shadow_fee_pct: 0.0,
+            // Buys exactly one unit at the slipped price
+            buy_quote_qty_e8: 5_105_100_000,
+            ..Config::default()
+        };
+        let mut engine = shadow_engine(&config);
+
+        let event = engine
+            .on_trigger(&trigger(&config, 1, 5_000_000_000))
+            .unwrap();
+        assert!(matches!(event, ExecutionEvent::BuyPending(_)));
+
+        // Other symbols do not fill it
+        assert!(engine
+            .client_mut()
+            .on_tick(&TradeTick::new(0, 1_000_000_000, 1))
+            .is_empty());
+
+        let events = tick(&mut engine, 5_100_000_000, 2);
+        let [ExecutionEvent::TakeProfitPlaced {
+            buy, take_profit, ..
+        }] = events[..]
+        else {
+            panic!("unexpected events {:?}", events);
+        };
+        // 51.00 + 0.1%
+        assert_eq!(buy.executed_qty_e8, 100_000_000);
+        assert_eq!(buy.avg_px_e8(), 5_105_100_000);
+        assert_eq!(take_profit.px_e8, 5_615_610_000);
+        assert_eq!(engine.client_mut().summary().open_positions, 1);
+    }
+
+    #[test]
+    fn test_take_profit_fills_when_crossed_and_is_ledgered() {
+        let config = Config {
+            shadow_slippage_pct: 0.0,
+            shadow_fee_pct: 0.1,
+            ..Config::default()
+        };
+        let mut engine = shadow_engine(&config);
+        engine
+            .on_trigger(&trigger(&config, 1, 5_000_000_000))
+            .unwrap();
+        tick(&mut engine, 5_000_000_000, 1_000);
+
+        // TP at 55.00 rests below the crossing tick
+        assert!(tick(&mut engine, 5_499_999_999, 2_000).is_empty());
+        let events = tick(&mut engine, 5_600_000_000, 3_000);
+        assert!(
+            matches!(events[..], [ExecutionEvent::TakeProfitDone(r)] if r.status == OrderStatus::Filled)
+        );
+
+        let trades = engine.client_mut().trades().to_vec();
+        assert_eq!(trades.len(), 1);
+        let trade = trades[0];
+        assert_eq!(trade.entry_ts_unix_ms, 1_000);
+        assert_eq!(trade.exit_ts_unix_ms, 3_000);
+        // Filled at the limit price, not the crossing tick
+        assert_eq!(trade.exit_px_e8, 5_500_000_000);
+        // 0.4 SOL: 20.00 in, 22.00 out, 0.1% fee per leg
+        assert_eq!(trade.qty_e8, 40_000_000);
+        assert_eq!(trade.fees_e8, 2_000_000 + 2_200_000);
+        assert_eq!(trade.pnl_e8, 200_000_000 - 4_200_000);
+
+        let summary = engine.client_mut().summary();
+        assert_eq!(summary.trades, 1);
+        assert_eq!(summary.wins, 1);
+        assert_eq!(summary.open_positions, 0);
+
+        // Symbol can trade again
+        assert!(engine
+            .on_trigger(&trigger(&config, 1, 5_600_000_000))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_market_sell_closes_on_next_tick() {
+        let config = Config {
+            shadow_slippage_pct: 0.1,
+            shadow_fee_pct: 0.0,
+            buy_quote_qty_e8: 5_005_000_000,
+            ..Config::default()
+        };
+        let mut engine = shadow_engine(&config);
+        engine
+            .on_trigger(&trigger(&config, 1, 5_000_000_000))
+            .unwrap();
+        tick(&mut engine, 5_000_000_000, 1_000);
+
+        let sell = Order {
+            client_order_id: 9,
+            symbol_id: 1,
+            side: Side::Sell,
+            kind: OrderKind::Market,
+            px_e8: 0,
+            qty_e8: 100_000_000,
+            quote_qty_e8: 0,
+        };
+        let accepted = engine.client_mut().submit(&sell, b"", &mut || {}).unwrap();
+        assert_eq!(accepted.status, OrderStatus::New);
+        let reports = engine
+            .client_mut()
+            .on_tick(&TradeTick::new(1, 4_800_000_000, 2_000));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].status, OrderStatus::Filled);
+
+        // 48.00 less 0.1% against the 50.05 entry
+        let trade = engine.client().trades()[0];
+        assert_eq!(trade.exit_px_e8, 4_795_200_000);
+        assert_eq!(trade.pnl_e8, 4_795_200_000 - 5_005_000_000);
+        assert_eq!(engine.client().summary().open_positions, 0);
+    }
+
+    #[test]
+    fn test_ioc_buy_expires_past_cap() {
+        let config = Config {
+            buy_ioc_slippage_pct: Some(0.5),
+            ..Config::default()
+        };
+        let mut engine = shadow_engine(&config);
+        engine
+            .on_trigger(&trigger(&config, 1, 5_000_000_000))
+            .unwrap();
+
+        let events = tick(&mut engine, 5_100_000_000, 1);
+        assert!(
+            matches!(events[..], [ExecutionEvent::BuyUnfilled(r)] if r.status == OrderStatus::Expired)
+        );
+        assert_eq!(engine.client_mut().summary().open_positions, 0);
+    }
+
+    #[test]
+    fn test_ledger_file() {
+        let path = std::env::temp_dir().join(format!(
+            "ufa_shadow_ledger_{}/ledger.jsonl",
+            std::process::id()
+        ));
+        let config = Config {
+            shadow_fee_pct: 0.0,
+            shadow_slippage_pct: 0.0,
+            ..Config::default()
+        };
+        let mut engine = shadow_engine(&config);
+        engine.client_mut().open_ledger(&path).unwrap();
+
+        engine
+            .on_trigger(&trigger(&config, 1, 5_000_000_000))
+            .unwrap();
+        tick(&mut engine, 5_000_000_000, 1);
+        tick(&mut engine, 5_500_000_000, 2);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["symbol"], "SOLUSDT");
+        assert_eq!(record["pnl_e8"], 200_000_000);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
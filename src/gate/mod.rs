@@ -0,0 +1,374 @@
+//! Alt Market Gate (AHI): an alt health index computed off the hot path from
+//! breadth across all tracked symbols, flipping `HotPath::can_buy` with
+//! hysteresis
+//!
+//! The settings are read from the live config on every evaluation, so a
+//! reload changes them without a restart.
+
+use crate::config::{Config, GateConfig};
+use crate::hotpath::history::Aggregates;
+use crate::hotpath::HotPath;
+use crate::symbols::SymbolRegistry;
+use arc_swap::ArcSwap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// `last_ahi_bits` value meaning "no reading yet"
+const AHI_UNSET: u64 = u64::MAX;
+
+/// Market breadth at one evaluation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AhiReading {
+    /// Alt health index 0-100: mean of the 15m and 1h breadth (15m only
+    /// until any symbol has an hour of history)
+    pub ahi: f64,
+    /// Share of alts with a positive 15m return (0-1)
+    pub breadth_15m: f64,
+    /// Share of alts with a positive 1h return (0-1)
+    pub breadth_1h: Option<f64>,
+    /// Median 15m alt return (%)
+    pub median_ret_15m: f64,
+    /// 1h return of the reference symbol (%)
+    pub reference_ret_1h: Option<f64>,
+    /// Alts with a 15m return
+    pub symbols: usize,
+}
+
+impl AhiReading {
+    /// Reading from the alts' aggregates and the reference symbol's;
+    /// None if no alt has a 15m return
+    pub fn from_aggregates(
+        alts: impl IntoIterator<Item = Aggregates>,
+        reference: Option<Aggregates>,
+    ) -> Option<Self> {
+        let mut rets_15m = Vec::new();
+        let (mut up_1h, mut with_1h) = (0usize, 0usize);
+        for aggregates in alts {
+            if let Some(ret) = aggregates.ret_15m {
+                rets_15m.push(ret);
+            }
+            if let Some(ret) = aggregates.ret_1h {
+                with_1h += 1;
+                up_1h += (ret > 0.0) as usize;
+            }
+        }
+        if rets_15m.is_empty() {
+            return None;
+        }
+
+        let symbols = rets_15m.len();
+        let breadth_15m = rets_15m.iter().filter(|&&ret| ret > 0.0).count() as f64 / symbols as f64;
+        let breadth_1h = (with_1h > 0).then(|| up_1h as f64 / with_1h as f64);
+        rets_15m.sort_unstable_by(f64::total_cmp);
+        let median_ret_15m = match symbols % 2 {
+            1 => rets_15m[symbols / 2],
+            _ => (rets_15m[symbols / 2 - 1] + rets_15m[symbols / 2]) / 2.0,
+        };
+        let ahi = 100.0 * breadth_1h.map_or(breadth_15m, |b| (breadth_15m + b) / 2.0);
+
+        Some(Self {
+            ahi,
+            breadth_15m,
+            breadth_1h,
+            median_ret_15m,
+            reference_ret_1h: reference.and_then(|r| r.ret_1h),
+            symbols,
+        })
+    }
+}
+
+impl fmt::Display for AhiReading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "AHI {:.1}, {} alts, up 15m {:.0}%",
+            self.ahi,
+            self.symbols,
+            self.breadth_15m * 100.0
+        )?;
+        if let Some(breadth) = self.breadth_1h {
+            write!(f, ", up 1h {:.0}%", breadth * 100.0)?;
+        }
+        write!(f, ", median 15m {:+.2}%", self.median_ret_15m)?;
+        if let Some(ret) = self.reference_ret_1h {
+            write!(f, ", reference 1h {:+.2}%", ret)?;
+        }
+        Ok(())
+    }
+}
+
+/// A change of the gate's decision
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateTransition {
+    /// New can_buy value
+    pub can_buy: bool,
+    /// Condition(s) that caused the change
+    pub reason: String,
+    /// Reading the decision was made on
+    pub reading: AhiReading,
+}
+
+impl fmt::Display for GateTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can_buy -> {}: {} ({})",
+            self.can_buy, self.reason, self.reading
+        )
+    }
+}
+
+/// Hysteresis state machine over AHI readings
+pub struct AltMarketGate {
+    /// Live config (`gate` section); swapped by the config watcher
+    config: Arc<ArcSwap<Config>>,
+    /// Resolves the reference symbol to its symbol_id
+    registry: Arc<SymbolRegistry>,
+    /// Current decision (starts open)
+    open: AtomicBool,
+    /// AHI of the last reading as f64 bits
+    last_ahi_bits: AtomicU64,
+}
+
+impl AltMarketGate {
+    /// Create an open gate reading its settings from `config`
+    pub fn new(config: Arc<ArcSwap<Config>>, registry: Arc<SymbolRegistry>) -> Self {
+        Self {
+            config,
+            registry,
+            open: AtomicBool::new(true),
+            last_ahi_bits: AtomicU64::new(AHI_UNSET),
+        }
+    }
+
+    /// AHI of the last evaluation with enough symbols
+    pub fn last_ahi(&self) -> Option<f64> {
+        match self.last_ahi_bits.load(Ordering::Relaxed) {
+            AHI_UNSET => None,
+            bits => Some(f64::from_bits(bits)),
+        }
+    }
+
+    /// Read the published aggregates of `hotpath`, update the decision and
+    /// apply a change to its can_buy flag (off hot-path). Holds the current
+    /// decision while fewer than `min_symbols` alts have a 15m return; a
+    /// disabled gate reopens can_buy and decides nothing.
+    pub fn evaluate(&self, hotpath: &HotPath) -> Option<GateTransition> {
+        let config = self.config.load();
+        if !config.gate.enabled {
+            if !self.open.swap(true, Ordering::Relaxed) {
+                hotpath.set_can_buy(true);
+            }
+            return None;
+        }
+        let reference_id = self.registry.id_of(&config.gate.reference_symbol);
+        let alts = hotpath
+            .symbol_ids()
+            .filter(|&id| Some(id) != reference_id)
+            .filter_map(|id| hotpath.aggregates(id));
+        let reference = reference_id.and_then(|id| hotpath.aggregates(id));
+        let reading = AhiReading::from_aggregates(alts, reference)?;
+        if reading.symbols < config.gate.min_symbols {
+            return None;
+        }
+
+        let transition = self.update(&config.gate, reading)?;
+        hotpath.set_can_buy(transition.can_buy);
+        Some(transition)
+    }
+
+    /// Apply one reading to the decision under `config`; returns the
+    /// transition, if any
+    pub fn update(&self, config: &GateConfig, reading: AhiReading) -> Option<GateTransition> {
+        self.last_ahi_bits
+            .store(reading.ahi.to_bits(), Ordering::Relaxed);
+        let reference = reading.reference_ret_1h;
+
+        let reason = if self.open.load(Ordering::Relaxed) {
+            // Any condition below its close level closes the gate
+            let mut failed = Vec::new();
+            if reading.ahi < config.ahi_off_below {
+                failed.push(format!(
+                    "AHI {:.1} < {:.1}",
+                    reading.ahi, config.ahi_off_below
+                ));
+            }
+            if reading.median_ret_15m < config.median_ret_15m_off_below_pct {
+                failed.push(format!(
+                    "median 15m {:+.2}% < {:+.2}%",
+                    reading.median_ret_15m, config.median_ret_15m_off_below_pct
+                ));
+            }
+            if let Some(ret) = reference.filter(|&r| r < config.reference_ret_1h_off_below_pct) {
+                failed.push(format!(
+                    "{} 1h {:+.2}% < {:+.2}%",
+                    config.reference_symbol, ret, config.reference_ret_1h_off_below_pct
+                ));
+            }
+            (!failed.is_empty()).then(|| failed.join(", "))?
+        } else {
+            // Every condition must be back above its reopen level
+            let recovered = reading.ahi >= config.ahi_on_above
+                && reading.median_ret_15m >= config.median_ret_15m_on_above_pct
+                && reference.is_none_or(|r| r >= config.reference_ret_1h_on_above_pct);
+            if !recovered {
+                return None;
+            }
+            format!(
+                "AHI {:.1} >= {:.1}, median 15m {:+.2}% >= {:+.2}%",
+                reading.ahi,
+                config.ahi_on_above,
+                reading.median_ret_15m,
+                config.median_ret_15m_on_above_pct
+            )
+        };
+
+        let can_buy = !self.open.load(Ordering::Relaxed);
+        self.open.store(can_buy, Ordering::Relaxed);
+        Some(GateTransition {
+            can_buy,
+            reason,
+            reading,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gate over a live default config, with BTCUSDT as symbol_id 0
+    fn gate(config: &Arc<ArcSwap<Config>>) -> AltMarketGate {
+        let registry =
+            SymbolRegistry::from_symbols(&["BTCUSDT".to_string()], config.load().max_symbols)
+                .unwrap();
+        AltMarketGate::new(Arc::clone(config), Arc::new(registry))
+    }
+
+    fn reading(ahi: f64, median_ret_15m: f64, reference_ret_1h: Option<f64>) -> AhiReading {
+        AhiReading {
+            ahi,
+            breadth_15m: ahi / 100.0,
+            breadth_1h: None,
+            median_ret_15m,
+            reference_ret_1h,
+            symbols: 20,
+        }
+    }
+
+    fn aggregates(ret_15m: f64, ret_1h: Option<f64>) -> Aggregates {
+        Aggregates {
+            ret_15m: Some(ret_15m),
+            ret_1h,
+            ..Aggregates::default()
+        }
+    }
+
+    #[test]
+    fn test_reading_from_aggregates() {
+        let alts = [
+            aggregates(1.0, Some(2.0)),
+            aggregates(-0.5, Some(1.0)),
+            aggregates(3.0, None),
+            aggregates(-2.0, Some(-1.0)),
+            Aggregates::default(),
+        ];
+        let reference = aggregates(0.1, Some(-1.5));
+        let reading = AhiReading::from_aggregates(alts, Some(reference)).unwrap();
+
+        assert_eq!(reading.symbols, 4);
+        assert_eq!(reading.breadth_15m, 0.5);
+        assert_eq!(reading.breadth_1h, Some(2.0 / 3.0));
+        assert_eq!(reading.median_ret_15m, 0.25);
+        assert_eq!(reading.reference_ret_1h, Some(-1.5));
+        assert!((reading.ahi - 100.0 * (0.5 + 2.0 / 3.0) / 2.0).abs() < 1e-9);
+
+        assert_eq!(
+            AhiReading::from_aggregates([Aggregates::default()], None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let gate = gate(&Arc::new(ArcSwap::from_pointee(Config::default())));
+        let config = GateConfig::default();
+
+        assert_eq!(gate.update(&config, reading(45.0, 0.0, Some(0.0))), None);
+        let closed = gate
+            .update(&config, reading(35.0, -1.2, Some(0.0)))
+            .unwrap();
+        assert!(!closed.can_buy);
+        assert!(closed.reason.contains("AHI 35.0 < 40.0"));
+        assert!(closed.reason.contains("median 15m -1.20% < -1.00%"));
+        assert_eq!(gate.last_ahi(), Some(35.0));
+
+        // Inside the band: stays closed
+        assert_eq!(gate.update(&config, reading(50.0, 0.0, Some(0.0))), None);
+        // AHI recovered but the reference is still falling
+        assert_eq!(gate.update(&config, reading(60.0, 0.0, Some(-1.5))), None);
+        let opened = gate
+            .update(&config, reading(60.0, 0.0, Some(-0.5)))
+            .unwrap();
+        assert!(opened.can_buy);
+        assert!(opened.reason.starts_with("AHI 60.0 >= 55.0"));
+
+        // Inside the band: stays open; reference dump alone closes it
+        assert_eq!(gate.update(&config, reading(50.0, -0.8, None)), None);
+        let closed = gate
+            .update(&config, reading(60.0, 0.0, Some(-2.5)))
+            .unwrap();
+        assert_eq!(closed.reason, "BTCUSDT 1h -2.50% < -2.00%");
+        assert!(closed.to_string().starts_with("can_buy -> false: BTCUSDT"));
+    }
+
+    #[test]
+    fn test_evaluate_drives_can_buy() {
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 13,
+            ..Config::default()
+        }));
+        let hotpath = HotPath::new(Arc::clone(&config));
+        let gate = gate(&config);
+
+        // 16 minutes of 1/s trades: the reference flat, 12 alts falling
+        let now = 1_700_000_000_000u64;
+        for sec in (0..960u64).rev() {
+            let ts = now - sec * 1_000;
+            hotpath.record_history(0, 100_000_000, ts);
+            for id in 1..13 {
+                hotpath.record_history(id, 100_000_000 + sec * 10_000, ts);
+            }
+        }
+        // Not enough symbols with aggregates yet: no decision
+        assert_eq!(gate.evaluate(&hotpath), None);
+
+        for id in hotpath.symbol_ids() {
+            hotpath.update_aggregates(id, now);
+        }
+        let transition = gate.evaluate(&hotpath).unwrap();
+        assert!(!transition.can_buy);
+        assert_eq!(transition.reading.symbols, 12);
+        assert_eq!(transition.reading.reference_ret_1h, None);
+        assert!(!hotpath.get_can_buy());
+
+        // Reloaded settings apply to the next evaluation
+        let mut reloaded = (**config.load()).clone();
+        reloaded.gate.ahi_off_below = -1.0;
+        reloaded.gate.ahi_on_above = -1.0;
+        reloaded.gate.median_ret_15m_off_below_pct = -50.0;
+        reloaded.gate.median_ret_15m_on_above_pct = -50.0;
+        config.store(Arc::new(reloaded.clone()));
+        assert!(gate.evaluate(&hotpath).unwrap().can_buy);
+        assert!(hotpath.get_can_buy());
+
+        reloaded.gate = GateConfig::default();
+        config.store(Arc::new(reloaded.clone()));
+        assert!(!gate.evaluate(&hotpath).unwrap().can_buy);
+        reloaded.gate.enabled = false;
+        config.store(Arc::new(reloaded));
+        assert_eq!(gate.evaluate(&hotpath), None);
+        assert!(hotpath.get_can_buy());
+    }
+}
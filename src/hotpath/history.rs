@@ -0,0 +1,328 @@
+//! Downsampled per-symbol price history for long-horizon returns (5m to 24h)
+//!
+//! Each symbol keeps one closing price per second for the last hour and one
+//! per minute for the last day. Rings are written in place by the ingest
+//! thread (`HotPath::record_history`, outside the measured tick path) with
+//! the same published-index protocol as `PriceSnapshot`, and returns are
+//! computed off the hot path and published as atomics.
+
+use super::PriceSlot;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+/// 1s buckets: one hour plus a minute of slack, so the bucket at or before
+/// `now - 1h` is still held when every second has a trade
+const SECOND_BUCKETS: usize = 3_660;
+
+/// 1m buckets: one day plus five minutes of slack
+const MINUTE_BUCKETS: usize = 1_445;
+
+/// `published` value meaning "not enough history"
+const RETURN_UNSET: u64 = u64::MAX;
+
+/// Returns (%) over the long horizons; None until the history covers them
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Aggregates {
+    pub ret_5m: Option<f64>,
+    pub ret_15m: Option<f64>,
+    pub ret_1h: Option<f64>,
+    pub ret_24h: Option<f64>,
+}
+
+/// Ring of fixed-width time buckets holding each bucket's last price
+#[derive(Debug)]
+struct BucketRing {
+    /// Bucket width in milliseconds
+    bucket_ms: u64,
+    /// Bucket start time (`ts_unix_ms`) and closing price per slot
+    buckets: Box<[PriceSlot]>,
+    /// Sequence number of the next bucket (slot = seq % capacity)
+    head: AtomicU64,
+    /// Sequence number + 1 of the last bucket the writer started writing
+    reserved: AtomicU64,
+}
+
+/// Bucket starts considered by `BucketRing::return_over`
+#[derive(Debug, Clone, Copy)]
+struct Horizon {
+    horizon_ms: u64,
+    /// Buckets starting before this are evicted
+    evicted_before: u64,
+}
+
+impl BucketRing {
+    fn new(bucket_ms: u64, capacity: usize) -> Self {
+        Self {
+            bucket_ms,
+            buckets: (0..capacity).map(|_| PriceSlot::default()).collect(),
+            head: AtomicU64::new(0),
+            reserved: AtomicU64::new(0),
+        }
+    }
+
+    fn capacity(&self) -> u64 {
+        self.buckets.len() as u64
+    }
+
+    fn slot(&self, seq: u64) -> &PriceSlot {
+        &self.buckets[(seq % self.capacity()) as usize]
+    }
+
+    /// Set the close of the bucket holding `ts_unix_ms` (one writer at a
+    /// time). Timestamps before the newest bucket update the newest bucket.
+    fn record(&self, px_e8: u64, ts_unix_ms: u64) {
+        let start = ts_unix_ms - ts_unix_ms % self.bucket_ms;
+        let head = self.head.load(Ordering::Relaxed);
+        if head > 0 {
+            let newest = self.slot(head - 1);
+            if start <= newest.ts_unix_ms.load(Ordering::Relaxed) {
+                // Same bucket: only the close moves, the start is unchanged
+                newest.px_e8.store(px_e8, Ordering::Relaxed);
+                return;
+            }
+        }
+
+        self.reserved.store(head + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        let slot = self.slot(head);
+        slot.px_e8.store(px_e8, Ordering::Relaxed);
+        slot.ts_unix_ms.store(start, Ordering::Relaxed);
+        self.head.store(head + 1, Ordering::Release);
+    }
+
+    /// Return (%) from the close of the newest bucket starting at or before
+    /// `now - horizon_ms` to the latest close; None if the ring does not
+    /// reach back that far or that bucket was evicted
+    fn return_over(&self, horizon: Horizon, now_ms: u64) -> Option<f64> {
+        let target_ts = now_ms.checked_sub(horizon.horizon_ms)?;
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let oldest = head.saturating_sub(self.capacity());
+
+            // Binary search for the first bucket starting after `target_ts`
+            let (mut lo, mut hi) = (oldest, head);
+            let mut lowest = head;
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                lowest = lowest.min(mid);
+                if self.slot(mid).ts_unix_ms.load(Ordering::Relaxed) <= target_ts {
+                    lo = mid + 1;
+                } else {
+                    hi = mid;
+                }
+            }
+            let prices = (lo > oldest).then(|| {
+                lowest = lowest.min(lo - 1);
+                let start = self.slot(lo - 1).ts_unix_ms.load(Ordering::Relaxed);
+                (
+                    start,
+                    self.slot(lo - 1).px_e8.load(Ordering::Relaxed),
+                    self.slot(head - 1).px_e8.load(Ordering::Relaxed),
+                )
+            });
+
+            fence(Ordering::Acquire);
+            // Retry if the writer has started reusing a slot we read
+            if self.reserved.load(Ordering::Relaxed) > lowest + self.capacity() {
+                continue;
+            }
+            let (start, old_px, new_px) = prices?;
+            if start < horizon.evicted_before || old_px == 0 {
+                return None;
+            }
+            return Some(((new_px as f64 - old_px as f64) / old_px as f64) * 100.0);
+        }
+    }
+}
+
+/// Multi-resolution price history and published long-horizon returns for
+/// one symbol
+#[derive(Debug)]
+pub struct PriceHistory {
+    /// 1s closes for the last hour (5m, 15m, 1h returns)
+    seconds: BucketRing,
+    /// 1m closes for the last day (24h return)
+    minutes: BucketRing,
+    /// Last refreshed returns as f64 bits (5m, 15m, 1h, 24h)
+    published: [AtomicU64; 4],
+    /// Trades before this time are ignored (set by `evict_through`)
+    evicted_before: AtomicU64,
+}
+
+impl PriceHistory {
+    /// Create an empty history (rings are pre-allocated)
+    pub fn new() -> Self {
+        Self {
+            seconds: BucketRing::new(1_000, SECOND_BUCKETS),
+            minutes: BucketRing::new(60_000, MINUTE_BUCKETS),
+            published: std::array::from_fn(|_| AtomicU64::new(RETURN_UNSET)),
+            evicted_before: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a trade price (tick writer; O(1), no allocations)
+    pub fn record(&self, px_e8: u64, ts_unix_ms: u64) {
+        self.seconds.record(px_e8, ts_unix_ms);
+        self.minutes.record(px_e8, ts_unix_ms);
+    }
+
+    /// Compute the long-horizon returns as of `now_ms` (off hot-path)
+    pub fn compute(&self, now_ms: u64) -> Aggregates {
+        const MINUTE_MS: u64 = 60_000;
+        let evicted_before = self.evicted_before.load(Ordering::Relaxed);
+        let horizon = |minutes: u64| Horizon {
+            horizon_ms: minutes * MINUTE_MS,
+            evicted_before,
+        };
+        Aggregates {
+            ret_5m: self.seconds.return_over(horizon(5), now_ms),
+            ret_15m: self.seconds.return_over(horizon(15), now_ms),
+            ret_1h: self.seconds.return_over(horizon(60), now_ms),
+            ret_24h: self.minutes.return_over(horizon(24 * 60), now_ms),
+        }
+    }
+
+    /// Drop every trade up to `ts_unix_ms` from the returns (off hot-path;
+    /// the rings are left to the writer and refill as trading resumes).
+    /// Buckets holding an evicted trade are evicted whole, so trades must
+    /// resume at least one bucket width (1m) later to count. Returns false
+    /// if they were already evicted.
+    pub fn evict_through(&self, ts_unix_ms: u64) -> bool {
+        let previous = self
+            .evicted_before
+            .fetch_max(ts_unix_ms + 1, Ordering::Relaxed);
+        if previous > ts_unix_ms {
+            return false;
+        }
+        for slot in &self.published {
+            slot.store(RETURN_UNSET, Ordering::Relaxed);
+        }
+        true
+    }
+
+    /// Compute the returns as of `now_ms` and publish them for `published`
+    pub fn refresh(&self, now_ms: u64) -> Aggregates {
+        let aggregates = self.compute(now_ms);
+        let values = [
+            aggregates.ret_5m,
+            aggregates.ret_15m,
+            aggregates.ret_1h,
+            aggregates.ret_24h,
+        ];
+        for (slot, value) in self.published.iter().zip(values) {
+            slot.store(value.map_or(RETURN_UNSET, f64::to_bits), Ordering::Relaxed);
+        }
+        aggregates
+    }
+
+    /// Returns from the last `refresh` (hot-path safe: four atomic loads)
+    pub fn published(&self) -> Aggregates {
+        let load = |i: usize| match self.published[i].load(Ordering::Relaxed) {
+            RETURN_UNSET => None,
+            bits => Some(f64::from_bits(bits)),
+        };
+        Aggregates {
+            ret_5m: load(0),
+            ret_15m: load(1),
+            ret_1h: load(2),
+            ret_24h: load(3),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_TS: u64 = 1_700_000_000_000;
+
+    fn assert_return(actual: Option<f64>, old_px: u64, new_px: u64) {
+        let expected = (new_px as f64 - old_px as f64) / old_px as f64 * 100.0;
+        let actual = actual.expect("return missing");
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_returns_from_second_buckets() {
+        // One trade per second for two hours, price rising 1000 e8 per second
+        let history = PriceHistory::new();
+        let px_at = |sec: u64| 100_000_000 + sec * 1_000;
+        for sec in 0..7_200 {
+            history.record(px_at(sec), BASE_TS + sec * 1_000 + 250);
+        }
+        let now_sec = 7_199;
+        let now = BASE_TS + now_sec * 1_000 + 250;
+
+        let aggregates = history.compute(now);
+        assert_return(aggregates.ret_5m, px_at(now_sec - 300), px_at(now_sec));
+        assert_return(aggregates.ret_15m, px_at(now_sec - 900), px_at(now_sec));
+        assert_return(aggregates.ret_1h, px_at(now_sec - 3_600), px_at(now_sec));
+        // Two hours of data cannot answer a 24h return
+        assert_eq!(aggregates.ret_24h, None);
+    }
+
+    #[test]
+    fn test_24h_return_from_minute_buckets() {
+        // One trade every 30s for 25 hours
+        let history = PriceHistory::new();
+        let px_at = |step: u64| 100_000_000 + step * 500;
+        let steps = 25 * 120;
+        for step in 0..steps {
+            history.record(px_at(step), BASE_TS + step * 30_000);
+        }
+        let last = steps - 1;
+        let now = BASE_TS + last * 30_000;
+
+        // BASE_TS is 20s into a minute, so each minute bucket holds an even
+        // step and the odd step after it; 24h back is odd and closes its bucket
+        let aggregates = history.compute(now);
+        assert_return(aggregates.ret_24h, px_at(last - 2_880), px_at(last));
+        assert_return(aggregates.ret_1h, px_at(last - 120), px_at(last));
+    }
+
+    #[test]
+    fn test_bucket_close_and_publish() {
+        let history = PriceHistory::new();
+        assert_eq!(history.published(), Aggregates::default());
+
+        // Several trades in one second: the bucket closes at the last one
+        history.record(100_000_000, BASE_TS);
+        history.record(90_000_000, BASE_TS + 400);
+        history.record(95_000_000, BASE_TS + 999);
+        history.record(99_000_000, BASE_TS + 300_000);
+        let now = BASE_TS + 300_000;
+
+        let aggregates = history.refresh(now);
+        assert_return(aggregates.ret_5m, 95_000_000, 99_000_000);
+        assert_eq!(aggregates.ret_15m, None);
+        assert_eq!(history.published(), aggregates);
+    }
+
+    #[test]
+    fn test_evicted_trades_are_ignored() {
+        let history = PriceHistory::new();
+        for sec in 0..600 {
+            history.record(100_000_000 + sec * 1_000, BASE_TS + sec * 1_000);
+        }
+        let last_ts = BASE_TS + 599_000;
+        assert!(history.refresh(last_ts).ret_5m.is_some());
+
+        assert!(history.evict_through(last_ts));
+        assert!(!history.evict_through(last_ts));
+        assert_eq!(history.published(), Aggregates::default());
+        assert_eq!(history.compute(last_ts), Aggregates::default());
+
+        // Trading resumes an hour later; returns rebuild from new trades only
+        let resume_ts = last_ts + 3_600_000;
+        for sec in 0..=300 {
+            history.record(200_000_000 + sec * 1_000, resume_ts + sec * 1_000);
+        }
+        let aggregates = history.compute(resume_ts + 300_000);
+        assert_return(aggregates.ret_5m, 200_000_000, 200_300_000);
+        assert_eq!(aggregates.ret_1h, None);
+    }
+}
@@ -1,153 +1,223 @@
+use crate::config::{Config, SymbolOverride};
 use crate::data_feed::TradeTick;
+use crate::risk::{Risk, SlotReservation};
+use crate::symbols::SymbolRegistry;
 use arc_swap::ArcSwap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use history::{Aggregates, PriceHistory};
+use std::ops::Range;
+use std::sync::atomic::{fence, AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
-/// Price snapshot for a symbol (60-second window)
-#[derive(Debug, Clone)]
+pub mod history;
+
+/// Ring slots reserved per second of window (assume max 100 ticks/sec)
+pub const RING_SLOTS_PER_SEC: u64 = 100;
+
+/// Largest per-symbol price ring (10 minutes at 100 ticks/sec)
+pub const MAX_RING_CAPACITY: u64 = 60_000;
+
+/// Price ring for a symbol, updated in place by a single writer while any
+/// number of readers compute returns without locks.
+///
+/// Timestamps are kept non-decreasing in insertion order, so the oldest
+/// point inside the return window is found by moving a tail cursor forward
+/// as points arrive instead of scanning the ring. The writer publishes each
+/// point by advancing `head`; a reader retries only if the writer wrapped
+/// around onto a slot it was reading, so readers never block the writer.
+#[derive(Debug)]
 pub struct PriceSnapshot {
     /// Ring buffer of prices (fixed size, pre-allocated)
-    prices: Vec<PricePoint>,
-    /// Current write index in the ring
-    write_idx: usize,
-    /// Number of valid entries
-    count: usize,
-    /// Window duration in milliseconds (60s)
-    window_ms: u64,
-    /// 15-minute aggregate return (computed off hot-path)
-    ret_15m: Option<f64>,
-    /// 1-hour aggregate return (computed off hot-path)
-    ret_1h: Option<f64>,
+    prices: Box<[PriceSlot]>,
+    /// Sequence number of the next write (slot = seq % capacity); points
+    /// below it are visible to readers
+    head: AtomicU64,
+    /// Sequence number + 1 of the last point the writer started writing
+    reserved: AtomicU64,
+    /// Sequence number of the oldest point within `window_ms` of the newest
+    tail: AtomicU64,
+    /// Window tracked by `tail` in milliseconds
+    window_ms: AtomicU64,
 }
 
-#[derive(Debug, Clone, Copy)]
-struct PricePoint {
-    px_e8: u64,
-    ts_unix_ms: u64,
+/// One ring entry; the fields are atomics so a lapped reader sees stale or
+/// mixed values (and retries) instead of a data race
+#[derive(Debug, Default)]
+struct PriceSlot {
+    px_e8: AtomicU64,
+    ts_unix_ms: AtomicU64,
 }
 
 impl PriceSnapshot {
     /// Create a new price snapshot with fixed capacity
     pub fn new(window_secs: u64) -> Self {
-        let capacity = (window_secs * 100) as usize; // Assume max 100 ticks/sec
+        let capacity = (window_secs * RING_SLOTS_PER_SEC) as usize;
         Self {
-            prices: vec![
-                PricePoint {
-                    px_e8: 0,
-                    ts_unix_ms: 0
-                };
-                capacity
-            ],
-            write_idx: 0,
-            count: 0,
-            window_ms: window_secs * 1000,
-            ret_15m: None,
-            ret_1h: None,
+            prices: (0..capacity).map(|_| PriceSlot::default()).collect(),
+            head: AtomicU64::new(0),
+            reserved: AtomicU64::new(0),
+            tail: AtomicU64::new(0),
+            window_ms: AtomicU64::new(window_secs * 1000),
         }
     }
 
-    /// Add a new price point (called off hot-path)
-    pub fn add(&mut self, px_e8: u64, ts_unix_ms: u64) {
-        self.prices[self.write_idx] = PricePoint { px_e8, ts_unix_ms };
-        self.write_idx = (self.write_idx + 1) % self.prices.len();
-        if self.count < self.prices.len() {
-            self.count += 1;
-        }
+    fn capacity(&self) -> u64 {
+        self.prices.len() as u64
     }
 
-    /// Compute 60-second return (hot-path read-only, zero allocations)
-    pub fn compute_return_60s(&self, current_ts_ms: u64) -> Option<f64> {
-        if self.count < 2 {
-            return None;
-        }
+    /// Number of valid entries when the next write is `head`
+    fn len(&self, head: u64) -> u64 {
+        head.min(self.capacity())
+    }
 
-        // Find oldest and newest valid prices within 60s window
-        let cutoff_ts = current_ts_ms.saturating_sub(self.window_ms);
-        let mut oldest_price: Option<u64> = None;
-        let mut oldest_ts = u64::MAX;
-        let mut newest_price: Option<u64> = None;
-        let mut newest_ts = 0u64;
-
-        // Scan ring buffer for valid prices (no allocations)
-        for i in 0..self.count {
-            let point = &self.prices[i];
-            if point.ts_unix_ms >= cutoff_ts {
-                // Track oldest price in window
-                if point.ts_unix_ms < oldest_ts {
-                    oldest_ts = point.ts_unix_ms;
-                    oldest_price = Some(point.px_e8);
-                }
-                // Track newest price in window
-                if point.ts_unix_ms > newest_ts {
-                    newest_ts = point.ts_unix_ms;
-                    newest_price = Some(point.px_e8);
-                }
-            }
-        }
+    /// Slot holding sequence number `seq`
+    fn slot(&self, seq: u64) -> &PriceSlot {
+        &self.prices[(seq % self.capacity()) as usize]
+    }
 
-        if let (Some(old_px), Some(new_px)) = (oldest_price, newest_price) {
-            if old_px > 0 {
-                let ret = ((new_px as f64 - old_px as f64) / old_px as f64) * 100.0;
-                return Some(ret);
-            }
-        }
+    fn ts_at(&self, seq: u64) -> u64 {
+        self.slot(seq).ts_unix_ms.load(Ordering::Relaxed)
+    }
 
-        None
+    fn px_at(&self, seq: u64) -> u64 {
+        self.slot(seq).px_e8.load(Ordering::Relaxed)
     }
 
-    /// Compute return over a longer window (off hot-path)
-    fn compute_return_window(&self, window_secs: u64, current_ts_ms: u64) -> Option<f64> {
-        if self.count < 2 {
-            return None;
+    /// Sequence number of the oldest point before `head` with
+    /// `ts_unix_ms >= cutoff_ts` (or `head` if there is none), walking from
+    /// `start`; O(1) amortised when `start` is the tail of the same window.
+    /// Also returns the lowest sequence number read.
+    fn seek(&self, head: u64, start: u64, cutoff_ts: u64) -> (u64, u64) {
+        let oldest = head - self.len(head);
+        let mut seq = start.clamp(oldest, head);
+        let mut lowest = seq;
+        // Wider window than the tracked one: walk back
+        while seq > oldest && self.ts_at(seq - 1) >= cutoff_ts {
+            seq -= 1;
         }
-
-        let cutoff_ts = current_ts_ms.saturating_sub(window_secs * 1000);
-        let mut oldest_price: Option<u64> = None;
-        let mut oldest_ts = u64::MAX;
-        let mut newest_price: Option<u64> = None;
-        let mut newest_ts = 0u64;
-
-        for i in 0..self.count {
-            let point = &self.prices[i];
-            if point.ts_unix_ms >= cutoff_ts {
-                if point.ts_unix_ms < oldest_ts {
-                    oldest_ts = point.ts_unix_ms;
-                    oldest_price = Some(point.px_e8);
-                }
-                if point.ts_unix_ms > newest_ts {
-                    newest_ts = point.ts_unix_ms;
-                    newest_price = Some(point.px_e8);
-                }
-            }
+        if seq > oldest {
+            lowest = lowest.min(seq - 1);
         }
+        // Narrower window or a later timestamp: walk forward
+        while seq < head && self.ts_at(seq) < cutoff_ts {
+            seq += 1;
+        }
+        (seq, lowest.min(seq))
+    }
 
-        if let (Some(old_px), Some(new_px)) = (oldest_price, newest_price) {
-            if old_px > 0 {
-                let ret = ((new_px as f64 - old_px as f64) / old_px as f64) * 100.0;
-                return Some(ret);
+    /// Run `read` on the ring as of the current `head`; `read` returns its
+    /// result and the lowest sequence number it looked at. Retried if the
+    /// writer has since started overwriting that slot.
+    fn read<T>(&self, read: impl Fn(u64) -> (T, u64)) -> T {
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let (value, lowest) = read(head);
+            // Pairs with the writer's fence: a slot value we read implies
+            // its reservation is visible below
+            fence(Ordering::Acquire);
+            // The slot of `seq` is reused by the write of `seq + capacity`
+            if self.reserved.load(Ordering::Relaxed) <= lowest + self.capacity() {
+                return value;
             }
         }
+    }
 
-        None
+    /// Add a new price point in place (called off hot-path, one writer at a
+    /// time). A timestamp older than the newest point is clamped to it to
+    /// keep the ring ordered.
+    pub fn add(&self, px_e8: u64, ts_unix_ms: u64) {
+        let head = self.head.load(Ordering::Relaxed);
+        let ts_unix_ms = match head {
+            0 => ts_unix_ms,
+            head => ts_unix_ms.max(self.ts_at(head - 1)),
+        };
+
+        self.reserved.store(head + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        let slot = self.slot(head);
+        slot.px_e8.store(px_e8, Ordering::Relaxed);
+        slot.ts_unix_ms.store(ts_unix_ms, Ordering::Relaxed);
+        self.head.store(head + 1, Ordering::Release);
+
+        let cutoff_ts = ts_unix_ms.saturating_sub(self.window_ms.load(Ordering::Relaxed));
+        let (tail, _) = self.seek(head + 1, self.tail.load(Ordering::Relaxed), cutoff_ts);
+        self.tail.store(tail, Ordering::Release);
     }
 
-    /// Update aggregate returns (15m and 1h) - called off hot-path
-    pub fn update_aggregates(&mut self, current_ts_ms: u64) {
-        self.ret_15m = self.compute_return_window(15 * 60, current_ts_ms);
-        self.ret_1h = self.compute_return_window(60 * 60, current_ts_ms);
+    /// Timestamp of the newest point, if any
+    pub fn newest_ts(&self) -> Option<u64> {
+        self.read(|head| match head {
+            0 => (None, 0),
+            head => (Some(self.ts_at(head - 1)), head - 1),
+        })
     }
 
-    /// Get 15-minute return (precomputed, hot-path safe)
-    #[allow(dead_code)]
-    pub fn get_return_15m(&self) -> Option<f64> {
-        self.ret_15m
+    /// Change the window the tail cursor tracks (takes effect on the next add)
+    pub fn set_window_secs(&self, window_secs: u64) {
+        self.window_ms.store(window_secs * 1000, Ordering::Relaxed);
     }
 
-    /// Get 1-hour return (precomputed, hot-path safe)
-    #[allow(dead_code)]
-    pub fn get_return_1h(&self) -> Option<f64> {
-        self.ret_1h
+    /// Compute return over `window_secs` (hot-path read-only, zero allocations,
+    /// O(1) amortised when `window_secs` is the tracked window)
+    pub fn compute_return_window(&self, window_secs: u64, current_ts_ms: u64) -> Option<f64> {
+        let cutoff_ts = current_ts_ms.saturating_sub(window_secs * 1000);
+        self.read(|head| {
+            if self.len(head) < 2 {
+                return (None, head);
+            }
+            let (oldest, lowest) = self.seek(head, self.tail.load(Ordering::Acquire), cutoff_ts);
+            if oldest == head {
+                return (None, lowest);
+            }
+            let old_px = self.px_at(oldest);
+            let new_px = self.px_at(head - 1);
+            if old_px == 0 {
+                return (None, lowest);
+            }
+            let ret = ((new_px as f64 - old_px as f64) / old_px as f64) * 100.0;
+            (Some(ret), lowest)
+        })
+    }
+
+    /// Return over `window_secs` by scanning every entry (the pre-cursor
+    /// implementation); kept as a reference for benchmarks and tests
+    pub fn compute_return_scan(&self, window_secs: u64, current_ts_ms: u64) -> Option<f64> {
+        let cutoff_ts = current_ts_ms.saturating_sub(window_secs * 1000);
+        self.read(|head| {
+            let count = self.len(head) as usize;
+            let lowest = head - count as u64;
+            if count < 2 {
+                return (None, lowest);
+            }
+
+            let mut oldest_price: Option<u64> = None;
+            let mut oldest_ts = u64::MAX;
+            let mut newest_price: Option<u64> = None;
+            let mut newest_ts = 0u64;
+
+            for slot in &self.prices[..count] {
+                let ts_unix_ms = slot.ts_unix_ms.load(Ordering::Relaxed);
+                if ts_unix_ms >= cutoff_ts {
+                    if ts_unix_ms < oldest_ts {
+                        oldest_ts = ts_unix_ms;
+                        oldest_price = Some(slot.px_e8.load(Ordering::Relaxed));
+                    }
+                    if ts_unix_ms > newest_ts {
+                        newest_ts = ts_unix_ms;
+                        newest_price = Some(slot.px_e8.load(Ordering::Relaxed));
+                    }
+                }
+            }
+
+            if let (Some(old_px), Some(new_px)) = (oldest_price, newest_price) {
+                if old_px > 0 {
+                    let ret = ((new_px as f64 - old_px as f64) / old_px as f64) * 100.0;
+                    return (Some(ret), lowest);
+                }
+            }
+
+            (None, lowest)
+        })
     }
 }
 
@@ -159,69 +229,266 @@ pub struct TriggerEvent {
     pub ts_unix_ms: u64,
     pub return_pct: f64,
     pub price_e8: u64,
+    /// Per-symbol cap on the quote amount to buy (None = the slot's allocation)
+    pub max_position_quote_e8: Option<u64>,
+    /// Position slot held for this trigger until its position ends
+    pub slot: SlotReservation,
+}
+
+/// `threshold_bits` value meaning "use the global threshold"
+const THRESHOLD_UNSET: u64 = u64::MAX;
+
+/// `triggered_at_ms` value of a symbol free to trigger
+const ARMED: u64 = u64::MAX;
+
+/// Per-symbol overrides as atomics so the hot path reads them without locks
+#[derive(Debug)]
+struct SymbolParams {
+    /// Return threshold as f64 bits (THRESHOLD_UNSET = global threshold)
+    threshold_bits: AtomicU64,
+    /// Return window in seconds (0 = full ring window)
+    window_secs: AtomicU64,
+    /// false = never trigger on this symbol
+    enabled: AtomicBool,
+    /// Quote amount cap in e8 (0 = no cap)
+    max_position_quote_e8: AtomicU64,
+}
+
+impl SymbolParams {
+    fn new() -> Self {
+        Self {
+            threshold_bits: AtomicU64::new(THRESHOLD_UNSET),
+            window_secs: AtomicU64::new(0),
+            enabled: AtomicBool::new(true),
+            max_position_quote_e8: AtomicU64::new(0),
+        }
+    }
+
+    /// Publish `overrides` (None = global defaults)
+    fn store(&self, overrides: Option<&SymbolOverride>) {
+        let overrides = overrides.copied().unwrap_or_default();
+        self.threshold_bits.store(
+            overrides
+                .return_threshold_pct
+                .map_or(THRESHOLD_UNSET, f64::to_bits),
+            Ordering::Relaxed,
+        );
+        self.window_secs
+            .store(overrides.price_window_secs.unwrap_or(0), Ordering::Relaxed);
+        self.enabled
+            .store(overrides.enabled.unwrap_or(true), Ordering::Relaxed);
+        self.max_position_quote_e8.store(
+            overrides.max_position_quote_e8.unwrap_or(0),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// Price snapshot and trigger overrides for one symbol_id
+struct SymbolSlot {
+    /// Price ring (updated in place, lock-free reads)
+    snapshot: PriceSnapshot,
+    /// Downsampled 1h/24h history and published long-horizon returns
+    history: PriceHistory,
+    /// Per-symbol trigger parameters
+    params: SymbolParams,
+    /// Tick time of the last trigger while re-triggers are suppressed
+    /// (ARMED otherwise); written only by the symbol's hot-path thread
+    triggered_at_ms: AtomicU64,
 }
 
 /// Hot-path processor for tick-to-trigger logic
 pub struct HotPath {
     /// Global flag to enable/disable buying (atomic for lock-free access)
     can_buy: Arc<AtomicBool>,
-    /// Return threshold for triggering
-    threshold_pct: f64,
-    /// Price snapshots per symbol (Arc-swapped for lock-free reads)
-    snapshots: Vec<ArcSwap<PriceSnapshot>>,
-    /// Maximum symbols
-    max_symbols: usize,
+    /// Live config (return threshold); swapped by the config watcher
+    config: Arc<ArcSwap<Config>>,
+    /// Price snapshot and overrides per symbol_id, starting at `first_symbol_id`
+    slots: Vec<SymbolSlot>,
+    /// symbol_id of `slots[0]` (non-zero for shard slices)
+    first_symbol_id: u32,
+    /// Ring window in seconds (the return window of symbols without an override)
+    window_secs: u64,
+    /// Position slots a trigger must reserve and the buying freeze (shared
+    /// across shards)
+    risk: Arc<Risk>,
+    /// Ticks over the threshold dropped while their symbol was suppressed
+    suppressed: AtomicU64,
 }
 
 impl HotPath {
-    /// Create a new hot-path processor
-    pub fn new(max_symbols: usize, threshold_pct: f64, window_secs: u64) -> Self {
-        let snapshots: Vec<ArcSwap<PriceSnapshot>> = (0..max_symbols)
-            .map(|_| ArcSwap::new(Arc::new(PriceSnapshot::new(window_secs))))
+    /// Create a new hot-path processor; ring sizes and symbol count are fixed
+    /// from the config at creation, the threshold is read live on every tick
+    pub fn new(config: Arc<ArcSwap<Config>>) -> Self {
+        let max_symbols = config.load().max_symbols as u32;
+        Self::with_range(config, 0..max_symbols)
+    }
+
+    /// Create a hot-path processor owning only `symbol_ids` (one shard's slice);
+    /// ticks for other symbols never trigger
+    pub fn with_range(config: Arc<ArcSwap<Config>>, symbol_ids: Range<u32>) -> Self {
+        let window_secs = config.load().price_window_secs;
+        let risk = Arc::new(Risk::new(&config.load()));
+        let slots: Vec<SymbolSlot> = symbol_ids
+            .clone()
+            .map(|_| SymbolSlot {
+                snapshot: PriceSnapshot::new(window_secs),
+                history: PriceHistory::new(),
+                params: SymbolParams::new(),
+                triggered_at_ms: AtomicU64::new(ARMED),
+            })
             .collect();
 
         Self {
             can_buy: Arc::new(AtomicBool::new(true)),
-            threshold_pct,
-            snapshots,
-            max_symbols,
+            config,
+            slots,
+            first_symbol_id: symbol_ids.start,
+            window_secs,
+            risk,
+            suppressed: AtomicU64::new(0),
         }
     }
 
-    /// Update snapshot for a symbol (off hot-path)
+    /// Share `risk` with other hot-path slices and the execution engine
+    /// (replaces the processor's own; call before processing ticks)
+    pub fn set_risk(&mut self, risk: Arc<Risk>) {
+        self.risk = risk;
+    }
+
+    /// Position slots and freeze the hot path checks
+    pub fn risk(&self) -> &Arc<Risk> {
+        &self.risk
+    }
+
+    /// Slot of `symbol_id` if this processor owns it
+    fn slot(&self, symbol_id: u32) -> Option<&SymbolSlot> {
+        let index = symbol_id.checked_sub(self.first_symbol_id)?;
+        self.slots.get(index as usize)
+    }
+
+    /// Resolve `config.symbols` through the registry and publish them per
+    /// symbol_id; symbols without an entry go back to the global parameters.
+    /// Returns override names the registry does not know (off hot-path).
+    pub fn apply_symbol_overrides(
+        &self,
+        config: &Config,
+        registry: &SymbolRegistry,
+    ) -> Vec<String> {
+        let mut resolved: Vec<Option<&SymbolOverride>> = vec![None; self.slots.len()];
+        let mut unknown = Vec::new();
+        for (symbol, overrides) in &config.symbols {
+            let Some(id) = registry.id_of(symbol) else {
+                unknown.push(symbol.clone());
+                continue;
+            };
+            // Symbols owned by other shards are not unknown
+            if let Some(index) = id.checked_sub(self.first_symbol_id) {
+                if let Some(entry) = resolved.get_mut(index as usize) {
+                    *entry = Some(overrides);
+                }
+            }
+        }
+        for (slot, overrides) in self.slots.iter().zip(resolved) {
+            slot.params.store(overrides);
+        }
+        unknown
+    }
+
+    /// Return window of a symbol: its override or the ring window
+    fn window_secs(&self, params: &SymbolParams) -> u64 {
+        match params.window_secs.load(Ordering::Relaxed) {
+            0 => self.window_secs,
+            window_secs => window_secs,
+        }
+    }
+
+    /// Update snapshot for a symbol in place (off hot-path; one writer per
+    /// symbol at a time, readers are never blocked)
     pub fn update_snapshot(&self, symbol_id: u32, px_e8: u64, ts_unix_ms: u64) {
-        if (symbol_id as usize) < self.max_symbols {
-            let current = self.snapshots[symbol_id as usize].load();
-            let mut new_snapshot = (**current).clone();
-            new_snapshot.add(px_e8, ts_unix_ms);
-            self.snapshots[symbol_id as usize].store(Arc::new(new_snapshot));
+        if let Some(slot) = self.slot(symbol_id) {
+            slot.snapshot
+                .set_window_secs(self.window_secs(&slot.params));
+            slot.snapshot.add(px_e8, ts_unix_ms);
+        }
+    }
+
+    /// Record a trade into a symbol's long-horizon history (ingest side, off
+    /// the measured tick path; one writer per symbol at a time)
+    pub fn record_history(&self, symbol_id: u32, px_e8: u64, ts_unix_ms: u64) {
+        if let Some(slot) = self.slot(symbol_id) {
+            slot.history.record(px_e8, ts_unix_ms);
         }
     }
 
     /// Process a tick on the hot-path (zero allocations, single-threaded)
     pub fn process_tick(&self, tick: &TradeTick) -> Option<TriggerEvent> {
         // Check can_buy flag (atomic load, relaxed ordering for performance)
-        if !self.can_buy.load(Ordering::Relaxed) || (tick.symbol_id as usize) >= self.max_symbols {
+        if !self.can_buy.load(Ordering::Relaxed) || self.risk.freeze.is_frozen(tick.ts_unix_ms) {
+            return None;
+        }
+        let slot = self.slot(tick.symbol_id)?;
+        let params = &slot.params;
+        if !params.enabled.load(Ordering::Relaxed)
+            || self.risk.bans.is_banned(tick.symbol_id, tick.ts_unix_ms)
+        {
             return None;
         }
 
-        // Load snapshot (lock-free read via arc-swap, immutable snapshot)
-        let snapshot = self.snapshots[tick.symbol_id as usize].load();
-
-        // Compute 60s return (no allocations, read-only operation)
-        if let Some(ret_60s) = snapshot.compute_return_60s(tick.ts_unix_ms) {
-            // Check trigger condition
-            if ret_60s >= self.threshold_pct {
-                return Some(TriggerEvent {
-                    symbol_id: tick.symbol_id,
-                    ts_unix_ms: tick.ts_unix_ms,
-                    return_pct: ret_60s,
-                    price_e8: tick.px_e8,
-                });
+        // Compute windowed return (no allocations, read-only operation)
+        let ret = slot
+            .snapshot
+            .compute_return_window(self.window_secs(params), tick.ts_unix_ms)?;
+        let config = self.config.load();
+
+        // Suppressed since the last trigger: re-arm once the cool-down is
+        // over or the return has fallen below the re-arm level
+        let triggered_at_ms = slot.triggered_at_ms.load(Ordering::Relaxed);
+        if triggered_at_ms != ARMED {
+            let cooled = tick.ts_unix_ms
+                >= triggered_at_ms.saturating_add(config.retrigger_cooldown_secs * 1_000);
+            let fell = config.rearm_below_pct.is_some_and(|level| ret < level);
+            if !cooled && !fell {
+                if ret >= self.threshold_pct(params, &config) {
+                    self.suppressed.fetch_add(1, Ordering::Relaxed);
+                }
+                return None;
             }
+            slot.triggered_at_ms.store(ARMED, Ordering::Relaxed);
         }
 
-        None
+        // Check trigger condition against the symbol's or the live global threshold
+        if ret < self.threshold_pct(params, &config) {
+            return None;
+        }
+        // No free slot: the trigger never becomes an order
+        let reservation = self.risk.slots.reserve(tick.symbol_id)?;
+        slot.triggered_at_ms
+            .store(tick.ts_unix_ms, Ordering::Relaxed);
+        Some(TriggerEvent {
+            symbol_id: tick.symbol_id,
+            ts_unix_ms: tick.ts_unix_ms,
+            return_pct: ret,
+            price_e8: tick.px_e8,
+            max_position_quote_e8: match params.max_position_quote_e8.load(Ordering::Relaxed) {
+                0 => None,
+                cap => Some(cap),
+            },
+            slot: reservation,
+        })
+    }
+
+    /// Trigger threshold of a symbol: its override or the global one
+    fn threshold_pct(&self, params: &SymbolParams, config: &Config) -> f64 {
+        match params.threshold_bits.load(Ordering::Relaxed) {
+            THRESHOLD_UNSET => config.return_threshold_pct,
+            bits => f64::from_bits(bits),
+        }
+    }
+
+    /// Ticks over the threshold dropped by re-trigger suppression so far
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
     }
 
     /// Set global can_buy flag (atomic store, can be called from risk/gate task)
@@ -230,20 +497,45 @@ impl HotPath {
     }
 
     /// Get can_buy status
-    #[allow(dead_code)]
     pub fn get_can_buy(&self) -> bool {
         self.can_buy.load(Ordering::Relaxed)
     }
 
-    /// Update aggregates for a symbol (off hot-path maintenance task)
-    pub fn update_aggregates(&self, symbol_id: u32, current_ts_ms: u64) {
-        if (symbol_id as usize) < self.max_symbols {
-            let current = self.snapshots[symbol_id as usize].load();
-            let mut new_snapshot = (**current).clone();
-            new_snapshot.update_aggregates(current_ts_ms);
-            self.snapshots[symbol_id as usize].store(Arc::new(new_snapshot));
+    /// Recompute and publish a symbol's 5m/15m/1h/24h returns from its
+    /// history (off hot-path maintenance task)
+    pub fn update_aggregates(&self, symbol_id: u32, current_ts_ms: u64) -> Option<Aggregates> {
+        self.slot(symbol_id)
+            .map(|slot| slot.history.refresh(current_ts_ms))
+    }
+
+    /// Drop a symbol's history from its long-horizon returns if its last
+    /// trade is older than `stale_before_ms` (off hot-path). Returns true
+    /// if data was evicted by this call.
+    pub fn evict_stale(&self, symbol_id: u32, stale_before_ms: u64) -> bool {
+        let Some(slot) = self.slot(symbol_id) else {
+            return false;
+        };
+        match slot.snapshot.newest_ts() {
+            Some(newest_ts) if newest_ts < stale_before_ms => slot.history.evict_through(newest_ts),
+            _ => false,
         }
     }
+
+    /// Timestamp of a symbol's last trade
+    pub fn last_trade_ms(&self, symbol_id: u32) -> Option<u64> {
+        self.slot(symbol_id)?.snapshot.newest_ts()
+    }
+
+    /// symbol_ids this processor owns
+    pub fn symbol_ids(&self) -> Range<u32> {
+        self.first_symbol_id..self.first_symbol_id + self.slots.len() as u32
+    }
+
+    /// Long-horizon returns of a symbol as of the last `update_aggregates`
+    /// (hot-path safe, for the market gate and strategy)
+    pub fn aggregates(&self, symbol_id: u32) -> Option<Aggregates> {
+        self.slot(symbol_id).map(|slot| slot.history.published())
+    }
 }
 
 /// Latency measurement for a single tick processing
@@ -273,3 +565,382 @@ impl LatencyMeasurement {
         self.end.duration_since(self.start).as_micros() as u64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc_count::allocations_during;
+
+    #[test]
+    fn test_window_cursor_matches_scan() {
+        // 200-slot ring; gaps of 1-30ms so it wraps and the window moves
+        let snapshot = PriceSnapshot::new(2);
+        let mut rng_state = 7u64;
+        let mut ts = 1_700_000_000_000u64;
+        for _ in 0..2_000 {
+            rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
+            ts += 1 + rng_state % 30;
+            snapshot.add(100_000_000 + rng_state % 1_000_000, ts);
+
+            for window_secs in [1, 2] {
+                for now in [ts, ts + 500, ts + 5_000] {
+                    assert_eq!(
+                        snapshot.compute_return_window(window_secs, now),
+                        snapshot.compute_return_scan(window_secs, now),
+                        "window {}s at {}",
+                        window_secs,
+                        now
+                    );
+                }
+            }
+        }
+
+        // Narrower tracked window; wider queries walk back from the cursor
+        snapshot.set_window_secs(1);
+        snapshot.add(100_000_000, ts + 10);
+        assert_eq!(
+            snapshot.compute_return_window(2, ts + 10),
+            snapshot.compute_return_scan(2, ts + 10)
+        );
+    }
+
+    #[test]
+    fn test_out_of_order_timestamp_is_clamped() {
+        let snapshot = PriceSnapshot::new(60);
+        snapshot.add(100_000_000, 1_700_000_005_000);
+        // Older timestamp than the newest point: stored at the newest one
+        snapshot.add(110_000_000, 1_700_000_004_000);
+        let ret = snapshot
+            .compute_return_window(60, 1_700_000_005_000)
+            .unwrap();
+        assert!((ret - 10.0).abs() < 1e-9);
+        assert_eq!(snapshot.compute_return_window(1, 1_700_000_070_000), None);
+    }
+
+    #[test]
+    fn test_steady_state_updates_do_not_allocate() {
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 4,
+            ..Config::default()
+        }));
+        let hotpath = HotPath::new(config);
+        let tick_at = |i: u64| {
+            TradeTick::new(
+                (i % 4) as u32,
+                100_000_000 + (i % 20_000) * 1_000,
+                1_700_000_000_000 + i * 10,
+            )
+        };
+        let run = |ticks: std::ops::Range<u64>| {
+            ticks
+                .filter(|&i| {
+                    let tick = tick_at(i);
+                    hotpath.update_snapshot(tick.symbol_id, tick.px_e8, tick.ts_unix_ms);
+                    hotpath.record_history(tick.symbol_id, tick.px_e8, tick.ts_unix_ms);
+                    // Free the slot at once, as if the take-profit filled
+                    hotpath
+                        .process_tick(&tick)
+                        .is_some_and(|trigger| hotpath.risk().slots.release(&trigger.slot))
+                })
+                .count()
+        };
+
+        // Warm up past ring wrap-around (and the config guard's per-thread setup)
+        run(0..30_000);
+        let (triggers, allocations) = allocations_during(|| run(30_000..60_000));
+        assert!(triggers > 0);
+        assert_eq!(allocations, 0);
+    }
+
+    #[test]
+    fn test_readers_see_consistent_windows_while_writer_runs() {
+        // 200-slot ring, one point per 10ms; the 1s window always spans 101
+        // consecutive points whose prices differ by 100
+        let snapshot = Arc::new(PriceSnapshot::new(2));
+        let newest_ts = Arc::new(AtomicU64::new(0));
+        let price_at = |seq: u64| (100_000_000 + seq, seq * 10);
+        for seq in 0..200 {
+            let (px_e8, ts) = price_at(seq);
+            snapshot.add(px_e8, ts);
+            newest_ts.store(ts, Ordering::Release);
+        }
+
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let (snapshot, newest_ts, done) = (
+                Arc::clone(&snapshot),
+                Arc::clone(&newest_ts),
+                Arc::clone(&done),
+            );
+            std::thread::spawn(move || {
+                let mut reads = 0u64;
+                while !done.load(Ordering::Acquire) || reads == 0 {
+                    let now = newest_ts.load(Ordering::Acquire);
+                    let ret = snapshot.compute_return_window(1, now).unwrap();
+                    // Price gap in e8 units: 100 when current, up to 199 if
+                    // the writer moved on after `now` was read
+                    let gap = ret * 1e6;
+                    assert!((99.0..200.0).contains(&gap), "gap {}", gap);
+                    reads += 1;
+                }
+                reads
+            })
+        };
+
+        for seq in 200..200_000 {
+            let (px_e8, ts) = price_at(seq);
+            snapshot.add(px_e8, ts);
+            newest_ts.store(ts, Ordering::Release);
+        }
+        done.store(true, Ordering::Release);
+        assert!(reader.join().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_threshold_reload_keeps_history() {
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 4,
+            ..Config::default()
+        }));
+        let hotpath = HotPath::new(Arc::clone(&config));
+
+        let base_ts = 1_700_000_000_000;
+        hotpath.update_snapshot(1, 100_000_000, base_ts);
+        hotpath.update_snapshot(1, 103_000_000, base_ts + 10_000);
+        let tick = TradeTick::new(1, 103_000_000, base_ts + 10_000);
+        assert!(hotpath.process_tick(&tick).is_none());
+
+        config.store(Arc::new(Config {
+            return_threshold_pct: 2.5,
+            ..(**config.load()).clone()
+        }));
+        let trigger = hotpath
+            .process_tick(&tick)
+            .expect("3% move over 2.5% threshold");
+        assert_eq!(trigger.symbol_id, 1);
+        assert!((trigger.return_pct - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_range_slice_owns_only_its_symbols() {
+        let config = Arc::new(ArcSwap::from_pointee(Config::default()));
+        let hotpath = HotPath::with_range(config, 10..20);
+
+        let base_ts = 1_700_000_000_000;
+        for id in [9, 10, 19, 20] {
+            hotpath.update_snapshot(id, 100_000_000, base_ts);
+            hotpath.update_snapshot(id, 110_000_000, base_ts + 1_000);
+        }
+        let fired: Vec<u32> = [9, 10, 19, 20]
+            .into_iter()
+            .filter_map(|id| {
+                hotpath.process_tick(&TradeTick::new(id, 110_000_000, base_ts + 1_000))
+            })
+            .map(|trigger| trigger.symbol_id)
+            .collect();
+        assert_eq!(fired, vec![10, 19]);
+    }
+
+    #[test]
+    fn test_trigger_needs_a_free_position_slot() {
+        // No suppression: every tick over the threshold asks for a slot
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 4,
+            retrigger_cooldown_secs: 0,
+            ..Config::default()
+        }));
+        let hotpath = HotPath::new(config);
+        let base_ts = 1_700_000_000_000;
+        for id in 0..4 {
+            hotpath.update_snapshot(id, 100_000_000, base_ts);
+            hotpath.update_snapshot(id, 110_000_000, base_ts + 1_000);
+        }
+        let tick = |id| TradeTick::new(id, 110_000_000, base_ts + 1_000);
+
+        let a = hotpath.process_tick(&tick(0)).unwrap();
+        assert_eq!((a.slot.slot, a.slot.quote_qty_e8), (0, 2_000_000_000));
+        // Symbol 0 already holds A
+        assert!(hotpath.process_tick(&tick(0)).is_none());
+        let b = hotpath.process_tick(&tick(1)).unwrap();
+        assert_eq!(b.slot.slot, 1);
+        assert!(hotpath.process_tick(&tick(2)).is_none());
+        assert_eq!(hotpath.risk().slots.denied(), 2);
+
+        hotpath.risk().slots.release(&a.slot);
+        let c = hotpath.process_tick(&tick(2)).unwrap();
+        assert_eq!((c.symbol_id, c.slot.slot), (2, 0));
+    }
+
+    #[test]
+    fn test_retrigger_suppression() {
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 4,
+            retrigger_cooldown_secs: 60,
+            rearm_below_pct: Some(2.0),
+            ..Config::default()
+        }));
+        let hotpath = HotPath::new(Arc::clone(&config));
+        let base_ts = 1_700_000_000_000;
+        let fire = |px_e8, ts| {
+            hotpath.update_snapshot(0, px_e8, ts);
+            let trigger = hotpath.process_tick(&TradeTick::new(0, px_e8, ts));
+            if let Some(trigger) = &trigger {
+                hotpath.risk().slots.release(&trigger.slot);
+            }
+            trigger.is_some()
+        };
+        fire(100_000_000, base_ts);
+        assert!(fire(110_000_000, base_ts + 1_000));
+
+        // Still pumping: suppressed, the slots are not even asked
+        assert!(!fire(111_000_000, base_ts + 2_000));
+        assert!(!fire(112_000_000, base_ts + 3_000));
+        assert_eq!(hotpath.suppressed(), 2);
+        assert_eq!(hotpath.risk().slots.denied(), 0);
+        // Cool-down over (60s after the trigger); window starts at 110
+        assert!(fire(120_000_000, base_ts + 61_000));
+
+        // The return falls below 2% (window start 111 -> 112): re-armed
+        // before the cool-down is over
+        assert!(!fire(112_000_000, base_ts + 62_000));
+        assert!(fire(125_000_000, base_ts + 63_000));
+        assert!(!fire(126_000_000, base_ts + 63_500));
+
+        // A hot-reloaded cool-down of 0 turns suppression off
+        config.store(Arc::new(Config {
+            max_symbols: 4,
+            retrigger_cooldown_secs: 0,
+            ..Config::default()
+        }));
+        assert!(fire(130_000_000, base_ts + 64_000));
+        assert!(fire(130_000_000, base_ts + 65_000));
+        assert_eq!(hotpath.suppressed(), 3);
+    }
+
+    #[test]
+    fn test_freeze_blocks_triggers_until_it_expires() {
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 4,
+            ..Config::default()
+        }));
+        let hotpath = HotPath::new(config);
+        let base_ts = 1_700_000_000_000;
+        let freeze = &hotpath.risk().freeze;
+        for loss in 0..3 {
+            freeze.record_trade(-100_000_000, base_ts + loss);
+        }
+
+        // Default: 3 losses freeze buying for an hour of trade time
+        for ts in [base_ts + 1_000, base_ts + 3_600_002] {
+            hotpath.update_snapshot(1, 100_000_000, ts - 1_000);
+            hotpath.update_snapshot(1, 110_000_000, ts);
+        }
+        let tick = |ts| TradeTick::new(1, 110_000_000, ts);
+        assert!(hotpath.process_tick(&tick(base_ts + 1_000)).is_none());
+        assert_eq!(hotpath.risk().slots.denied(), 0);
+        assert!(hotpath.process_tick(&tick(base_ts + 3_600_002)).is_some());
+    }
+
+    #[test]
+    fn test_banned_symbol_never_triggers() {
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 4,
+            ..Config::default()
+        }));
+        let hotpath = HotPath::new(config);
+        let base_ts = 1_700_000_000_000;
+        hotpath.risk().bans.set_config_bans([1]);
+        hotpath.risk().bans.ban_after_loss(2, base_ts);
+        for id in 0..3 {
+            hotpath.update_snapshot(id, 100_000_000, base_ts);
+            hotpath.update_snapshot(id, 110_000_000, base_ts + 1_000);
+        }
+
+        let tick = |id| TradeTick::new(id, 110_000_000, base_ts + 1_000);
+        assert!(hotpath.process_tick(&tick(1)).is_none());
+        assert!(hotpath.process_tick(&tick(2)).is_none());
+        // Banned symbols do not take a slot
+        assert_eq!(hotpath.risk().slots.denied(), 0);
+        assert_eq!(hotpath.process_tick(&tick(0)).unwrap().symbol_id, 0);
+
+        hotpath.risk().bans.set_config_bans([]);
+        let trigger = hotpath.process_tick(&tick(1)).unwrap();
+        assert_eq!(trigger.symbol_id, 1);
+        hotpath.risk().slots.release(&trigger.slot);
+
+        // The loss ban lapses with trade time, without an expire pass
+        let after_ban = base_ts + 86_400_000;
+        hotpath.update_snapshot(2, 100_000_000, after_ban - 1_000);
+        let lapsed = TradeTick::new(2, 110_000_000, after_ban);
+        hotpath.update_snapshot(2, lapsed.px_e8, lapsed.ts_unix_ms);
+        assert_eq!(hotpath.process_tick(&lapsed).unwrap().symbol_id, 2);
+    }
+
+    #[test]
+    fn test_symbol_overrides() {
+        let names: Vec<String> = ["AAAUSDT", "BBBUSDT", "CCCUSDT"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let registry = SymbolRegistry::from_symbols(&names, 4).unwrap();
+        let mut config = Config {
+            max_symbols: 4,
+            ..Config::default()
+        };
+        config.symbols.insert(
+            "AAAUSDT".to_string(),
+            SymbolOverride {
+                return_threshold_pct: Some(2.0),
+                max_position_quote_e8: Some(500_000_000),
+                ..SymbolOverride::default()
+            },
+        );
+        config.symbols.insert(
+            "BBBUSDT".to_string(),
+            SymbolOverride {
+                enabled: Some(false),
+                ..SymbolOverride::default()
+            },
+        );
+        config.symbols.insert(
+            "CCCUSDT".to_string(),
+            SymbolOverride {
+                price_window_secs: Some(10),
+                ..SymbolOverride::default()
+            },
+        );
+        config
+            .symbols
+            .insert("ZZZUSDT".to_string(), SymbolOverride::default());
+
+        let hotpath = HotPath::new(Arc::new(ArcSwap::from_pointee(config.clone())));
+        let unknown = hotpath.apply_symbol_overrides(&config, &registry);
+        assert_eq!(unknown, vec!["ZZZUSDT".to_string()]);
+
+        // Every symbol moves +6% over 30s, but only +1% over the last 10s
+        let base_ts = 1_700_000_000_000;
+        for id in 0..3 {
+            hotpath.update_snapshot(id, 100_000_000, base_ts);
+            hotpath.update_snapshot(id, 105_000_000, base_ts + 20_000);
+            hotpath.update_snapshot(id, 106_000_000, base_ts + 30_000);
+        }
+        let tick = |id| TradeTick::new(id, 106_000_000, base_ts + 30_000);
+
+        let a = hotpath
+            .process_tick(&tick(0))
+            .expect("6% over the 2% override");
+        assert_eq!(a.max_position_quote_e8, Some(500_000_000));
+        assert!(hotpath.risk().slots.release(&a.slot));
+        assert!(hotpath.process_tick(&tick(1)).is_none());
+        assert!(hotpath.process_tick(&tick(2)).is_none());
+
+        // Dropping the overrides restores the global 60s window and 5% threshold
+        config.symbols.clear();
+        hotpath.apply_symbol_overrides(&config, &registry);
+        let b = hotpath
+            .process_tick(&tick(1))
+            .expect("6% over the global 5%");
+        assert_eq!(b.max_position_quote_e8, None);
+        assert!(hotpath.process_tick(&tick(2)).is_some());
+    }
+}
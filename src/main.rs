@@ -1,20 +1,47 @@
+#[cfg(test)]
+mod alloc_count;
 mod config;
+mod cpu;
 mod data_feed;
+mod execution;
+mod gate;
 mod hotpath;
+mod maintenance;
 mod metrics;
+mod risk;
 mod sbe_decoder_ffi;
+#[cfg(feature = "rust-sbe")]
+mod sbe_decoder_rust;
+mod shard;
+mod symbols;
+mod ws_feed;
+
+#[cfg(not(any(feature = "c-sbe", feature = "rust-sbe")))]
+compile_error!("enable at least one SBE decoder feature: `c-sbe` or `rust-sbe`");
 
+use arc_swap::ArcSwap;
 use clap::Parser;
-use config::Config;
-use data_feed::TickGenerator;
-use hotpath::{HotPath, LatencyMeasurement};
-use metrics::MetricsCollector;
+use config::{Config, ConfigReloader, ThreadRole};
+use data_feed::{DataFeed, TickGenerator, TradeTick};
+use execution::shadow::ShadowExchange;
+use execution::{ExecError, ExecutionEngine, ExecutionEvent};
+use gate::AltMarketGate;
+use hotpath::{HotPath, LatencyMeasurement, PriceSnapshot, TriggerEvent, RING_SLOTS_PER_SEC};
+use maintenance::Maintenance;
+use metrics::{LatencyBudget, MetricsCollector, Stage, StageTimeline};
+use risk::{Risk, TradeRisk};
+use shard::{ShardOptions, ShardPlan, ShardRuntime, ShardWorker};
+use std::ops::Range;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use symbols::SymbolRegistry;
+use ws_feed::mock::MockTradeServer;
+use ws_feed::{WsFeed, WsFeedConfig};
 
 /// Ultra-fast altcoin trading bot
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Run in shadow benchmark mode
@@ -29,29 +56,157 @@ struct Args {
     #[arg(long, default_value = "300")]
     num_symbols: u32,
 
-    /// Number of symbols per shard (for future sharding support)
+    /// Run the benchmark sharded: one router plus one thread per this many symbols
     #[arg(long)]
     symbols_per_shard: Option<usize>,
 
+    /// Comma-separated CPU cores to pin shard threads to, round-robin
+    /// (overrides threads.shard.cores)
+    #[arg(long, value_delimiter = ',')]
+    shard_cores: Vec<usize>,
+
+    /// Also report the per-tick SBE decode cost of each compiled decoder
+    #[arg(long)]
+    bench_decode: bool,
+
+    /// Also compare the full-ring scan against the tail cursor for the windowed return
+    #[arg(long)]
+    bench_window: bool,
+
     /// Path to write HDR histogram output
     #[arg(long, default_value = "target/shadow_bench/hdr_histogram.hdr")]
     hist_out: PathBuf,
+
+    /// WebSocket base URL for the trade stream (normal mode)
+    #[arg(long, default_value = "wss://stream.binance.com:9443")]
+    ws_url: String,
+
+    /// Comma-separated exchange symbols to subscribe to (normal mode)
+    #[arg(long, value_delimiter = ',', default_value = "BTCUSDT,ETHUSDT,SOLUSDT")]
+    symbols: Vec<String>,
+
+    /// exchangeInfo-style JSON with symbol metadata (reloaded when the file changes)
+    #[arg(long)]
+    exchange_info: Option<PathBuf>,
+
+    /// Replay scripted trades from an in-process mock server instead of connecting out
+    #[arg(long)]
+    mock_feed: bool,
+
+    /// Path of the shadow-mode trade ledger (JSON lines)
+    #[arg(long, default_value = "target/shadow/ledger.jsonl")]
+    ledger_out: PathBuf,
+
+    /// TOML or JSON config file (fields override the built-in defaults)
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Override the return threshold (%)
+    #[arg(long)]
+    return_threshold_pct: Option<f64>,
+
+    /// Override the price window (seconds)
+    #[arg(long)]
+    price_window_secs: Option<u64>,
+
+    /// Override the maximum number of tracked symbols
+    #[arg(long)]
+    max_symbols: Option<usize>,
+
+    /// Override any config field, e.g. --set take_profit_pct=8 (repeatable)
+    #[arg(long = "set", value_name = "FIELD=VALUE", value_parser = parse_key_value)]
+    overrides: Vec<(String, String)>,
+
+    /// Print the effective config (defaults < file < ALTBOT_* env < flags) and exit
+    #[arg(long)]
+    print_config: bool,
+}
+
+/// Parse a `FIELD=VALUE` override
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected FIELD=VALUE, got '{}'", s))?;
+    Ok((key.trim().to_string(), value.trim().to_string()))
+}
+
+/// Merge the config: defaults < --config file < ALTBOT_* env < command-line flags
+fn load_config(args: &Args) -> Result<Config, String> {
+    let config = match &args.config {
+        Some(path) => Config::load_file(path)?,
+        None => Config::default(),
+    };
+    // vars() panics on a non-UTF-8 entry anywhere in the environment
+    let env = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)));
+    let config = config.with_overrides(config::env_overrides(env))?;
+
+    let mut flags = Vec::new();
+    if let Some(pct) = args.return_threshold_pct {
+        flags.push(("return_threshold_pct".to_string(), pct.to_string()));
+    }
+    if let Some(secs) = args.price_window_secs {
+        flags.push(("price_window_secs".to_string(), secs.to_string()));
+    }
+    if let Some(max) = args.max_symbols {
+        flags.push(("max_symbols".to_string(), max.to_string()));
+    }
+    flags.extend(args.overrides.iter().cloned());
+    let mut config = config.with_overrides(flags)?;
+    if !args.shard_cores.is_empty() {
+        config.threads.shard.cores = args.shard_cores.clone();
+    }
+    Ok(config)
 }
 
 fn main() {
     let args = Args::parse();
 
+    let config = match load_config(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load config: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let num_symbols = if args.bench_shadow {
+        args.num_symbols as usize
+    } else {
+        args.symbols.len()
+    };
+    let valid = config.validate(num_symbols);
+
+    if args.print_config {
+        match config.to_toml() {
+            Ok(text) => print!("{}", text),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    if let Err(e) = valid {
+        eprintln!("Invalid config: {}", e);
+        std::process::exit(2);
+    }
+    if args.print_config {
+        return;
+    }
+    if config.threads.mlockall {
+        match cpu::lock_memory() {
+            Ok(()) => println!("Memory locked (mlockall)"),
+            Err(e) => eprintln!("Memory not locked: {}", e),
+        }
+    }
+
     if args.bench_shadow {
         println!("Running in shadow benchmark mode...");
-        run_shadow_benchmark(&args);
+        run_shadow_benchmark(&args, &config);
     } else {
         println!("Running in normal mode (shadow mode enabled by default)...");
-        run_normal_mode();
+        run_normal_mode(&args, &config);
     }
 }
 
 /// Run shadow benchmark harness
-fn run_shadow_benchmark(args: &Args) {
+fn run_shadow_benchmark(args: &Args, config: &Config) {
     let num_ticks = args.num_ticks;
     let num_symbols = args.num_symbols;
 
@@ -60,90 +215,221 @@ fn run_shadow_benchmark(args: &Args) {
         num_ticks, num_symbols
     );
 
-    if let Some(shard_size) = args.symbols_per_shard {
-        let num_shards = (num_symbols as usize).div_ceil(shard_size);
-        println!(
-            "Using {} symbols per shard ({} shards total)",
-            shard_size, num_shards
-        );
-    }
-
-    let config = Config::default();
+    let plan = match args
+        .symbols_per_shard
+        .map(|n| ShardPlan::new(num_symbols, n))
+    {
+        Some(Ok(plan)) => {
+            println!(
+                "Using {} symbols per shard ({} shards total)",
+                plan.range(0).len(),
+                plan.num_shards()
+            );
+            Some(plan)
+        }
+        Some(Err(e)) => {
+            eprintln!("Invalid --symbols-per-shard: {}", e);
+            std::process::exit(2);
+        }
+        None => None,
+    };
 
     // Generate synthetic ticks
     let generator = TickGenerator::new(num_symbols, num_ticks);
     let ticks = generator.generate();
     println!("Generated {} ticks", ticks.len());
 
-    // Create metrics collector
-    let mut metrics =
-        MetricsCollector::new(100_000, 3).expect("Failed to create metrics collector");
+    if args.bench_decode {
+        run_decode_benchmark(&ticks);
+    }
+    if args.bench_window {
+        run_window_benchmark(num_symbols, config.price_window_secs);
+    }
 
-    // Create hot-path processor
-    let hotpath = Arc::new(HotPath::new(
-        config.max_symbols,
-        config.return_threshold_pct,
-        config.price_window_secs,
-    ));
+    // Triggers go through the execution engine against the shadow exchange
+    let symbol_names: Vec<String> = (0..num_symbols).map(|i| format!("SYM{}USDT", i)).collect();
+    let registry = Arc::new(
+        SymbolRegistry::from_symbols(&symbol_names, config.max_symbols)
+            .expect("--num-symbols exceeds max_symbols"),
+    );
+
+    // One hot-path slice, engine and collector per shard (a single one
+    // unsharded); every shard shares the position slots and freeze
+    let shared_config = Arc::new(ArcSwap::from_pointee(config.clone()));
+    let risk = Arc::new(Risk::new(config));
+    let mut shards: Vec<BenchShard> = match &plan {
+        Some(plan) => (0..plan.num_shards())
+            .map(|shard| BenchShard::new(&shared_config, plan.range(shard), &registry, &risk))
+            .collect(),
+        None => vec![BenchShard::new(
+            &shared_config,
+            0..config.max_symbols as u32,
+            &registry,
+            &risk,
+        )],
+    };
+    for shard in &shards {
+        warn_unknown_overrides(&shard.hotpath.apply_symbol_overrides(config, &registry));
+    }
+    warn_unknown_bans(&risk.apply_config_bans(config, &registry));
+
+    // Every tick travels as an SBE frame (feed -> decode -> channel)
+    let mut frames = Vec::with_capacity(ticks.len() * 64);
+    let mut frame_ends = Vec::with_capacity(ticks.len());
+    for tick in &ticks {
+        sbe_decoder_ffi::wire::encode_trade(
+            &mut frames,
+            tick.symbol_id,
+            tick.px_e8,
+            tick.ts_unix_ms,
+        );
+        frame_ends.push(frames.len());
+    }
 
     // Pre-populate price snapshots to ensure we have history for return calculation
     println!("Pre-populating price snapshots...");
     for tick in ticks.iter().take(1000) {
-        hotpath.update_snapshot(tick.symbol_id, tick.px_e8, tick.ts_unix_ms);
+        let shard = plan.map_or(Some(0), |plan| plan.shard_of(tick.symbol_id));
+        if let Some(shard) = shard {
+            shards[shard]
+                .hotpath
+                .update_snapshot(tick.symbol_id, tick.px_e8, tick.ts_unix_ms);
+        }
     }
 
     // Process ticks and measure latency
     println!("Processing ticks...");
-    let mut trigger_count = 0;
+    let mut metrics =
+        MetricsCollector::new(100_000, 3).expect("Failed to create metrics collector");
     let bench_start = Instant::now();
 
-    for (idx, tick) in ticks.iter().enumerate() {
-        let mut measurement = LatencyMeasurement::new();
-
-        // Start timing
-        measurement.start();
-
-        // Update snapshot (in real system, this happens off hot-path)
-        hotpath.update_snapshot(tick.symbol_id, tick.px_e8, tick.ts_unix_ms);
+    let shards = match plan {
+        Some(plan) => {
+            // Short queues keep the router from running far ahead of the
+            // shards; time spent queued shows up as decode_done latency
+            let options = ShardOptions {
+                channel_capacity: 16,
+                placement: config.threads.shard.clone(),
+                decode_errors: metrics.decode_errors(),
+                decode_error_policy: config.decode_error_policy,
+            };
+            let mut runtime =
+                ShardRuntime::start(plan, shards, options).expect("Failed to start shards");
+            runtime.placements().iter().for_each(report_placement);
+            // Placed after the shards are spawned so they don't inherit it
+            place_thread("router", &config.threads.router);
+            let mut frame_start = 0;
+            for (idx, &frame_end) in frame_ends.iter().enumerate() {
+                if let Err(e) = runtime.route(&frames[frame_start..frame_end]) {
+                    eprintln!("Router stopped: {}", e);
+                    break;
+                }
+                frame_start = frame_end;
 
-        // Process tick on hot-path
-        if let Some(trigger) = hotpath.process_tick(tick) {
-            trigger_count += 1;
-            if trigger_count <= 10 {
-                println!(
-                    "Trigger #{}: symbol={} return={:.2}% price={}",
-                    trigger_count,
-                    trigger.symbol_id,
-                    trigger.return_pct,
-                    trigger.price_e8 as f64 / 1e8
-                );
+                // Progress update
+                if (idx + 1) % 10_000 == 0 {
+                    println!("Routed {}/{} ticks...", idx + 1, num_ticks);
+                }
             }
+            let (shards, router_stats) = runtime.finish().expect("Shard failed");
+            println!("Router: {:?}", router_stats);
+            shards
         }
+        None => {
+            let mut feed = DataFeed::new(true, 1024);
+            feed.set_error_counters(metrics.decode_errors());
+            feed.set_error_policy(config.decode_error_policy);
+            let rx = feed.get_receiver().expect("receiver already taken");
+            let shard = &mut shards[0];
+            place_thread("hotpath", &config.threads.hotpath);
 
-        // End timing
-        measurement.end();
+            let mut frame_start = 0;
+            for (idx, &frame_end) in frame_ends.iter().enumerate() {
+                feed.feed_bytes(&frames[frame_start..frame_end]);
+                frame_start = frame_end;
+                feed.decode_and_send(1);
+                if let Some(error) = feed.halted_on() {
+                    eprintln!("Feed halted: {}", error);
+                    break;
+                }
+                let Ok(tick) = rx.try_recv() else {
+                    continue;
+                };
 
-        // Record latency
-        if let Err(e) = metrics.record(measurement.duration_micros()) {
-            eprintln!("Failed to record metric: {}", e);
-        }
+                if let Some(trigger) = shard.process(&tick) {
+                    if shard.triggers <= 10 {
+                        println!(
+                            "Trigger #{}: symbol={} return={:.2}% price={}",
+                            shard.triggers,
+                            trigger.symbol_id,
+                            trigger.return_pct,
+                            trigger.price_e8 as f64 / 1e8
+                        );
+                    }
+                }
 
-        // Progress update
-        if (idx + 1) % 10_000 == 0 {
-            println!("Processed {}/{} ticks...", idx + 1, num_ticks);
+                // Progress update
+                if (idx + 1) % 10_000 == 0 {
+                    println!("Processed {}/{} ticks...", idx + 1, num_ticks);
+                }
+            }
+            shards
         }
-    }
+    };
 
     let bench_duration = bench_start.elapsed();
     let duration_secs = bench_duration.as_secs_f64();
 
+    // Aggregate the shard histograms (per-shard summaries only when sharded)
+    for (idx, shard) in shards.iter().enumerate() {
+        let merged = match &plan {
+            Some(plan) => metrics.merge_shard(idx, plan.range(idx), &shard.metrics),
+            None => metrics.merge(&shard.metrics),
+        };
+        if let Err(e) = merged {
+            eprintln!("{}", e);
+        }
+    }
+    metrics.set_budget(LatencyBudget {
+        p95_ms: config.target_p95_ms,
+        p99_ms: config.target_p99_ms,
+    });
+
+    let shadow = shards
+        .iter()
+        .map(|shard| shard.engine.client().summary())
+        .fold((0, 0), |(trades, open), summary| {
+            (trades + summary.trades, open + summary.open_positions)
+        });
+
     println!("\n=== Benchmark Complete ===");
     println!("Total time: {:.2}s", duration_secs);
     println!(
         "Throughput: {:.0} ticks/sec",
         num_ticks as f64 / duration_secs
     );
-    println!("Triggers: {}", trigger_count);
+    println!(
+        "Triggers: {} ({} re-triggers suppressed)",
+        shards.iter().map(|shard| shard.triggers).sum::<u64>(),
+        shards
+            .iter()
+            .map(|shard| shard.hotpath.suppressed())
+            .sum::<u64>()
+    );
+    println!("Shadow trades: {} closed, {} open", shadow.0, shadow.1);
+    println!(
+        "Position slots: {}; {} triggers denied",
+        risk.slots,
+        risk.slots.denied()
+    );
+    let book = risk.book.summary();
+    println!("Position book: {}", book);
+    metrics.set_book(book);
+    let freeze = risk.freeze.latest();
+    if let Some(freeze) = freeze {
+        println!("Freeze: {}", freeze);
+    }
+    metrics.set_freeze(freeze);
     println!();
 
     // Print metrics summary
@@ -162,20 +448,760 @@ fn run_shadow_benchmark(args: &Args) {
         Err(e) => eprintln!("Failed to write JSON summary: {}", e),
     }
 
+    println!();
+    metrics.print_verdict();
+    if metrics.verdict().is_some_and(|v| !v.pass) {
+        println!("\n✗ Shadow benchmark over latency budget");
+        std::process::exit(1);
+    }
     println!("\n✓ Shadow benchmark completed successfully");
     std::process::exit(0);
 }
 
-/// Run in normal mode (future: connect to real data feed)
-fn run_normal_mode() {
-    let config = Config::default();
-    println!("Configuration: {:?}", config);
+/// Apply a thread role's placement to the calling thread and report it
+fn place_thread(name: &str, role: &ThreadRole) {
+    report_placement(&cpu::place_current_thread(
+        name,
+        &role.cores,
+        role.fifo_priority,
+    ));
+}
+
+/// Print a thread's effective placement; failures and unhonoured core
+/// lists go to stderr
+fn report_placement(report: &cpu::PlacementReport) {
+    if report.honoured() && report.errors.is_empty() {
+        println!("Thread placement: {}", report);
+    } else {
+        eprintln!("Thread placement: {}", report);
+    }
+}
+
+/// Hot-path slice, shadow execution engine and metrics for one benchmark shard
+struct BenchShard {
+    hotpath: HotPath,
+    engine: ExecutionEngine<ShadowExchange>,
+    metrics: MetricsCollector,
+    triggers: u64,
+}
+
+impl BenchShard {
+    fn new(
+        config: &Arc<ArcSwap<Config>>,
+        symbol_ids: Range<u32>,
+        registry: &Arc<SymbolRegistry>,
+        risk: &Arc<Risk>,
+    ) -> Self {
+        let engine_config = config.load();
+        let mut hotpath = HotPath::with_range(Arc::clone(config), symbol_ids);
+        hotpath.set_risk(Arc::clone(risk));
+        Self {
+            hotpath,
+            engine: ExecutionEngine::new(
+                ShadowExchange::new(Arc::clone(registry), &engine_config),
+                Arc::clone(registry),
+                Arc::clone(risk),
+                &engine_config,
+            ),
+            metrics: MetricsCollector::new(100_000, 3).expect("Failed to create metrics collector"),
+            triggers: 0,
+        }
+    }
+
+    /// Shadow fills, then the measured snapshot update and trigger check,
+    /// then history recording and execution of any trigger
+    fn process(&mut self, tick: &TradeTick) -> Option<TriggerEvent> {
+        let mut timeline = StageTimeline::from_tick(tick);
+        apply_shadow_fills(&mut self.engine, &mut self.metrics, tick, |_| {});
+
+        let mut measurement = LatencyMeasurement::new();
+
+        // Start timing
+        measurement.start();
+
+        // Update snapshot (in real system, this happens off hot-path)
+        self.hotpath
+            .update_snapshot(tick.symbol_id, tick.px_e8, tick.ts_unix_ms);
+
+        // Process tick on hot-path
+        let trigger = self.hotpath.process_tick(tick);
+
+        // End timing
+        measurement.end();
+
+        // Long-horizon history (the live ingest thread records it there)
+        self.hotpath
+            .record_history(tick.symbol_id, tick.px_e8, tick.ts_unix_ms);
+
+        match &trigger {
+            Some(trigger) => {
+                timeline.mark(Stage::TriggerDecided);
+                let _ = execute_trigger(&mut self.engine, &mut self.metrics, trigger, timeline);
+                self.triggers += 1;
+            }
+            None => self.metrics.record_timeline(&timeline),
+        }
+
+        // Record latency
+        if let Err(e) = self.metrics.record(measurement.duration_micros()) {
+            eprintln!("Failed to record metric: {}", e);
+        }
+        trigger
+    }
+}
+
+impl ShardWorker for BenchShard {
+    fn on_tick(&mut self, tick: &TradeTick) {
+        self.process(tick);
+    }
+}
+
+/// Encode ticks as SBE frames and report per-tick decode cost of each decoder
+fn run_decode_benchmark(ticks: &[TradeTick]) {
+    let mut frames = Vec::with_capacity(ticks.len() * 64);
+    for tick in ticks {
+        sbe_decoder_ffi::wire::encode_trade(
+            &mut frames,
+            tick.symbol_id,
+            tick.px_e8,
+            tick.ts_unix_ms,
+        );
+    }
+
+    println!("\n=== SBE Decode Cost ===");
+    println!("Frames: {} ({} bytes)", ticks.len(), frames.len());
+
+    #[cfg(feature = "c-sbe")]
+    {
+        let mut decoder = sbe_decoder_ffi::SbeDecoderFfi::new();
+        decoder.feed(&frames);
+        report_decode_cost("c-sbe", ticks.len(), |out| {
+            decoder.decode_many(out).unwrap_or(0)
+        });
+    }
+
+    #[cfg(feature = "rust-sbe")]
+    {
+        let mut decoder = sbe_decoder_rust::SbeDecoderRust::new();
+        decoder.feed(&frames);
+        report_decode_cost("rust-sbe", ticks.len(), |out| {
+            decoder.decode_many(out).unwrap_or(0)
+        });
+    }
+
     println!();
-    println!("Normal mode is not yet implemented.");
-    println!("Use --bench-shadow to run the benchmark harness.");
+}
+
+/// Fill one full price ring per symbol and report the per-tick cost of the
+/// windowed return with the full-ring scan and with the tail cursor
+fn run_window_benchmark(num_symbols: u32, window_secs: u64) {
+    const SCAN_ROUNDS: u64 = 10;
+    const CURSOR_ROUNDS: u64 = 1_000;
+
+    let points = window_secs * RING_SLOTS_PER_SEC;
+    let step_ms = 1000 / RING_SLOTS_PER_SEC;
+    let base_ts = 1_700_000_000_000u64;
+    let mut rng_state = 12345u64;
+    let snapshots: Vec<PriceSnapshot> = (0..num_symbols)
+        .map(|_| {
+            let snapshot = PriceSnapshot::new(window_secs);
+            // Two ring-fulls so the ring has wrapped
+            for i in 0..2 * points {
+                rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
+                let px_e8 = 100_000_000 + rng_state % 10_000_000;
+                snapshot.add(px_e8, base_ts + i * step_ms);
+            }
+            snapshot
+        })
+        .collect();
+    let now_ms = base_ts + (2 * points - 1) * step_ms;
+
+    println!("\n=== Windowed Return Cost ===");
+    println!(
+        "Symbols: {}, ring: {} points ({}s at {}/s)",
+        num_symbols, points, window_secs, RING_SLOTS_PER_SEC
+    );
+
+    let mismatches = snapshots
+        .iter()
+        .filter(|snapshot| {
+            snapshot.compute_return_scan(window_secs, now_ms)
+                != snapshot.compute_return_window(window_secs, now_ms)
+        })
+        .count();
+    if mismatches > 0 {
+        eprintln!(
+            "window: {} symbols disagree between scan and cursor",
+            mismatches
+        );
+    }
+
+    let time = |name: &str, rounds: u64, compute: &dyn Fn(&PriceSnapshot) -> Option<f64>| {
+        let start = Instant::now();
+        for _ in 0..rounds {
+            for snapshot in &snapshots {
+                std::hint::black_box(compute(std::hint::black_box(snapshot)));
+            }
+        }
+        let ns_per_tick =
+            start.elapsed().as_nanos() as f64 / (rounds * num_symbols as u64).max(1) as f64;
+        println!("{}: {:.1} ns/tick", name, ns_per_tick);
+        ns_per_tick
+    };
+    let scan = time("scan", SCAN_ROUNDS, &|snapshot| {
+        snapshot.compute_return_scan(window_secs, now_ms)
+    });
+    let cursor = time("cursor", CURSOR_ROUNDS, &|snapshot| {
+        snapshot.compute_return_window(window_secs, now_ms)
+    });
+    println!("Speedup: {:.0}x", scan / cursor.max(f64::MIN_POSITIVE));
     println!();
-    println!("Example:");
-    println!("  cargo run --release -- --bench-shadow");
+}
+
+/// Drain a decoder in fixed-size batches and print ns/tick
+fn report_decode_cost(
+    name: &str,
+    expected: usize,
+    mut decode: impl FnMut(&mut [TradeTick]) -> usize,
+) {
+    let mut batch = [TradeTick::new(0, 0, 0); 256];
+    let mut decoded = 0;
+    let start = Instant::now();
+
+    loop {
+        let n = decode(&mut batch);
+        decoded += n;
+        if n < batch.len() {
+            break;
+        }
+    }
+
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {} ticks in {:.2} ms ({:.1} ns/tick)",
+        name,
+        decoded,
+        elapsed.as_secs_f64() * 1000.0,
+        elapsed.as_nanos() as f64 / decoded.max(1) as f64
+    );
+    if decoded != expected {
+        eprintln!("{}: expected {} ticks, decoded {}", name, expected, decoded);
+    }
+}
+
+/// Run in normal mode: WebSocket trade stream -> DataFeed channel -> hot path
+fn run_normal_mode(args: &Args, config: &Config) {
+    println!("Configuration: {:?}", config);
+    if !config.shadow_mode {
+        eprintln!("Live order routing is not available yet; enable shadow_mode");
+        std::process::exit(1);
+    }
+
+    // Symbol ids come from exchangeInfo when given, otherwise from --symbols order
+    let registry = match &args.exchange_info {
+        Some(path) => SymbolRegistry::load_file(path, config.max_symbols, Some(&args.symbols)),
+        None => SymbolRegistry::from_symbols(&args.symbols, config.max_symbols),
+    };
+    let registry = match registry {
+        Ok(registry) => Arc::new(registry),
+        Err(e) => {
+            eprintln!("Failed to build symbol registry: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let subscribed = registry.trading_symbols();
+    if subscribed.is_empty() {
+        eprintln!("No TRADING symbols to subscribe to");
+        std::process::exit(1);
+    }
+    // Offline mode: replay a scripted pump on the first symbol
+    let mock_server = if args.mock_feed {
+        let server = MockTradeServer::start(mock_script(&subscribed))
+            .expect("Failed to start mock trade server");
+        println!("Mock trade server listening on {}", server.url());
+        Some(server)
+    } else {
+        None
+    };
+
+    let ws_config = WsFeedConfig {
+        base_url: mock_server
+            .as_ref()
+            .map(|server| server.url())
+            .unwrap_or_else(|| args.ws_url.clone()),
+        symbols: subscribed,
+    };
+    println!("Subscribing to {}", ws_config.stream_url());
+
+    let mut feed = DataFeed::new(false, 65_536);
+    let rx = feed.get_receiver().expect("receiver already taken");
+    let tx = feed.get_sender().expect("sender already taken");
+    let reconnect = mock_server.is_none();
+
+    // The hot path reads the threshold from the shared config on every tick
+    let shared_config = Arc::new(ArcSwap::from_pointee(config.clone()));
+    let hotpath = Arc::new(HotPath::new(Arc::clone(&shared_config)));
+
+    let ws_registry = Arc::clone(&registry);
+    let ingest_placement = config.threads.ingest.clone();
+    // Long-horizon history is recorded here, off the measured tick path
+    let history = Arc::clone(&hotpath);
+    let ingester = std::thread::spawn(move || {
+        place_thread("ingest", &ingest_placement);
+        let ws = WsFeed::new(ws_config, ws_registry);
+        loop {
+            let mut record = |tick: &TradeTick| {
+                history.record_history(tick.symbol_id, tick.px_e8, tick.ts_unix_ms)
+            };
+            match ws.run(&tx, &mut record) {
+                Ok(stats) => println!("Trade stream closed: {:?}", stats),
+                Err(e) => eprintln!("Trade stream error: {}", e),
+            }
+            if !reconnect {
+                break;
+            }
+            std::thread::sleep(Duration::from_secs(1));
+            println!("Reconnecting...");
+        }
+    });
+
+    warn_unknown_overrides(&hotpath.apply_symbol_overrides(config, &registry));
+    warn_unknown_bans(&hotpath.risk().apply_config_bans(config, &registry));
+    report_status_bans(hotpath.risk().apply_symbol_status(&registry));
+    if let Some(path) = args.exchange_info.clone() {
+        spawn_exchange_info_watcher(
+            path,
+            Arc::clone(&registry),
+            args.symbols.clone(),
+            Arc::clone(hotpath.risk()),
+        );
+    }
+    if args.config.is_some() {
+        let reloader = ConfigReloader::new(Arc::clone(&shared_config), args.symbols.len());
+        let (hotpath, registry) = (Arc::clone(&hotpath), Arc::clone(&registry));
+        let shared_config = Arc::clone(&shared_config);
+        spawn_config_watcher(args.clone(), reloader, move || {
+            let config = shared_config.load();
+            warn_unknown_overrides(&hotpath.apply_symbol_overrides(&config, &registry));
+            warn_unknown_bans(&hotpath.risk().apply_config_bans(&config, &registry));
+        });
+    }
+
+    // Long-horizon aggregates, stale-symbol eviction and health off the hot path
+    let mut maintenance = Maintenance::new(
+        Arc::clone(&hotpath),
+        config.maintenance,
+        Box::new(maintenance::wall_clock_ms),
+    );
+    if config.gate.enabled && registry.id_of(&config.gate.reference_symbol).is_none() {
+        eprintln!(
+            "Gate reference symbol {} is not tracked; gating on breadth only",
+            config.gate.reference_symbol
+        );
+    }
+    // Always installed: the `gate` section is hot-reloadable, enabled included
+    maintenance.set_gate(AltMarketGate::new(
+        Arc::clone(&shared_config),
+        Arc::clone(&registry),
+    ));
+    if let Err(e) = maintenance.spawn(|health| {
+        println!(
+            "Health: {} active, {} stale of {} symbols; {} evicted; aggregates pass {}µs; can_buy={}; AHI {}; slots {}/{} held, exposure {:.2}; {}; {} banned",
+            health.active,
+            health.stale,
+            health.symbols,
+            health.evictions,
+            health.last_pass_us,
+            health.can_buy,
+            health.ahi.map_or("-".to_string(), |ahi| format!("{:.1}", ahi)),
+            health.slots_held,
+            health.slots,
+            health.exposure_e8 as f64 / 1e8,
+            health
+                .freeze
+                .map_or("not frozen".to_string(), |freeze| freeze.to_string()),
+            health.banned
+        )
+    }) {
+        eprintln!("Maintenance disabled: {}", e);
+    }
+
+    // Shadow mode: orders are filled by the simulator against the trade stream
+    let mut exchange = ShadowExchange::new(Arc::clone(&registry), config);
+    match exchange.open_ledger(&args.ledger_out) {
+        Ok(()) => println!("Shadow ledger: {}", args.ledger_out.display()),
+        Err(e) => eprintln!("Shadow ledger disabled: {}", e),
+    }
+    let risk = Arc::clone(hotpath.risk());
+    spawn_unfreeze_listener(Arc::clone(&risk));
+    let mut engine =
+        ExecutionEngine::new(exchange, Arc::clone(&registry), Arc::clone(&risk), config);
+    let mut metrics =
+        MetricsCollector::new(100_000, 3).expect("Failed to create metrics collector");
+
+    // Helper threads are spawned by now; only the hot path gets this placement
+    place_thread("hotpath", &config.threads.hotpath);
+
+    // Consume until the ingester exits and drops its sender
+    let mut tick_count = 0u64;
+    for tick in rx.iter() {
+        tick_count += 1;
+        let mut timeline = StageTimeline::from_tick(&tick);
+        let outcome = apply_shadow_fills(&mut engine, &mut metrics, &tick, |event| {
+            print_execution_event(&registry, event)
+        });
+        print_trade_risk(&registry, tick.symbol_id, outcome);
+
+        let mut measurement = LatencyMeasurement::new();
+        measurement.start();
+        hotpath.update_snapshot(tick.symbol_id, tick.px_e8, tick.ts_unix_ms);
+        let trigger = hotpath.process_tick(&tick);
+        measurement.end();
+        let _ = metrics.record(measurement.duration_micros());
+
+        let Some(trigger) = trigger else {
+            metrics.record_timeline(&timeline);
+            continue;
+        };
+        timeline.mark(Stage::TriggerDecided);
+        let (result, outcome) = execute_trigger(&mut engine, &mut metrics, &trigger, timeline);
+
+        // Log after the orders are out
+        println!(
+            "Trigger: symbol={} ({}) slot={} return={:.2}% price={}",
+            trigger.symbol_id,
+            registry.name_of(trigger.symbol_id),
+            risk.slots.name(trigger.slot.slot),
+            trigger.return_pct,
+            trigger.price_e8 as f64 / 1e8
+        );
+        match result {
+            Ok(event) => print_execution_event(&registry, &event),
+            Err(e @ ExecError::TakeProfitFailed { .. }) => {
+                eprintln!("Execution failed: {}; flattening", e)
+            }
+            Err(e) => eprintln!("Execution skipped: {}", e),
+        }
+        print_trade_risk(&registry, trigger.symbol_id, outcome);
+    }
+
+    let _ = ingester.join();
+    println!("Processed {} ticks", tick_count);
+    metrics.print_summary();
+
+    let summary = engine.client().summary();
+    println!(
+        "Shadow trades: {} (wins {}, losses {}), open positions: {}, fees: {:.4}, PnL: {:.4}",
+        summary.trades,
+        summary.wins,
+        summary.losses,
+        summary.open_positions,
+        summary.fees_e8 as f64 / 1e8,
+        summary.pnl_e8 as f64 / 1e8
+    );
+    println!("Position book: {}", risk.book.summary());
+}
+
+/// Mark the position book to `tick`, match the shadow exchange's working
+/// orders against it, hand the fills to the engine and record the timelines
+/// of completed BUY+LIMIT paths. An unprotected position of the tick's
+/// symbol is sent a flatten SELL (retried on every tick until it goes out).
+/// A closed round trip goes to the risk state (freeze, symbol ban); returns
+/// what it changed. Only the tick's symbol can close a trade.
+fn apply_shadow_fills(
+    engine: &mut ExecutionEngine<ShadowExchange>,
+    metrics: &mut MetricsCollector,
+    tick: &TradeTick,
+    mut on_event: impl FnMut(&ExecutionEvent),
+) -> Option<TradeRisk> {
+    engine.risk().book.mark(tick.symbol_id, tick.px_e8);
+    let closed_before = engine.client().trades().len();
+    for report in engine.client_mut().on_tick(tick) {
+        match engine.on_report(&report) {
+            Ok(Some(event)) => {
+                if let ExecutionEvent::TakeProfitPlaced { timeline, .. }
+                | ExecutionEvent::TakeProfitFilled { timeline, .. } = &event
+                {
+                    metrics.record_timeline(timeline);
+                }
+                on_event(&event);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Execution error: {}", e),
+        }
+    }
+    if engine.is_unprotected(tick.symbol_id) {
+        match engine.flatten(tick.symbol_id) {
+            Ok(Some(event)) => on_event(&event),
+            Ok(None) => {}
+            Err(e) => eprintln!("Execution error: {}", e),
+        }
+    }
+
+    record_closed_trades(engine, closed_before)
+}
+
+/// Send the BUY for `trigger`; the tick's timeline is recorded now unless
+/// the BUY is still waiting for its fill (then `apply_shadow_fills` does it).
+/// A round trip that closes right away goes to the risk state like in
+/// `apply_shadow_fills`.
+fn execute_trigger(
+    engine: &mut ExecutionEngine<ShadowExchange>,
+    metrics: &mut MetricsCollector,
+    trigger: &TriggerEvent,
+    timeline: StageTimeline,
+) -> (Result<ExecutionEvent, ExecError>, Option<TradeRisk>) {
+    let closed_before = engine.client().trades().len();
+    let result = engine.on_trigger_timed(trigger, timeline);
+    match &result {
+        Ok(ExecutionEvent::BuyPending(_)) => {}
+        Ok(
+            ExecutionEvent::TakeProfitPlaced { timeline, .. }
+            | ExecutionEvent::TakeProfitFilled { timeline, .. },
+        ) => metrics.record_timeline(timeline),
+        _ => metrics.record_timeline(&timeline),
+    }
+    (result, record_closed_trades(engine, closed_before))
+}
+
+/// Feed the shadow round trips closed since `closed_before` to the risk
+/// state (freeze, symbol ban); returns what the last one changed
+fn record_closed_trades(
+    engine: &ExecutionEngine<ShadowExchange>,
+    closed_before: usize,
+) -> Option<TradeRisk> {
+    let risk = engine.risk();
+    let mut outcome = None;
+    for trade in &engine.client().trades()[closed_before..] {
+        outcome = Some(risk.record_trade(trade.symbol_id, trade.pnl_e8, trade.exit_ts_unix_ms));
+    }
+    outcome
+}
+
+/// Log the ban and freeze a closed trade of `symbol_id` started
+fn print_trade_risk(registry: &SymbolRegistry, symbol_id: u32, outcome: Option<TradeRisk>) {
+    if let Some(until) = outcome.and_then(|o| o.banned_until_unix_ms) {
+        println!(
+            "Ban: {} until {} after a losing trade",
+            registry.name_of(symbol_id),
+            until
+        );
+    }
+    if let Some(freeze) = outcome.and_then(|o| o.freeze) {
+        println!("Freeze: {}", freeze);
+    }
+}
+
+/// Log one execution engine event
+fn print_execution_event(registry: &SymbolRegistry, event: &ExecutionEvent) {
+    match event {
+        ExecutionEvent::BuyPending(order) => {
+            println!(
+                "BUY sent: {} id={}",
+                registry.name_of(order.symbol_id),
+                order.client_order_id
+            )
+        }
+        ExecutionEvent::BuyUnfilled(report) => println!(
+            "BUY not filled: {} status={:?}",
+            registry.name_of(report.symbol_id),
+            report.status
+        ),
+        ExecutionEvent::TakeProfitPlaced {
+            buy, take_profit, ..
+        } => println!(
+            "BUY filled: {} qty={} avg={} -> TP LIMIT SELL qty={} @ {}",
+            registry.name_of(buy.symbol_id),
+            buy.executed_qty_e8 as f64 / 1e8,
+            buy.avg_px_e8() as f64 / 1e8,
+            take_profit.qty_e8 as f64 / 1e8,
+            take_profit.px_e8 as f64 / 1e8
+        ),
+        ExecutionEvent::TakeProfitFilled {
+            buy, take_profit, ..
+        } => println!(
+            "BUY filled: {} qty={} avg={} -> TP filled at once qty={} avg={}",
+            registry.name_of(buy.symbol_id),
+            buy.executed_qty_e8 as f64 / 1e8,
+            buy.avg_px_e8() as f64 / 1e8,
+            take_profit.executed_qty_e8 as f64 / 1e8,
+            take_profit.avg_px_e8() as f64 / 1e8
+        ),
+        ExecutionEvent::TakeProfitDone(report) => println!(
+            "TP {:?}: {} qty={} avg={}",
+            report.status,
+            registry.name_of(report.symbol_id),
+            report.executed_qty_e8 as f64 / 1e8,
+            report.avg_px_e8() as f64 / 1e8
+        ),
+        ExecutionEvent::Unprotected(report) => println!(
+            "Unprotected: {} SELL {:?} qty={}, flattening the rest",
+            registry.name_of(report.symbol_id),
+            report.status,
+            report.executed_qty_e8 as f64 / 1e8
+        ),
+        ExecutionEvent::FlattenPending(order) => println!(
+            "Flatten SELL sent: {} qty={} id={}",
+            registry.name_of(order.symbol_id),
+            order.qty_e8 as f64 / 1e8,
+            order.client_order_id
+        ),
+        ExecutionEvent::Flattened(report) => println!(
+            "Flattened: {} qty={} avg={}",
+            registry.name_of(report.symbol_id),
+            report.executed_qty_e8 as f64 / 1e8,
+            report.avg_px_e8() as f64 / 1e8
+        ),
+    }
+}
+
+/// Reload the symbol registry whenever the exchangeInfo file changes, then
+/// re-ban the symbols that are not TRADING
+fn spawn_exchange_info_watcher(
+    path: PathBuf,
+    registry: Arc<SymbolRegistry>,
+    allow: Vec<String>,
+    risk: Arc<Risk>,
+) {
+    std::thread::spawn(move || {
+        let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut last_modified = modified(&path);
+        loop {
+            std::thread::sleep(Duration::from_secs(5));
+            let current = modified(&path);
+            if current == last_modified {
+                continue;
+            }
+            last_modified = current;
+            match registry.reload_file(&path, Some(&allow)) {
+                Ok(summary) => {
+                    println!("Reloaded {}: {:?}", path.display(), summary);
+                    report_status_bans(risk.apply_symbol_status(&registry));
+                }
+                Err(e) => eprintln!("Symbol registry reload failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Per-symbol overrides naming symbols outside the registry have no effect
+fn warn_unknown_overrides(unknown: &[String]) {
+    if !unknown.is_empty() {
+        eprintln!(
+            "Ignoring overrides for unknown symbols: {}",
+            unknown.join(", ")
+        );
+    }
+}
+
+/// Banned symbols outside the registry have no effect
+fn warn_unknown_bans(unknown: &[String]) {
+    if !unknown.is_empty() {
+        eprintln!("Ignoring bans of unknown symbols: {}", unknown.join(", "));
+    }
+}
+
+/// Log how many registry symbols are banned for not trading
+fn report_status_bans(count: usize) {
+    if count > 0 {
+        println!("Banned {} symbols whose status is not TRADING", count);
+    }
+}
+
+/// Lift a buying freeze on SIGUSR1
+fn spawn_unfreeze_listener(risk: Arc<Risk>) {
+    let requested = Arc::new(AtomicBool::new(false));
+    if let Err(e) =
+        signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(&requested))
+    {
+        eprintln!("SIGUSR1 manual unfreeze disabled: {}", e);
+        return;
+    }
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(1));
+        if !requested.swap(false, Ordering::Relaxed) {
+            continue;
+        }
+        match risk.freeze.unfreeze() {
+            Some(lifted) => println!("Unfrozen manually; lifted: {}", lifted),
+            None => println!("Unfreeze requested, but buying was not frozen"),
+        }
+    });
+}
+
+/// Reload `--config` when the file changes or on SIGHUP, re-applying env and
+/// flag overrides; only hot-reloadable fields reach the running bot, then
+/// `on_reload` runs
+fn spawn_config_watcher(
+    args: Args,
+    reloader: ConfigReloader,
+    on_reload: impl Fn() + Send + 'static,
+) {
+    let Some(path) = args.config.clone() else {
+        return;
+    };
+    let hangup = Arc::new(AtomicBool::new(false));
+    if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&hangup)) {
+        eprintln!("SIGHUP config reload disabled: {}", e);
+    }
+
+    std::thread::spawn(move || {
+        let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut last_modified = modified(&path);
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            let current = modified(&path);
+            let signaled = hangup.swap(false, Ordering::Relaxed);
+            if current == last_modified && !signaled {
+                continue;
+            }
+            last_modified = current;
+            match load_config(&args).and_then(|next| reloader.apply(next)) {
+                Ok(summary) => {
+                    println!("Reloaded {}: applied {:?}", path.display(), summary.applied);
+                    if !summary.applied.is_empty() {
+                        on_reload();
+                    }
+                    if !summary.needs_restart.is_empty() {
+                        eprintln!(
+                            "Config fields changed but need a restart: {:?}",
+                            summary.needs_restart
+                        );
+                    }
+                }
+                Err(e) => eprintln!("Config reload failed, keeping running config: {}", e),
+            }
+        }
+    });
+}
+
+/// Scripted trades for `--mock-feed`: flat prices, and a steady pump on the first
+/// symbol that triggers a BUY and later reaches its take-profit
+fn mock_script(symbols: &[String]) -> Vec<String> {
+    let base_ts = 1_700_000_000_000u64;
+    let mut script = vec![r#"{"result":null,"id":1}"#.to_string()];
+    let mut trade_id = 0;
+
+    for step in 0..30u64 {
+        for (idx, symbol) in symbols.iter().enumerate() {
+            let base = 10.0 + idx as f64;
+            let price = if idx == 0 {
+                base * (1.0 + 0.0075 * step as f64)
+            } else {
+                base
+            };
+            trade_id += 1;
+            script.push(ws_feed::mock::trade_message(
+                symbol,
+                trade_id,
+                &format!("{:.8}", price),
+                base_ts + step * 5_000,
+            ));
+        }
+    }
+
+    script
 }
 
 #[cfg(test)]
@@ -186,6 +1212,7 @@ mod tests {
     fn test_config_default() {
         let config = Config::default();
         assert_eq!(config.target_p95_ms, 15);
+        assert_eq!(config.target_p99_ms, 25);
         assert!(config.shadow_mode);
         assert_eq!(config.return_threshold_pct, 5.0);
     }
@@ -206,9 +1233,15 @@ mod tests {
         // Create data feed with SBE decoder
         let mut feed = DataFeed::new(true, 1000);
         let rx = feed.get_receiver().unwrap();
+        feed.feed_bytes(sbe_decoder_ffi::fixtures::TRADE_STREAM);
+        feed.feed_bytes(sbe_decoder_ffi::fixtures::TRADE_GROUP);
 
         // Create hot path
-        let hotpath = Arc::new(HotPath::new(100, 5.0, 60));
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 100,
+            ..Config::default()
+        }));
+        let hotpath = Arc::new(HotPath::new(config));
 
         // Decode some ticks
         let decoded = feed.decode_and_send(100);
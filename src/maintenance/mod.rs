@@ -0,0 +1,364 @@
+//! Off-hot-path maintenance: a tokio task that periodically refreshes the
+//! long-horizon aggregates of every symbol (then re-evaluates the market
+//! gate), evicts stale history, lifts expired symbol bans and publishes
+//! health snapshots
+
+use crate::config::MaintenanceConfig;
+use crate::gate::{AltMarketGate, GateTransition};
+use crate::hotpath::HotPath;
+use crate::risk::FreezeStatus;
+use arc_swap::ArcSwap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::MissedTickBehavior;
+
+/// Source of "now" in unix milliseconds (wall clock in production)
+pub type Clock = Box<dyn Fn() -> u64 + Send + Sync>;
+
+/// Wall-clock time in unix milliseconds, comparable to exchange trade times
+pub fn wall_clock_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// State of the tracked symbols at one point in time
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HealthSnapshot {
+    /// Clock time of the snapshot (unix ms)
+    pub ts_unix_ms: u64,
+    /// Symbols owned by the hot path
+    pub symbols: usize,
+    /// Symbols with a trade within `stale_after_secs`
+    pub active: usize,
+    /// Symbols whose last trade is older than `stale_after_secs`
+    pub stale: usize,
+    /// Aggregate refresh passes so far
+    pub aggregate_passes: u64,
+    /// Duration of the last aggregate refresh pass in microseconds
+    pub last_pass_us: u64,
+    /// Symbols evicted so far
+    pub evictions: u64,
+    /// Global can_buy flag
+    pub can_buy: bool,
+    /// Alt health index of the last gate evaluation
+    pub ahi: Option<f64>,
+    /// Position slots held by open positions
+    pub slots_held: usize,
+    /// Position slots in total
+    pub slots: usize,
+    /// Quote amount held in open positions (e8)
+    pub exposure_e8: u64,
+    /// Buying freeze in effect
+    pub freeze: Option<FreezeStatus>,
+    /// Symbols banned from triggering
+    pub banned: usize,
+}
+
+/// Periodic maintenance of a `HotPath`
+pub struct Maintenance {
+    hotpath: Arc<HotPath>,
+    config: MaintenanceConfig,
+    clock: Clock,
+    /// Market gate re-evaluated after each aggregate pass
+    gate: Option<AltMarketGate>,
+    /// Latest health snapshot
+    health: Arc<ArcSwap<HealthSnapshot>>,
+    aggregate_passes: AtomicU64,
+    last_pass_us: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Maintenance {
+    /// Create a maintenance task for `hotpath`; `clock` supplies unix ms
+    pub fn new(hotpath: Arc<HotPath>, config: MaintenanceConfig, clock: Clock) -> Self {
+        Self {
+            hotpath,
+            config,
+            clock,
+            gate: None,
+            health: Arc::new(ArcSwap::from_pointee(HealthSnapshot::default())),
+            aggregate_passes: AtomicU64::new(0),
+            last_pass_us: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Re-evaluate `gate` after every aggregate pass
+    pub fn set_gate(&mut self, gate: AltMarketGate) {
+        self.gate = Some(gate);
+    }
+
+    /// Apply the gate to the freshly published aggregates
+    pub fn evaluate_gate(&self) -> Option<GateTransition> {
+        self.gate.as_ref()?.evaluate(&self.hotpath)
+    }
+
+    /// Shared handle to the latest health snapshot
+    #[allow(dead_code)]
+    pub fn health(&self) -> Arc<ArcSwap<HealthSnapshot>> {
+        Arc::clone(&self.health)
+    }
+
+    /// Recompute and publish the aggregates of every symbol with trades
+    pub fn refresh_aggregates(&self) -> usize {
+        let start = Instant::now();
+        let now_ms = (self.clock)();
+        let refreshed = self
+            .hotpath
+            .symbol_ids()
+            .filter(|&id| self.hotpath.last_trade_ms(id).is_some())
+            .filter(|&id| self.hotpath.update_aggregates(id, now_ms).is_some())
+            .count();
+        self.aggregate_passes.fetch_add(1, Ordering::Relaxed);
+        self.last_pass_us
+            .store(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+        refreshed
+    }
+
+    /// Evict the history of symbols without trades for `stale_after_secs`;
+    /// returns how many were newly evicted
+    pub fn evict_stale(&self) -> usize {
+        let stale_before_ms = self.stale_before_ms();
+        let evicted = self
+            .hotpath
+            .symbol_ids()
+            .filter(|&id| self.hotpath.evict_stale(id, stale_before_ms))
+            .count();
+        self.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+        evicted
+    }
+
+    /// Lift symbol bans after losing trades that have run their course by
+    /// the newest trade time (the clock they are stamped and judged on);
+    /// returns the symbol_ids lifted
+    pub fn expire_bans(&self) -> Vec<u32> {
+        self.hotpath.risk().bans.expire(self.newest_trade_ms())
+    }
+
+    /// Latest trade time over all symbols (0 before the first trade)
+    fn newest_trade_ms(&self) -> u64 {
+        self.hotpath
+            .symbol_ids()
+            .filter_map(|id| self.hotpath.last_trade_ms(id))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Build and publish a health snapshot
+    pub fn publish_health(&self) -> HealthSnapshot {
+        let stale_before_ms = self.stale_before_ms();
+        let ts_unix_ms = (self.clock)();
+        let mut snapshot = HealthSnapshot {
+            ts_unix_ms,
+            symbols: self.hotpath.symbol_ids().len(),
+            aggregate_passes: self.aggregate_passes.load(Ordering::Relaxed),
+            last_pass_us: self.last_pass_us.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            can_buy: self.hotpath.get_can_buy(),
+            ahi: self.gate.as_ref().and_then(AltMarketGate::last_ahi),
+            slots_held: self.hotpath.risk().slots.held(),
+            slots: self.hotpath.risk().slots.len(),
+            exposure_e8: self.hotpath.risk().book.exposure_e8(),
+            freeze: self.hotpath.risk().freeze.status(ts_unix_ms),
+            banned: self.hotpath.risk().bans.count(self.newest_trade_ms()),
+            ..HealthSnapshot::default()
+        };
+        for id in self.hotpath.symbol_ids() {
+            match self.hotpath.last_trade_ms(id) {
+                Some(ts) if ts >= stale_before_ms => snapshot.active += 1,
+                Some(_) => snapshot.stale += 1,
+                None => {}
+            }
+        }
+        self.health.store(Arc::new(snapshot));
+        snapshot
+    }
+
+    fn stale_before_ms(&self) -> u64 {
+        (self.clock)().saturating_sub(self.config.stale_after_secs * 1000)
+    }
+
+    /// Run the three jobs on their intervals forever; each starts
+    /// immediately. `on_health` sees every published snapshot; gate
+    /// transitions are logged with their reason.
+    pub async fn run(&self, on_health: impl Fn(&HealthSnapshot)) {
+        let every = |ms: u64| {
+            let mut interval = tokio::time::interval(Duration::from_millis(ms));
+            // A slow pass delays the next one instead of bursting to catch up
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        };
+        let mut aggregates = every(self.config.aggregates_interval_ms);
+        let mut eviction = every(self.config.eviction_interval_ms);
+        let mut health = every(self.config.health_interval_ms);
+
+        loop {
+            tokio::select! {
+                // Evict before refreshing so stale symbols publish nothing
+                biased;
+                _ = eviction.tick() => {
+                    self.evict_stale();
+                    let lifted = self.expire_bans();
+                    if !lifted.is_empty() {
+                        println!("Bans expired: symbol_ids {:?}", lifted);
+                    }
+                }
+                _ = aggregates.tick() => {
+                    self.refresh_aggregates();
+                    if let Some(transition) = self.evaluate_gate() {
+                        println!("Gate: {}", transition);
+                    }
+                }
+                _ = health.tick() => on_health(&self.publish_health()),
+            }
+        }
+    }
+
+    /// Run on a dedicated "maintenance" thread with a single-threaded runtime
+    pub fn spawn(
+        self,
+        on_health: impl Fn(&HealthSnapshot) + Send + 'static,
+    ) -> Result<std::thread::JoinHandle<()>, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .map_err(|e| format!("Failed to build maintenance runtime: {}", e))?;
+        std::thread::Builder::new()
+            .name("maintenance".to_string())
+            .spawn(move || runtime.block_on(self.run(on_health)))
+            .map_err(|e| format!("Failed to spawn maintenance thread: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::sync::Mutex;
+
+    const NOW_MS: u64 = 1_700_000_000_000;
+
+    /// Hot path with two hours of 1/s trades on symbol 0 up to `NOW_MS`, and
+    /// symbol 1 silent for the last hour
+    fn hotpath() -> Arc<HotPath> {
+        let config = Arc::new(ArcSwap::from_pointee(Config {
+            max_symbols: 4,
+            ..Config::default()
+        }));
+        let hotpath = Arc::new(HotPath::new(config));
+        for sec in (0..=7_200u64).rev() {
+            let ts = NOW_MS - sec * 1_000;
+            let px_e8 = 100_000_000 + (7_200 - sec) * 1_000;
+            hotpath.update_snapshot(0, px_e8, ts);
+            hotpath.record_history(0, px_e8, ts);
+            if sec >= 3_600 {
+                hotpath.update_snapshot(1, 50_000_000, ts);
+                hotpath.record_history(1, 50_000_000, ts);
+            }
+        }
+        hotpath
+    }
+
+    /// Clock following tokio's (paused) time from `NOW_MS`
+    fn paused_clock() -> Clock {
+        let start = tokio::time::Instant::now();
+        Box::new(move || NOW_MS + start.elapsed().as_millis() as u64)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_runs_jobs_on_their_intervals() {
+        let hotpath = hotpath();
+        let config = MaintenanceConfig {
+            aggregates_interval_ms: 1_000,
+            eviction_interval_ms: 10_000,
+            health_interval_ms: 5_000,
+            stale_after_secs: 300,
+        };
+        let maintenance = Arc::new(Maintenance::new(
+            Arc::clone(&hotpath),
+            config,
+            paused_clock(),
+        ));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let task = {
+            let (maintenance, seen) = (Arc::clone(&maintenance), Arc::clone(&seen));
+            tokio::spawn(async move {
+                maintenance
+                    .run(|health| seen.lock().unwrap().push(*health))
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(10_500)).await;
+        task.abort();
+
+        // Health at 0s, 5s and 10s; aggregates every second from 0s to 10s
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 3);
+        assert_eq!(
+            seen.iter()
+                .map(|h| h.ts_unix_ms - NOW_MS)
+                .collect::<Vec<_>>(),
+            [0, 5_000, 10_000]
+        );
+        let last = seen[2];
+        assert_eq!(last.aggregate_passes, 11);
+        assert_eq!((last.symbols, last.active, last.stale), (4, 1, 1));
+        assert_eq!(last.evictions, 1);
+        assert!(last.can_buy);
+        assert_eq!((last.slots_held, last.slots, last.freeze), (0, 2, None));
+        assert_eq!(**maintenance.health().load(), last);
+
+        // Symbol 0 has 2h of 1s data; the silent symbol was evicted
+        let active = hotpath.aggregates(0).unwrap();
+        assert!(active.ret_5m.unwrap() > 0.0);
+        assert!(active.ret_1h.is_some());
+        assert_eq!(active.ret_24h, None);
+        assert_eq!(hotpath.aggregates(1).unwrap().ret_1h, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_symbols_go_stale_as_time_passes() {
+        let hotpath = hotpath();
+        let maintenance = Maintenance::new(
+            Arc::clone(&hotpath),
+            MaintenanceConfig::default(),
+            paused_clock(),
+        );
+        assert_eq!(maintenance.evict_stale(), 1);
+        assert_eq!(maintenance.evict_stale(), 0);
+        assert_eq!(maintenance.refresh_aggregates(), 2);
+        assert!(hotpath.aggregates(0).unwrap().ret_5m.is_some());
+
+        // No trades for longer than stale_after_secs
+        tokio::time::advance(Duration::from_secs(301)).await;
+        assert_eq!(maintenance.evict_stale(), 1);
+        let health = maintenance.publish_health();
+        assert_eq!((health.active, health.stale, health.evictions), (0, 2, 2));
+        assert_eq!(hotpath.aggregates(0).unwrap().ret_5m, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_loss_bans_expire_on_trade_time() {
+        let hotpath = hotpath();
+        let maintenance = Maintenance::new(
+            Arc::clone(&hotpath),
+            MaintenanceConfig::default(),
+            paused_clock(),
+        );
+        let bans = &hotpath.risk().bans;
+        // Default loss_ban_secs: a day
+        bans.ban_after_loss(0, NOW_MS - 86_400_000 + 1);
+        bans.ban_after_loss(1, NOW_MS - 86_400_000);
+        bans.set_config_bans([2]);
+        assert_eq!(maintenance.publish_health().banned, 2);
+
+        // Lifted by the newest trade (NOW_MS), not the wall clock
+        tokio::time::advance(Duration::from_secs(86_400)).await;
+        assert_eq!(maintenance.expire_bans(), vec![1]);
+        assert_eq!(maintenance.publish_health().banned, 2);
+    }
+}
@@ -1,9 +1,119 @@
+use crate::data_feed::TradeTick;
+use crate::risk::{BookSummary, FreezeStatus};
+use crate::sbe_decoder_ffi::DecodeError;
 use hdrhistogram::serialization::Serializer;
 use hdrhistogram::Histogram;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
 use std::io::Write;
+use std::ops::Range;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+/// Largest stage latency tracked, in nanoseconds (larger values are clamped)
+const MAX_STAGE_NANOS: u64 = 60_000_000_000;
+
+/// Monotonic clock in nanoseconds since its first use in this process
+/// (shared by all threads so stage marks can be compared across them)
+pub fn monotonic_ns() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Stages of the tick -> BUY -> LIMIT path, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Raw message / frame received from the feed
+    FeedReceive,
+    /// Message decoded into a `TradeTick`
+    DecodeDone,
+    /// Hot path decided to trigger
+    TriggerDecided,
+    /// BUY request encoded
+    BuySerialized,
+    /// BUY request written by the exchange client
+    BuySent,
+    /// Exchange response to the BUY received
+    AckReceived,
+    /// Take-profit LIMIT request written by the exchange client
+    LimitSent,
+}
+
+/// Number of `Stage` variants
+pub const STAGE_COUNT: usize = 7;
+
+impl Stage {
+    /// All stages in path order
+    pub const ALL: [Stage; STAGE_COUNT] = [
+        Stage::FeedReceive,
+        Stage::DecodeDone,
+        Stage::TriggerDecided,
+        Stage::BuySerialized,
+        Stage::BuySent,
+        Stage::AckReceived,
+        Stage::LimitSent,
+    ];
+
+    /// Name used in summaries
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::FeedReceive => "feed_receive",
+            Stage::DecodeDone => "decode_done",
+            Stage::TriggerDecided => "trigger_decided",
+            Stage::BuySerialized => "buy_serialized",
+            Stage::BuySent => "buy_sent",
+            Stage::AckReceived => "ack_received",
+            Stage::LimitSent => "limit_sent",
+        }
+    }
+}
+
+/// `monotonic_ns` marks of one tick's way through the stages (0 = not reached)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimeline {
+    marks_ns: [u64; STAGE_COUNT],
+}
+
+impl StageTimeline {
+    /// Timeline starting with a tick's feed receive and decode stamps
+    pub fn from_tick(tick: &TradeTick) -> Self {
+        let mut timeline = Self::default();
+        timeline.set(Stage::FeedReceive, tick.recv_ns);
+        timeline.set(Stage::DecodeDone, tick.decoded_ns);
+        timeline
+    }
+
+    /// Mark `stage` as reached now
+    pub fn mark(&mut self, stage: Stage) {
+        self.marks_ns[stage as usize] = monotonic_ns();
+    }
+
+    /// Mark `stage` with a timestamp taken elsewhere (0 leaves it unmarked)
+    pub fn set(&mut self, stage: Stage, ns: u64) {
+        self.marks_ns[stage as usize] = ns;
+    }
+
+    /// Timestamp of `stage`, if reached
+    pub fn get(&self, stage: Stage) -> Option<u64> {
+        match self.marks_ns[stage as usize] {
+            0 => None,
+            ns => Some(ns),
+        }
+    }
+}
+
+/// Latency percentiles of one stage in nanoseconds for JSON output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageSummary {
+    pub stage: String,
+    pub count: u64,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+}
 
 /// Histogram summary for JSON output
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,11 +126,141 @@ pub struct HistogramSummary {
     pub p99: u64,
     pub p99_9: u64,
     pub throughput_avg: f64,
+    pub decode_errors: DecodeErrorSummary,
+    /// Time spent reaching each stage from the previous reached stage, plus
+    /// "tick_to_limit" from feed receive to LIMIT sent
+    pub stages: Vec<StageSummary>,
+    /// Pass/fail against the latency budget (None if no budget was set)
+    pub verdict: Option<BudgetVerdict>,
+    /// Per-shard tick latency (sharded runs only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shards: Vec<ShardSummary>,
+    /// Positions and PnL at the end of the run (None if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book: Option<BookSummary>,
+    /// Latest buying freeze of the run with its reason and expiry (None if
+    /// buying was never frozen)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freeze: Option<FreezeStatus>,
+}
+
+/// Tick latency of one shard in microseconds for JSON output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardSummary {
+    pub shard: usize,
+    /// Owned symbol ids: first_symbol_id..end_symbol_id
+    pub first_symbol_id: u32,
+    pub end_symbol_id: u32,
+    pub count: u64,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+/// Latency budget a run is judged against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyBudget {
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+/// One measured latency compared against the budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetCheck {
+    pub metric: String,
+    pub p95_us: u64,
+    pub p99_us: u64,
+    pub pass: bool,
+}
+
+/// Budget verdict for JSON output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetVerdict {
+    pub pass: bool,
+    pub target_p95_ms: u64,
+    pub target_p99_ms: u64,
+    pub checks: Vec<BudgetCheck>,
+}
+
+/// Decode error counts by kind for JSON output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodeErrorSummary {
+    pub null_pointer: u64,
+    pub truncated_frame: u64,
+    pub unknown_template: u64,
+    pub schema_version_mismatch: u64,
+    pub invalid_price: u64,
+    pub other: u64,
+}
+
+impl DecodeErrorSummary {
+    /// Total errors across all kinds
+    pub fn total(&self) -> u64 {
+        self.null_pointer
+            + self.truncated_frame
+            + self.unknown_template
+            + self.schema_version_mismatch
+            + self.invalid_price
+            + self.other
+    }
+}
+
+/// Lock-free decode error counters shared between the feed and metrics
+#[derive(Debug, Default)]
+pub struct DecodeErrorCounters {
+    null_pointer: AtomicU64,
+    truncated_frame: AtomicU64,
+    unknown_template: AtomicU64,
+    schema_version_mismatch: AtomicU64,
+    invalid_price: AtomicU64,
+    other: AtomicU64,
+}
+
+impl DecodeErrorCounters {
+    /// Count one decode error
+    pub fn record(&self, error: DecodeError) {
+        let counter = match error {
+            DecodeError::NullPointer => &self.null_pointer,
+            DecodeError::TruncatedFrame => &self.truncated_frame,
+            DecodeError::UnknownTemplate => &self.unknown_template,
+            DecodeError::SchemaVersionMismatch => &self.schema_version_mismatch,
+            DecodeError::InvalidPrice => &self.invalid_price,
+            DecodeError::Unknown(_) => &self.other,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Snapshot of the current counts
+    pub fn summary(&self) -> DecodeErrorSummary {
+        DecodeErrorSummary {
+            null_pointer: self.null_pointer.load(Ordering::Relaxed),
+            truncated_frame: self.truncated_frame.load(Ordering::Relaxed),
+            unknown_template: self.unknown_template.load(Ordering::Relaxed),
+            schema_version_mismatch: self.schema_version_mismatch.load(Ordering::Relaxed),
+            invalid_price: self.invalid_price.load(Ordering::Relaxed),
+            other: self.other.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// Metrics collector using HDR histogram for latency tracking
 pub struct MetricsCollector {
     histogram: Histogram<u64>,
+    /// Decode errors, shared with the data feed
+    decode_errors: Arc<DecodeErrorCounters>,
+    /// Per-stage latency in ns, indexed by `Stage` (FeedReceive stays empty)
+    stage_histograms: Vec<Histogram<u64>>,
+    /// Feed receive -> LIMIT sent in ns
+    tick_to_limit: Histogram<u64>,
+    /// Budget for `verdict`
+    budget: Option<LatencyBudget>,
+    /// Position book exported with the summary
+    book: Option<BookSummary>,
+    /// Buying freeze exported with the summary
+    freeze: Option<FreezeStatus>,
+    /// Summaries of the shard collectors merged into this one
+    shards: Vec<ShardSummary>,
 }
 
 impl MetricsCollector {
@@ -31,7 +271,178 @@ impl MetricsCollector {
         let histogram = Histogram::new_with_max(max_value, significant_figures)
             .map_err(|e| format!("Failed to create histogram: {}", e))?;
 
-        Ok(Self { histogram })
+        let stage_histogram = || {
+            Histogram::new_with_bounds(1, MAX_STAGE_NANOS, significant_figures)
+                .map_err(|e| format!("Failed to create stage histogram: {}", e))
+        };
+        let stage_histograms = Stage::ALL
+            .iter()
+            .map(|_| stage_histogram())
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self {
+            histogram,
+            decode_errors: Arc::new(DecodeErrorCounters::default()),
+            stage_histograms,
+            tick_to_limit: stage_histogram()?,
+            budget: None,
+            book: None,
+            freeze: None,
+            shards: Vec::new(),
+        })
+    }
+
+    /// Fold another collector's histograms into this one
+    pub fn merge(&mut self, other: &MetricsCollector) -> Result<(), String> {
+        let merge_error = |e| format!("Failed to merge histogram: {}", e);
+        self.histogram.add(&other.histogram).map_err(merge_error)?;
+        for (ours, theirs) in self
+            .stage_histograms
+            .iter_mut()
+            .zip(&other.stage_histograms)
+        {
+            ours.add(theirs).map_err(merge_error)?;
+        }
+        self.tick_to_limit
+            .add(&other.tick_to_limit)
+            .map_err(merge_error)
+    }
+
+    /// Fold a shard's histograms into this (aggregate) collector and keep
+    /// the shard's own tick latency summary
+    pub fn merge_shard(
+        &mut self,
+        shard: usize,
+        symbol_ids: Range<u32>,
+        other: &MetricsCollector,
+    ) -> Result<(), String> {
+        self.merge(other)
+            .map_err(|e| format!("Shard {}: {}", shard, e))?;
+        self.shards.push(ShardSummary {
+            shard,
+            first_symbol_id: symbol_ids.start,
+            end_symbol_id: symbol_ids.end,
+            count: other.count(),
+            p50: other.percentile(0.50),
+            p95: other.percentile(0.95),
+            p99: other.percentile(0.99),
+            max: other.histogram.max(),
+        });
+        Ok(())
+    }
+
+    /// Judge summaries against `budget`
+    pub fn set_budget(&mut self, budget: LatencyBudget) {
+        self.budget = Some(budget);
+    }
+
+    /// Export `book` with the JSON summary
+    pub fn set_book(&mut self, book: BookSummary) {
+        self.book = Some(book);
+    }
+
+    /// Export the latest buying freeze with the JSON summary
+    pub fn set_freeze(&mut self, freeze: Option<FreezeStatus>) {
+        self.freeze = freeze;
+    }
+
+    /// Compare the per-tick latency and, once any LIMIT was sent, the full
+    /// tick -> LIMIT path against the budget
+    pub fn verdict(&self) -> Option<BudgetVerdict> {
+        let budget = self.budget?;
+        let check = |metric: &str, p95_us: u64, p99_us: u64| BudgetCheck {
+            metric: metric.to_string(),
+            p95_us,
+            p99_us,
+            pass: p95_us <= budget.p95_ms * 1000 && p99_us <= budget.p99_ms * 1000,
+        };
+
+        let mut checks = vec![check(
+            "tick_latency",
+            self.percentile(0.95),
+            self.percentile(0.99),
+        )];
+        if !self.tick_to_limit.is_empty() {
+            checks.push(check(
+                "tick_to_limit",
+                self.tick_to_limit.value_at_quantile(0.95) / 1000,
+                self.tick_to_limit.value_at_quantile(0.99) / 1000,
+            ));
+        }
+
+        Some(BudgetVerdict {
+            pass: checks.iter().all(|c| c.pass),
+            target_p95_ms: budget.p95_ms,
+            target_p99_ms: budget.p99_ms,
+            checks,
+        })
+    }
+
+    /// Print the budget verdict, if a budget is set
+    pub fn print_verdict(&self) {
+        let Some(verdict) = self.verdict() else {
+            return;
+        };
+        println!(
+            "=== Latency Budget (p95 <= {} ms, p99 <= {} ms) ===",
+            verdict.target_p95_ms, verdict.target_p99_ms
+        );
+        for check in &verdict.checks {
+            println!(
+                "{:<16} p95={:.3} ms p99={:.3} ms {}",
+                check.metric,
+                check.p95_us as f64 / 1000.0,
+                check.p99_us as f64 / 1000.0,
+                if check.pass { "PASS" } else { "FAIL" }
+            );
+        }
+        println!("Verdict: {}", if verdict.pass { "PASS" } else { "FAIL" });
+    }
+
+    /// Record the stages reached by one tick; each reached stage gets the
+    /// time since the previous reached stage (allocation-free)
+    pub fn record_timeline(&mut self, timeline: &StageTimeline) {
+        let mut prev_ns = None;
+        for stage in Stage::ALL {
+            let Some(ns) = timeline.get(stage) else {
+                continue;
+            };
+            if let Some(prev_ns) = prev_ns {
+                self.stage_histograms[stage as usize].saturating_record(ns.saturating_sub(prev_ns));
+            }
+            prev_ns = Some(ns);
+        }
+
+        if let (Some(start), Some(end)) = (
+            timeline.get(Stage::FeedReceive),
+            timeline.get(Stage::LimitSent),
+        ) {
+            self.tick_to_limit
+                .saturating_record(end.saturating_sub(start));
+        }
+    }
+
+    /// Per-stage latency summaries (stages with samples only)
+    pub fn stage_summaries(&self) -> Vec<StageSummary> {
+        let summarize = |name: &str, histogram: &Histogram<u64>| StageSummary {
+            stage: name.to_string(),
+            count: histogram.len(),
+            p50: histogram.value_at_quantile(0.50),
+            p95: histogram.value_at_quantile(0.95),
+            p99: histogram.value_at_quantile(0.99),
+            max: histogram.max(),
+        };
+
+        let mut summaries: Vec<StageSummary> = Stage::ALL
+            .iter()
+            .zip(&self.stage_histograms)
+            .filter(|(_, histogram)| !histogram.is_empty())
+            .map(|(stage, histogram)| summarize(stage.name(), histogram))
+            .collect();
+        if !self.tick_to_limit.is_empty() {
+            summaries.push(summarize("tick_to_limit", &self.tick_to_limit));
+        }
+        summaries
     }
 
     /// Record a latency measurement in microseconds (hot-path compatible)
@@ -51,6 +462,11 @@ impl MetricsCollector {
         self.histogram.len()
     }
 
+    /// Decode error counters to hand to a `DataFeed`
+    pub fn decode_errors(&self) -> Arc<DecodeErrorCounters> {
+        Arc::clone(&self.decode_errors)
+    }
+
     /// Print summary statistics
     pub fn print_summary(&self) {
         println!("=== Latency Summary ===");
@@ -85,6 +501,39 @@ impl MetricsCollector {
             self.histogram.min(),
             self.histogram.min() as f64 / 1000.0
         );
+
+        let decode_errors = self.decode_errors.summary();
+        if decode_errors.total() > 0 {
+            println!("Decode errors: {:?}", decode_errors);
+        }
+
+        if !self.shards.is_empty() {
+            println!("=== Shard Latency (µs) ===");
+            for shard in &self.shards {
+                println!(
+                    "shard {:<3} symbols {:>4}..{:<4} n={:<8} p50={:<6} p95={:<6} p99={:<6} max={}",
+                    shard.shard,
+                    shard.first_symbol_id,
+                    shard.end_symbol_id,
+                    shard.count,
+                    shard.p50,
+                    shard.p95,
+                    shard.p99,
+                    shard.max
+                );
+            }
+        }
+
+        let stages = self.stage_summaries();
+        if !stages.is_empty() {
+            println!("=== Stage Latency (ns since previous stage) ===");
+            for stage in &stages {
+                println!(
+                    "{:<16} n={:<8} p50={:<10} p95={:<10} p99={:<10} max={}",
+                    stage.stage, stage.count, stage.p50, stage.p95, stage.p99, stage.max
+                );
+            }
+        }
     }
 
     /// Write histogram to file in HDR histogram format
@@ -126,6 +575,12 @@ impl MetricsCollector {
             p99: self.percentile(0.99),
             p99_9: self.percentile(0.999),
             throughput_avg,
+            decode_errors: self.decode_errors.summary(),
+            stages: self.stage_summaries(),
+            verdict: self.verdict(),
+            shards: self.shards.clone(),
+            book: self.book.clone(),
+            freeze: self.freeze,
         }
     }
 
@@ -150,6 +605,11 @@ impl MetricsCollector {
 mod tests {
     use super::*;
 
+    /// Within HDR histogram precision (3 significant figures)
+    fn close(value: u64, expected: u64) -> bool {
+        value.abs_diff(expected) <= expected / 1000
+    }
+
     #[test]
     fn test_metrics_collector() {
         let mut collector = MetricsCollector::new(100_000, 3).unwrap();
@@ -163,4 +623,145 @@ mod tests {
         assert_eq!(collector.count(), 4);
         assert!(collector.percentile(0.5) >= 100);
     }
+
+    #[test]
+    fn test_decode_error_counters() {
+        let collector = MetricsCollector::new(100_000, 3).unwrap();
+        let counters = collector.decode_errors();
+
+        counters.record(DecodeError::TruncatedFrame);
+        counters.record(DecodeError::TruncatedFrame);
+        counters.record(DecodeError::UnknownTemplate);
+        counters.record(DecodeError::Unknown(99));
+
+        let summary = collector.generate_summary(1.0).decode_errors;
+        assert_eq!(summary.truncated_frame, 2);
+        assert_eq!(summary.unknown_template, 1);
+        assert_eq!(summary.other, 1);
+        assert_eq!(summary.total(), 4);
+    }
+
+    #[test]
+    fn test_freeze_in_summary() {
+        use crate::risk::freeze::FreezeReason;
+
+        let mut collector = MetricsCollector::new(100_000, 3).unwrap();
+        assert_eq!(collector.generate_summary(1.0).freeze, None);
+
+        let freeze = FreezeStatus {
+            reason: FreezeReason::LossStreak { losses: 3 },
+            since_unix_ms: 1_700_000_000_000,
+            until_unix_ms: 1_700_003_600_000,
+        };
+        collector.set_freeze(Some(freeze));
+        let json = serde_json::to_value(collector.generate_summary(1.0)).unwrap();
+        assert_eq!(json["freeze"]["reason"]["kind"], "loss_streak");
+        assert_eq!(json["freeze"]["until_unix_ms"], 1_700_003_600_000u64);
+    }
+
+    #[test]
+    fn test_budget_verdict() {
+        let mut collector = MetricsCollector::new(100_000, 3).unwrap();
+        for _ in 0..99 {
+            collector.record(2_000).unwrap();
+        }
+        collector.record(40_000).unwrap();
+        assert_eq!(collector.verdict(), None);
+
+        collector.set_budget(LatencyBudget {
+            p95_ms: 15,
+            p99_ms: 25,
+        });
+        let verdict = collector.verdict().unwrap();
+        assert!(verdict.pass);
+        assert_eq!(verdict.checks.len(), 1);
+
+        // p99 lands on the 40ms outlier once it is more than 1% of samples
+        collector.record(40_000).unwrap();
+        let verdict = collector.generate_summary(1.0).verdict.unwrap();
+        assert!(!verdict.pass);
+        assert!(verdict.checks[0].p95_us <= 15_000);
+        assert!(verdict.checks[0].p99_us > 25_000);
+
+        // Full-path check is added once a LIMIT was sent
+        let mut collector = MetricsCollector::new(100_000, 3).unwrap();
+        collector.set_budget(LatencyBudget {
+            p95_ms: 15,
+            p99_ms: 25,
+        });
+        collector.record(5).unwrap();
+        let mut timeline = StageTimeline::default();
+        timeline.set(Stage::FeedReceive, 1);
+        timeline.set(Stage::LimitSent, 20_000_001);
+        collector.record_timeline(&timeline);
+        let verdict = collector.verdict().unwrap();
+        assert_eq!(verdict.checks[1].metric, "tick_to_limit");
+        assert!(!verdict.checks[1].pass);
+        assert!(!verdict.pass);
+    }
+
+    #[test]
+    fn test_merge_shard() {
+        let mut shard0 = MetricsCollector::new(100_000, 3).unwrap();
+        let mut shard1 = MetricsCollector::new(100_000, 3).unwrap();
+        for latency in 1..=100 {
+            shard0.record(latency).unwrap();
+            shard1.record(latency + 1_000).unwrap();
+        }
+        let mut timeline = StageTimeline::default();
+        timeline.set(Stage::FeedReceive, 1_000);
+        timeline.set(Stage::LimitSent, 6_000);
+        shard1.record_timeline(&timeline);
+
+        let mut aggregate = MetricsCollector::new(100_000, 3).unwrap();
+        aggregate.merge_shard(0, 0..150, &shard0).unwrap();
+        aggregate.merge_shard(1, 150..300, &shard1).unwrap();
+
+        assert_eq!(aggregate.count(), 200);
+        assert_eq!(aggregate.percentile(0.25), 50);
+        assert!(close(aggregate.percentile(0.99), 1_099));
+        let summary = aggregate.generate_summary(1.0);
+        assert_eq!(summary.shards.len(), 2);
+        assert_eq!(summary.shards[1].first_symbol_id, 150);
+        assert_eq!(summary.shards[1].count, 100);
+        assert!(close(summary.shards[1].p50, 1_050));
+        let tick_to_limit = summary.stages.last().unwrap();
+        assert_eq!(tick_to_limit.stage, "tick_to_limit");
+        assert!(close(tick_to_limit.p50, 5_000));
+    }
+
+    #[test]
+    fn test_record_timeline() {
+        let mut collector = MetricsCollector::new(100_000, 3).unwrap();
+
+        let mut timeline = StageTimeline::default();
+        timeline.set(Stage::FeedReceive, 1_000);
+        timeline.set(Stage::DecodeDone, 1_500);
+        timeline.set(Stage::TriggerDecided, 2_000);
+        // BuySerialized not reached: BuySent is measured from TriggerDecided
+        timeline.set(Stage::BuySent, 5_000);
+        timeline.set(Stage::AckReceived, 105_000);
+        timeline.set(Stage::LimitSent, 106_000);
+        collector.record_timeline(&timeline);
+
+        // A tick that never triggered
+        let mut timeline = StageTimeline::default();
+        timeline.set(Stage::FeedReceive, 10_000);
+        timeline.set(Stage::DecodeDone, 10_700);
+        collector.record_timeline(&timeline);
+
+        let stages = collector.generate_summary(1.0).stages;
+        let get = |name: &str| stages.iter().find(|s| s.stage == name).cloned();
+        assert!(get("feed_receive").is_none());
+        assert!(get("buy_serialized").is_none());
+        assert_eq!(get("decode_done").unwrap().count, 2);
+        assert_eq!(get("decode_done").unwrap().max, 700);
+        assert!(close(get("buy_sent").unwrap().p50, 3_000));
+        assert!(close(get("ack_received").unwrap().p50, 100_000));
+        assert_eq!(get("tick_to_limit").unwrap().count, 1);
+        assert!(close(get("tick_to_limit").unwrap().p50, 105_000));
+        // Stage order is preserved, total last
+        assert_eq!(stages.last().unwrap().stage, "tick_to_limit");
+        assert_eq!(stages[0].stage, "decode_done");
+    }
 }
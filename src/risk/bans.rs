@@ -0,0 +1,257 @@
+//! Per-symbol buy bans: listed in the config, while the exchange status is
+//! not TRADING, and for a while after a losing trade
+//!
+//! Each source keeps its own bits under a lock (off hot path); their union
+//! is published as an atomic bitset, so the hot path checks an unbanned
+//! symbol with one atomic load. A set bit is then judged against the
+//! symbol's published expiry in trade time, like the buying freeze.
+
+/// Published expiry of a ban that only time does not lift
+const NO_EXPIRY: u64 = u64::MAX;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Word index and bit mask of `symbol_id` in a bitset
+fn bit(symbol_id: u32) -> (usize, u64) {
+    (symbol_id as usize / 64, 1 << (symbol_id % 64))
+}
+
+/// Ban bits per source, indexed like the published bitset
+#[derive(Debug)]
+struct BanSources {
+    config: Vec<u64>,
+    not_trading: Vec<u64>,
+    loss: Vec<u64>,
+    /// Expiry of each loss ban (unix ms; 0 = none)
+    loss_until_ms: Vec<u64>,
+}
+
+/// Ban state of every symbol_id below `max_symbols`
+#[derive(Debug)]
+pub struct SymbolBans {
+    /// Union of the sources: bit set = no trigger for the symbol until
+    /// its `banned_until` expiry
+    banned: Box<[AtomicU64]>,
+    /// Per symbol: loss ban expiry in trade time (unix ms), or `NO_EXPIRY`
+    /// while another source bans it
+    banned_until: Box<[AtomicU64]>,
+    sources: Mutex<BanSources>,
+    /// Length of a ban after a losing trade (0 = no loss bans)
+    loss_ban_ms: u64,
+}
+
+impl SymbolBans {
+    pub fn new(max_symbols: usize, loss_ban_secs: u64) -> Self {
+        let words = max_symbols.div_ceil(64);
+        Self {
+            banned: (0..words).map(|_| AtomicU64::new(0)).collect(),
+            banned_until: (0..max_symbols).map(|_| AtomicU64::new(0)).collect(),
+            sources: Mutex::new(BanSources {
+                config: vec![0; words],
+                not_trading: vec![0; words],
+                loss: vec![0; words],
+                loss_until_ms: vec![0; max_symbols],
+            }),
+            loss_ban_ms: loss_ban_secs * 1_000,
+        }
+    }
+
+    /// Whether triggers for `symbol_id` are banned at trade time
+    /// `ts_unix_ms` (hot path: one atomic load unless the symbol is banned)
+    pub fn is_banned(&self, symbol_id: u32, ts_unix_ms: u64) -> bool {
+        let (word, mask) = bit(symbol_id);
+        self.banned
+            .get(word)
+            .is_some_and(|bits| bits.load(Ordering::Relaxed) & mask != 0)
+            && self
+                .banned_until
+                .get(symbol_id as usize)
+                .is_some_and(|until| ts_unix_ms < until.load(Ordering::Relaxed))
+    }
+
+    /// Replace the config bans with `symbol_ids`
+    pub fn set_config_bans(&self, symbol_ids: impl IntoIterator<Item = u32>) {
+        self.replace(symbol_ids, |sources| &mut sources.config);
+    }
+
+    /// Replace the not-TRADING bans with `symbol_ids`
+    pub fn set_not_trading(&self, symbol_ids: impl IntoIterator<Item = u32>) {
+        self.replace(symbol_ids, |sources| &mut sources.not_trading);
+    }
+
+    /// Ban `symbol_id` for the configured period after a losing trade that
+    /// closed at `exit_ts_unix_ms`; returns the expiry (None if loss bans are
+    /// off or the id is out of range). An earlier expiry is never shortened.
+    pub fn ban_after_loss(&self, symbol_id: u32, exit_ts_unix_ms: u64) -> Option<u64> {
+        if self.loss_ban_ms == 0 {
+            return None;
+        }
+        let mut sources = self.sources.lock().unwrap_or_else(|e| e.into_inner());
+        let until = sources.loss_until_ms.get_mut(symbol_id as usize)?;
+        *until = (*until).max(exit_ts_unix_ms + self.loss_ban_ms);
+        let until = *until;
+        let (word, mask) = bit(symbol_id);
+        sources.loss[word] |= mask;
+        self.publish(&sources, word);
+        Some(until)
+    }
+
+    /// Clear loss bans that expired by trade time `now_ms` (off hot path;
+    /// the hot path already ignores them); returns the symbol_ids lifted
+    pub fn expire(&self, now_ms: u64) -> Vec<u32> {
+        let mut sources = self.sources.lock().unwrap_or_else(|e| e.into_inner());
+        let mut lifted = Vec::new();
+        for symbol_id in 0..sources.loss_until_ms.len() as u32 {
+            let until = &mut sources.loss_until_ms[symbol_id as usize];
+            if *until == 0 || now_ms < *until {
+                continue;
+            }
+            *until = 0;
+            let (word, mask) = bit(symbol_id);
+            sources.loss[word] &= !mask;
+            self.publish(&sources, word);
+            lifted.push(symbol_id);
+        }
+        lifted
+    }
+
+    /// Number of symbols banned at trade time `now_ms`
+    pub fn count(&self, now_ms: u64) -> usize {
+        (0..self.banned_until.len() as u32)
+            .filter(|&symbol_id| self.is_banned(symbol_id, now_ms))
+            .count()
+    }
+
+    fn replace(
+        &self,
+        symbol_ids: impl IntoIterator<Item = u32>,
+        source: impl Fn(&mut BanSources) -> &mut Vec<u64>,
+    ) {
+        let mut sources = self.sources.lock().unwrap_or_else(|e| e.into_inner());
+        let bits = source(&mut sources);
+        bits.fill(0);
+        for symbol_id in symbol_ids {
+            let (word, mask) = bit(symbol_id);
+            if let Some(bits) = bits.get_mut(word) {
+                *bits |= mask;
+            }
+        }
+        for word in 0..self.banned.len() {
+            self.publish(&sources, word);
+        }
+    }
+
+    /// Store the union of the sources for one word, expiries first
+    /// (callers hold the lock, so a plain store cannot lose another writer's
+    /// bits)
+    fn publish(&self, sources: &BanSources, word: usize) {
+        let hard = sources.config[word] | sources.not_trading[word];
+        let symbols = word * 64..(word * 64 + 64).min(self.banned_until.len());
+        for symbol_id in symbols {
+            let until = if hard & bit(symbol_id as u32).1 != 0 {
+                NO_EXPIRY
+            } else {
+                sources.loss_until_ms[symbol_id]
+            };
+            self.banned_until[symbol_id].store(until, Ordering::Relaxed);
+        }
+        self.banned[word].store(hard | sources.loss[word], Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    const T0: u64 = 1_700_000_000_000;
+
+    #[test]
+    fn test_sources_combine_into_one_bit() {
+        let bans = SymbolBans::new(130, 600);
+        assert!(!bans.is_banned(0, T0));
+        assert!(!bans.is_banned(500, T0));
+
+        bans.set_config_bans([3, 129, 500]);
+        bans.set_not_trading([3, 64]);
+        assert_eq!(bans.ban_after_loss(65, T0), Some(T0 + 600_000));
+        let banned: Vec<u32> = (0..130).filter(|&id| bans.is_banned(id, T0)).collect();
+        assert_eq!(banned, vec![3, 64, 65, 129]);
+        assert_eq!(bans.count(T0), 4);
+
+        // Replacing one source keeps the others
+        bans.set_config_bans([]);
+        bans.set_not_trading([]);
+        let banned: Vec<u32> = (0..130).filter(|&id| bans.is_banned(id, T0)).collect();
+        assert_eq!(banned, vec![65]);
+    }
+
+    #[test]
+    fn test_loss_bans_expire() {
+        let bans = SymbolBans::new(8, 600);
+        bans.ban_after_loss(1, T0);
+        bans.ban_after_loss(2, T0 + 100_000);
+        // A later loss extends, an earlier report never shortens
+        assert_eq!(bans.ban_after_loss(2, T0), Some(T0 + 700_000));
+        bans.set_config_bans([1]);
+
+        // Judged on trade time before any expire pass
+        assert!(bans.is_banned(2, T0 + 699_999));
+        assert!(!bans.is_banned(2, T0 + 700_000));
+        assert_eq!(bans.count(T0 + 700_000), 1);
+
+        assert_eq!(bans.expire(T0 + 599_999), Vec::<u32>::new());
+        assert_eq!(bans.expire(T0 + 600_000), vec![1]);
+        // Still listed in the config
+        assert!(bans.is_banned(1, u64::MAX - 1));
+        assert!(bans.is_banned(2, T0));
+        assert_eq!(bans.expire(T0 + 700_000), vec![2]);
+        assert!(!bans.is_banned(2, T0));
+
+        let off = SymbolBans::new(8, 0);
+        assert_eq!(off.ban_after_loss(1, T0), None);
+        assert!(!off.is_banned(1, T0));
+        assert_eq!(bans.ban_after_loss(8, T0), None);
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_every_source() {
+        // Writers flip sources of symbols sharing one word while a loss ban
+        // on symbol 0 must stay visible throughout
+        let bans = Arc::new(SymbolBans::new(64, 3_600));
+        bans.ban_after_loss(0, T0);
+        let done = Arc::new(AtomicBool::new(false));
+
+        let reader = {
+            let (bans, done) = (Arc::clone(&bans), Arc::clone(&done));
+            std::thread::spawn(move || {
+                while !done.load(Ordering::Acquire) {
+                    assert!(bans.is_banned(0, T0));
+                }
+            })
+        };
+        let writers: Vec<_> = (0..2u32)
+            .map(|writer| {
+                let bans = Arc::clone(&bans);
+                std::thread::spawn(move || {
+                    for round in 0..2_000u32 {
+                        let id = 1 + (round + writer) % 63;
+                        match writer {
+                            0 => bans.set_config_bans([id]),
+                            _ => bans.set_not_trading([id]),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        done.store(true, Ordering::Release);
+        reader.join().unwrap();
+        assert!(bans.is_banned(0, T0));
+        assert_eq!(bans.count(T0), 3);
+    }
+}
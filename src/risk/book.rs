@@ -0,0 +1,348 @@
+//! Position book: per-symbol quantity, average entry, open take-profit and
+//! realized/unrealized PnL
+//!
+//! Fills come from the execution engine and marks from the trade stream,
+//! both off the hot path. PnL is in quote e8 before fees.
+
+use crate::execution::{mul_div, ExecutionReport, Order, E8};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Resting take-profit of a position and what it has filled so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenTakeProfit {
+    pub client_order_id: u64,
+    pub px_e8: u64,
+    pub qty_e8: u64,
+    /// Cumulative filled base quantity
+    pub filled_qty_e8: u64,
+    /// Cumulative filled quote amount
+    pub filled_quote_e8: u64,
+}
+
+/// Book entry of one symbol
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    /// Base quantity held
+    qty_e8: u64,
+    /// Quote amount paid for `qty_e8`
+    cost_e8: u64,
+    take_profit: Option<OpenTakeProfit>,
+    realized_pnl_e8: i64,
+}
+
+impl Entry {
+    /// Sell `qty_e8` for `quote_e8`: the quantity leaves at its average cost
+    /// and the difference is realized
+    fn release(&mut self, qty_e8: u64, quote_e8: u64) {
+        if qty_e8 == 0 {
+            return;
+        }
+        let cost_e8 = if qty_e8 == self.qty_e8 {
+            self.cost_e8
+        } else {
+            mul_div(self.cost_e8, qty_e8, self.qty_e8)
+        };
+        self.qty_e8 -= qty_e8;
+        self.cost_e8 -= cost_e8;
+        self.realized_pnl_e8 += quote_e8 as i64 - cost_e8 as i64;
+    }
+
+    /// Whether the symbol holds, is selling or ever traded a position
+    fn traded(&self) -> bool {
+        self.qty_e8 > 0 || self.take_profit.is_some() || self.realized_pnl_e8 != 0
+    }
+}
+
+/// One symbol's position marked to its latest trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    pub symbol_id: u32,
+    pub qty_e8: u64,
+    /// Average entry price (0 when flat)
+    pub avg_entry_px_e8: u64,
+    /// Latest trade price (the entry price until a trade arrives)
+    pub mark_px_e8: u64,
+    pub take_profit: Option<OpenTakeProfit>,
+    pub realized_pnl_e8: i64,
+    pub unrealized_pnl_e8: i64,
+}
+
+/// Totals over the book for JSON output
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookSummary {
+    pub open_positions: usize,
+    /// Quote amount paid for the quantity held
+    pub exposure_e8: u64,
+    pub realized_pnl_e8: i64,
+    pub unrealized_pnl_e8: i64,
+    /// Symbols that hold a position or have traded
+    pub positions: Vec<PositionSnapshot>,
+}
+
+impl fmt::Display for BookSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} open, exposure {:.2}, realized {:.4}, unrealized {:.4}",
+            self.open_positions,
+            self.exposure_e8 as f64 / 1e8,
+            self.realized_pnl_e8 as f64 / 1e8,
+            self.unrealized_pnl_e8 as f64 / 1e8
+        )
+    }
+}
+
+/// Positions of every symbol_id below `max_symbols`
+#[derive(Debug)]
+pub struct PositionBook {
+    entries: Mutex<Vec<Entry>>,
+    /// Latest trade price per symbol (0 = none yet)
+    marks: Box<[AtomicU64]>,
+}
+
+impl PositionBook {
+    pub fn new(max_symbols: usize) -> Self {
+        Self {
+            entries: Mutex::new(vec![Entry::default(); max_symbols]),
+            marks: (0..max_symbols).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Mark `symbol_id` to a trade price (one atomic store per tick)
+    pub fn mark(&self, symbol_id: u32, px_e8: u64) {
+        if let Some(mark) = self.marks.get(symbol_id as usize) {
+            mark.store(px_e8, Ordering::Relaxed);
+        }
+    }
+
+    /// Add a BUY's fill (its final report) to the position
+    pub fn on_buy_fill(&self, report: &ExecutionReport) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(report.symbol_id as usize) {
+            entry.qty_e8 += report.executed_qty_e8;
+            entry.cost_e8 += report.cum_quote_e8;
+        }
+    }
+
+    /// Record `take_profit` as resting for its symbol
+    pub fn open_take_profit(&self, take_profit: &Order) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(take_profit.symbol_id as usize) {
+            entry.take_profit = Some(OpenTakeProfit {
+                client_order_id: take_profit.client_order_id,
+                px_e8: take_profit.px_e8,
+                qty_e8: take_profit.qty_e8,
+                filled_qty_e8: 0,
+                filled_quote_e8: 0,
+            });
+        }
+    }
+
+    /// Apply a report of the resting take-profit: newly filled quantity
+    /// leaves the position at its average cost and realizes PnL; a final
+    /// report closes the order. Reports of other orders are ignored.
+    pub fn on_take_profit_report(&self, report: &ExecutionReport) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(entry) = entries.get_mut(report.symbol_id as usize) else {
+            return;
+        };
+        let Some(tp) = entry
+            .take_profit
+            .as_mut()
+            .filter(|tp| tp.client_order_id == report.client_order_id)
+        else {
+            return;
+        };
+        let qty_e8 = report
+            .executed_qty_e8
+            .saturating_sub(tp.filled_qty_e8)
+            .min(entry.qty_e8);
+        let quote_e8 = report.cum_quote_e8.saturating_sub(tp.filled_quote_e8);
+        tp.filled_qty_e8 = report.executed_qty_e8;
+        tp.filled_quote_e8 = report.cum_quote_e8;
+        if report.status.is_final() {
+            entry.take_profit = None;
+        }
+        entry.release(qty_e8, quote_e8);
+    }
+
+    /// Release a SELL's fill (its final report) from the position, e.g. a
+    /// MARKET SELL flattening it
+    pub fn on_sell_fill(&self, report: &ExecutionReport) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(report.symbol_id as usize) {
+            let qty_e8 = report.executed_qty_e8.min(entry.qty_e8);
+            entry.release(qty_e8, report.cum_quote_e8);
+        }
+    }
+
+    /// Position of `symbol_id`; None if it never traded
+    pub fn position(&self, symbol_id: u32) -> Option<PositionSnapshot> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.get(symbol_id as usize)?;
+        entry.traded().then(|| self.snapshot(symbol_id, entry))
+    }
+
+    /// Quote amount paid for everything held
+    pub fn exposure_e8(&self) -> u64 {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.iter().map(|entry| entry.cost_e8).sum()
+    }
+
+    /// Every position and the book's totals
+    pub fn summary(&self) -> BookSummary {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let positions: Vec<PositionSnapshot> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.traded())
+            .map(|(symbol_id, entry)| self.snapshot(symbol_id as u32, entry))
+            .collect();
+        BookSummary {
+            open_positions: positions.iter().filter(|p| p.qty_e8 > 0).count(),
+            exposure_e8: entries.iter().map(|entry| entry.cost_e8).sum(),
+            realized_pnl_e8: positions.iter().map(|p| p.realized_pnl_e8).sum(),
+            unrealized_pnl_e8: positions.iter().map(|p| p.unrealized_pnl_e8).sum(),
+            positions,
+        }
+    }
+
+    fn snapshot(&self, symbol_id: u32, entry: &Entry) -> PositionSnapshot {
+        let avg_entry_px_e8 = match entry.qty_e8 {
+            0 => 0,
+            qty_e8 => mul_div(entry.cost_e8, E8, qty_e8),
+        };
+        let mark_px_e8 = match self.marks[symbol_id as usize].load(Ordering::Relaxed) {
+            0 => avg_entry_px_e8,
+            px_e8 => px_e8,
+        };
+        PositionSnapshot {
+            symbol_id,
+            qty_e8: entry.qty_e8,
+            avg_entry_px_e8,
+            mark_px_e8,
+            take_profit: entry.take_profit,
+            realized_pnl_e8: entry.realized_pnl_e8,
+            unrealized_pnl_e8: match entry.qty_e8 {
+                0 => 0,
+                qty_e8 => mul_div(qty_e8, mark_px_e8, E8) as i64 - entry.cost_e8 as i64,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::{OrderKind, OrderStatus, Side};
+
+    const USDT: u64 = 100_000_000;
+
+    fn report(
+        client_order_id: u64,
+        side: Side,
+        status: OrderStatus,
+        qty_e8: u64,
+        quote_e8: u64,
+    ) -> ExecutionReport {
+        ExecutionReport {
+            client_order_id,
+            symbol_id: 3,
+            side,
+            status,
+            executed_qty_e8: qty_e8,
+            cum_quote_e8: quote_e8,
+        }
+    }
+
+    fn take_profit(client_order_id: u64, qty_e8: u64) -> Order {
+        Order {
+            client_order_id,
+            symbol_id: 3,
+            side: Side::Sell,
+            kind: OrderKind::LimitGtc,
+            px_e8: 11 * USDT,
+            qty_e8,
+            quote_qty_e8: 0,
+        }
+    }
+
+    #[test]
+    fn test_position_marked_to_the_latest_trade() {
+        let book = PositionBook::new(8);
+        assert_eq!(book.position(3), None);
+
+        // 2 at 10.00, mark 10.50 before the take-profit
+        book.on_buy_fill(&report(
+            1,
+            Side::Buy,
+            OrderStatus::Filled,
+            2 * USDT,
+            20 * USDT,
+        ));
+        book.mark(3, 10 * USDT + USDT / 2);
+        book.open_take_profit(&take_profit(2, 2 * USDT));
+        let position = book.position(3).unwrap();
+        assert_eq!(position.qty_e8, 2 * USDT);
+        assert_eq!(position.avg_entry_px_e8, 10 * USDT);
+        assert_eq!(position.unrealized_pnl_e8, USDT as i64);
+        assert_eq!(position.take_profit.unwrap().px_e8, 11 * USDT);
+
+        let summary = book.summary();
+        assert_eq!(summary.open_positions, 1);
+        assert_eq!(summary.exposure_e8, 20 * USDT);
+        assert_eq!(
+            summary.to_string(),
+            "1 open, exposure 20.00, realized 0.0000, unrealized 1.0000"
+        );
+    }
+
+    #[test]
+    fn test_take_profit_fills_realize_pnl() {
+        let book = PositionBook::new(8);
+        book.on_buy_fill(&report(
+            1,
+            Side::Buy,
+            OrderStatus::Filled,
+            2 * USDT,
+            20 * USDT,
+        ));
+        book.open_take_profit(&take_profit(2, 2 * USDT));
+        book.mark(3, 11 * USDT);
+
+        // Cumulative reports: half, a repeat, then the rest
+        let half = report(2, Side::Sell, OrderStatus::PartiallyFilled, USDT, 11 * USDT);
+        book.on_take_profit_report(&half);
+        book.on_take_profit_report(&half);
+        let position = book.position(3).unwrap();
+        assert_eq!(
+            (position.qty_e8, position.realized_pnl_e8),
+            (USDT, USDT as i64)
+        );
+        assert_eq!(position.unrealized_pnl_e8, USDT as i64);
+        assert_eq!(position.take_profit.unwrap().filled_qty_e8, USDT);
+
+        // Another order's report changes nothing
+        book.on_take_profit_report(&report(9, Side::Sell, OrderStatus::Filled, USDT, USDT));
+        book.on_take_profit_report(&report(
+            2,
+            Side::Sell,
+            OrderStatus::Filled,
+            2 * USDT,
+            22 * USDT,
+        ));
+        let position = book.position(3).unwrap();
+        assert_eq!(position.qty_e8, 0);
+        assert_eq!(position.take_profit, None);
+        assert_eq!(position.realized_pnl_e8, 2 * USDT as i64);
+        assert_eq!(position.unrealized_pnl_e8, 0);
+
+        let summary = book.summary();
+        assert_eq!((summary.open_positions, summary.exposure_e8), (0, 0));
+        assert_eq!(summary.realized_pnl_e8, 2 * USDT as i64);
+        assert_eq!(summary.positions.len(), 1);
+    }
+}
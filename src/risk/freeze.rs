@@ -0,0 +1,259 @@
+//! Buying freeze after a losing streak or a daily drawdown
+//!
+//! Closed trades are recorded off the hot path; the hot path only compares
+//! the tick's timestamp against the published freeze expiry.
+
+use crate::config::FreezeConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Milliseconds in a (UTC) trading day
+const DAY_MS: u64 = 86_400_000;
+
+/// Why buying was frozen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FreezeReason {
+    /// `losses` closed trades in a row without profit
+    LossStreak { losses: u32 },
+    /// Realized PnL fell this far below the day's peak (e8)
+    DailyDrawdown { drawdown_e8: u64, limit_e8: u64 },
+}
+
+impl fmt::Display for FreezeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FreezeReason::LossStreak { losses } => write!(f, "{} consecutive losses", losses),
+            FreezeReason::DailyDrawdown {
+                drawdown_e8,
+                limit_e8,
+            } => write!(
+                f,
+                "daily drawdown {:.2} >= {:.2}",
+                *drawdown_e8 as f64 / 1e8,
+                *limit_e8 as f64 / 1e8
+            ),
+        }
+    }
+}
+
+/// An active freeze
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FreezeStatus {
+    pub reason: FreezeReason,
+    /// Exit time of the trade that caused it (unix ms)
+    pub since_unix_ms: u64,
+    /// Buying resumes at this trade time (unix ms)
+    pub until_unix_ms: u64,
+}
+
+impl fmt::Display for FreezeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "buying frozen for {}s until {} ({})",
+            (self.until_unix_ms - self.since_unix_ms) / 1_000,
+            self.until_unix_ms,
+            self.reason
+        )
+    }
+}
+
+/// Trade outcomes the freeze decisions are based on
+#[derive(Debug, Default)]
+struct Outcomes {
+    /// Closed trades without profit since the last win or freeze
+    loss_streak: u32,
+    /// UTC day of `day_pnl_e8`
+    day: u64,
+    /// Realized PnL of the day so far
+    day_pnl_e8: i64,
+    /// Highest `day_pnl_e8` of the day (at least 0)
+    day_peak_e8: i64,
+    /// Latest freeze (may have expired)
+    status: Option<FreezeStatus>,
+}
+
+/// Freeze state machine shared by the hot path and the execution side
+#[derive(Debug)]
+pub struct Freeze {
+    config: FreezeConfig,
+    /// Trade time (unix ms) buying resumes at; 0 when not frozen
+    until_ms: AtomicU64,
+    outcomes: Mutex<Outcomes>,
+}
+
+impl Freeze {
+    pub fn new(config: FreezeConfig) -> Self {
+        Self {
+            config,
+            until_ms: AtomicU64::new(0),
+            outcomes: Mutex::new(Outcomes::default()),
+        }
+    }
+
+    /// Whether buying is frozen at trade time `ts_unix_ms` (hot path: one
+    /// atomic load)
+    pub fn is_frozen(&self, ts_unix_ms: u64) -> bool {
+        ts_unix_ms < self.until_ms.load(Ordering::Relaxed)
+    }
+
+    /// Record a closed trade (off hot path); returns the freeze it started
+    pub fn record_trade(&self, pnl_e8: i64, exit_ts_unix_ms: u64) -> Option<FreezeStatus> {
+        let mut outcomes = self.outcomes.lock().unwrap_or_else(|e| e.into_inner());
+        let day = exit_ts_unix_ms / DAY_MS;
+        if day != outcomes.day {
+            outcomes.day = day;
+            outcomes.day_pnl_e8 = 0;
+            outcomes.day_peak_e8 = 0;
+        }
+        outcomes.day_pnl_e8 += pnl_e8;
+        outcomes.day_peak_e8 = outcomes.day_peak_e8.max(outcomes.day_pnl_e8);
+        if pnl_e8 > 0 {
+            outcomes.loss_streak = 0;
+            return None;
+        }
+        outcomes.loss_streak += 1;
+
+        let drawdown_e8 = (outcomes.day_peak_e8 - outcomes.day_pnl_e8) as u64;
+        let reason = match self.config.daily_drawdown_limit_e8 {
+            Some(limit_e8) if drawdown_e8 >= limit_e8 => FreezeReason::DailyDrawdown {
+                drawdown_e8,
+                limit_e8,
+            },
+            _ if self.config.max_consecutive_losses > 0
+                && outcomes.loss_streak >= self.config.max_consecutive_losses =>
+            {
+                FreezeReason::LossStreak {
+                    losses: outcomes.loss_streak,
+                }
+            }
+            _ => return None,
+        };
+        outcomes.loss_streak = 0;
+
+        let until_unix_ms = exit_ts_unix_ms + self.config.freeze_secs * 1_000;
+        let until_unix_ms = until_unix_ms.max(self.until_ms.load(Ordering::Relaxed));
+        self.until_ms.store(until_unix_ms, Ordering::Relaxed);
+        let status = FreezeStatus {
+            reason,
+            since_unix_ms: exit_ts_unix_ms,
+            until_unix_ms,
+        };
+        outcomes.status = Some(status);
+        Some(status)
+    }
+
+    /// Lift the freeze now (manual); returns the freeze that was lifted.
+    /// The losing streak starts over; the day's drawdown is kept, so a
+    /// further loss while it is over the limit freezes again.
+    pub fn unfreeze(&self) -> Option<FreezeStatus> {
+        let mut outcomes = self.outcomes.lock().unwrap_or_else(|e| e.into_inner());
+        self.until_ms.store(0, Ordering::Relaxed);
+        outcomes.loss_streak = 0;
+        outcomes.status.take()
+    }
+
+    /// Latest freeze, even if it has expired (None once manually lifted)
+    pub fn latest(&self) -> Option<FreezeStatus> {
+        let outcomes = self.outcomes.lock().unwrap_or_else(|e| e.into_inner());
+        outcomes.status
+    }
+
+    /// Freeze in effect at trade time `now_ms`
+    pub fn status(&self, now_ms: u64) -> Option<FreezeStatus> {
+        let outcomes = self.outcomes.lock().unwrap_or_else(|e| e.into_inner());
+        outcomes
+            .status
+            .filter(|status| now_ms < status.until_unix_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: u64 = 1_700_000_000_000;
+    const USDT: i64 = 100_000_000;
+
+    fn freeze(max_consecutive_losses: u32, daily_drawdown_limit_e8: Option<u64>) -> Freeze {
+        Freeze::new(FreezeConfig {
+            max_consecutive_losses,
+            daily_drawdown_limit_e8,
+            freeze_secs: 600,
+        })
+    }
+
+    #[test]
+    fn test_loss_streak_freezes_for_the_configured_period() {
+        let freeze = freeze(3, None);
+        assert_eq!(freeze.record_trade(-USDT, T0), None);
+        // A win resets the streak
+        assert_eq!(freeze.record_trade(2 * USDT, T0 + 1_000), None);
+        assert_eq!(freeze.record_trade(-USDT, T0 + 2_000), None);
+        assert_eq!(freeze.record_trade(0, T0 + 3_000), None);
+        let status = freeze.record_trade(-USDT, T0 + 4_000).unwrap();
+
+        assert_eq!(status.reason, FreezeReason::LossStreak { losses: 3 });
+        assert_eq!(status.until_unix_ms, T0 + 604_000);
+        assert_eq!(
+            status.to_string(),
+            "buying frozen for 600s until 1700000604000 (3 consecutive losses)"
+        );
+        assert!(freeze.is_frozen(T0 + 4_000));
+        assert!(freeze.is_frozen(T0 + 603_999));
+        assert_eq!(freeze.status(T0 + 10_000), Some(status));
+        // Expires without any call
+        assert!(!freeze.is_frozen(T0 + 604_000));
+        assert_eq!(freeze.status(T0 + 604_000), None);
+        assert_eq!(freeze.latest(), Some(status));
+    }
+
+    #[test]
+    fn test_daily_drawdown_from_the_days_peak() {
+        let freeze = freeze(0, Some(5 * USDT as u64));
+        assert_eq!(freeze.record_trade(4 * USDT, T0), None);
+        assert_eq!(freeze.record_trade(-3 * USDT, T0 + 1_000), None);
+        let status = freeze.record_trade(-2 * USDT, T0 + 2_000).unwrap();
+        assert_eq!(
+            status.reason,
+            FreezeReason::DailyDrawdown {
+                drawdown_e8: 5 * USDT as u64,
+                limit_e8: 5 * USDT as u64,
+            }
+        );
+        assert_eq!(status.reason.to_string(), "daily drawdown 5.00 >= 5.00");
+
+        // A new UTC day starts from zero
+        let next_day = (T0 / DAY_MS + 1) * DAY_MS;
+        assert_eq!(freeze.record_trade(-4 * USDT, next_day), None);
+        assert!(freeze.record_trade(-USDT, next_day + 1).is_some());
+    }
+
+    #[test]
+    fn test_manual_unfreeze() {
+        let freeze = freeze(2, Some(10 * USDT as u64));
+        freeze.record_trade(-USDT, T0);
+        let status = freeze.record_trade(-USDT, T0 + 1_000).unwrap();
+
+        assert_eq!(freeze.unfreeze(), Some(status));
+        assert!(!freeze.is_frozen(T0 + 2_000));
+        assert_eq!(freeze.status(T0 + 2_000), None);
+        assert_eq!(freeze.unfreeze(), None);
+        // The streak starts over
+        assert_eq!(freeze.record_trade(-USDT, T0 + 3_000), None);
+        // Still in the day's drawdown: another loss over the limit refreezes
+        assert!(freeze.record_trade(-8 * USDT, T0 + 4_000).is_some());
+    }
+
+    #[test]
+    fn test_later_freeze_never_shortens_an_earlier_one() {
+        let freeze = freeze(1, None);
+        let first = freeze.record_trade(-USDT, T0 + 500_000).unwrap();
+        // A trade that closed earlier is reported late
+        let second = freeze.record_trade(-USDT, T0).unwrap();
+        assert_eq!(second.until_unix_ms, first.until_unix_ms);
+    }
+}
@@ -0,0 +1,422 @@
+//! Risk: position slots (A/B) with per-slot capital allocation, the buying
+//! freeze after losses, per-symbol bans and the position book
+//!
+//! A trigger only becomes an order once it holds a slot. Slots are reserved
+//! on the hot path with one compare-and-swap and released by the execution
+//! engine when the take-profit completes, a flatten SELL sells what it left,
+//! or the BUY ends without a position.
+
+use crate::config::Config;
+use crate::symbols::SymbolRegistry;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub mod bans;
+pub mod book;
+pub mod freeze;
+
+pub use bans::SymbolBans;
+pub use book::{BookSummary, PositionBook};
+pub use freeze::{Freeze, FreezeStatus};
+
+/// Most position slots a config may declare
+pub const MAX_POSITION_SLOTS: usize = 16;
+
+/// `holder` value of a free slot
+const FREE: u64 = u64::MAX;
+
+/// Risk state shared by the hot path, the execution engine and maintenance
+#[derive(Debug)]
+pub struct Risk {
+    pub slots: PositionSlots,
+    pub freeze: Freeze,
+    pub bans: SymbolBans,
+    pub book: PositionBook,
+}
+
+/// What a closed trade changed in the risk state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TradeRisk {
+    /// Freeze the trade started
+    pub freeze: Option<FreezeStatus>,
+    /// Expiry of the symbol's ban after a losing trade (unix ms)
+    pub banned_until_unix_ms: Option<u64>,
+}
+
+impl Risk {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            slots: PositionSlots::new(config),
+            freeze: Freeze::new(config.risk.freeze),
+            bans: SymbolBans::new(config.max_symbols, config.risk.loss_ban_secs),
+            book: PositionBook::new(config.max_symbols),
+        }
+    }
+
+    /// Record a closed trade of `symbol_id` (off hot path): feeds the freeze
+    /// and bans the symbol after a loss
+    pub fn record_trade(&self, symbol_id: u32, pnl_e8: i64, exit_ts_unix_ms: u64) -> TradeRisk {
+        TradeRisk {
+            freeze: self.freeze.record_trade(pnl_e8, exit_ts_unix_ms),
+            banned_until_unix_ms: if pnl_e8 > 0 {
+                None
+            } else {
+                self.bans.ban_after_loss(symbol_id, exit_ts_unix_ms)
+            },
+        }
+    }
+
+    /// Ban the symbols of `config.banned_symbols`, replacing earlier config
+    /// bans; returns the names missing from the registry
+    pub fn apply_config_bans(&self, config: &Config, registry: &SymbolRegistry) -> Vec<String> {
+        let mut unknown = Vec::new();
+        let mut ids = Vec::with_capacity(config.banned_symbols.len());
+        for symbol in &config.banned_symbols {
+            match registry.id_of(symbol) {
+                Some(id) => ids.push(id),
+                None => unknown.push(symbol.clone()),
+            }
+        }
+        self.bans.set_config_bans(ids);
+        unknown
+    }
+
+    /// Ban every registry symbol whose status is not TRADING (after each
+    /// registry load); returns how many are banned
+    pub fn apply_symbol_status(&self, registry: &SymbolRegistry) -> usize {
+        let not_trading: Vec<u32> = (0..registry.len() as u32)
+            .filter(|&id| registry.info(id).is_some_and(|info| !info.is_trading()))
+            .collect();
+        let count = not_trading.len();
+        self.bans.set_not_trading(not_trading);
+        count
+    }
+}
+
+/// A slot held for one symbol's trigger; travels with the trigger to the
+/// execution engine, which releases it when the position ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotReservation {
+    /// Index into the slot table
+    pub slot: usize,
+    /// Symbol holding the slot
+    pub symbol_id: u32,
+    /// Quote amount allocated to the slot (the BUY size) in e8
+    pub quote_qty_e8: u64,
+}
+
+/// One slot: capital allocation and the symbol_id holding it
+#[derive(Debug)]
+struct PositionSlot {
+    name: String,
+    quote_qty_e8: u64,
+    /// symbol_id of the holder, FREE when available
+    holder: AtomicU64,
+}
+
+/// Fixed table of position slots shared by every hot-path slice and engine
+#[derive(Debug)]
+pub struct PositionSlots {
+    slots: Box<[PositionSlot]>,
+    /// Triggers refused because no slot was free or the symbol held one
+    denied: AtomicU64,
+}
+
+impl PositionSlots {
+    /// Slots from `config.risk`; slots without an allocation get
+    /// `buy_quote_qty_e8`
+    pub fn new(config: &Config) -> Self {
+        let slots = config
+            .risk
+            .slots
+            .iter()
+            .map(|slot| PositionSlot {
+                name: slot.name.clone(),
+                quote_qty_e8: slot.quote_qty_e8.unwrap_or(config.buy_quote_qty_e8),
+                holder: AtomicU64::new(FREE),
+            })
+            .collect();
+        Self {
+            slots,
+            denied: AtomicU64::new(0),
+        }
+    }
+
+    /// Reserve the first free slot for `symbol_id` (hot-path safe, no
+    /// allocation). None if every slot is held or the symbol already holds
+    /// one. Reservations for one symbol must come from one thread at a time
+    /// (a symbol belongs to one shard); different symbols may race freely.
+    pub fn reserve(&self, symbol_id: u32) -> Option<SlotReservation> {
+        if self.slot_of(symbol_id).is_none() {
+            for (index, slot) in self.slots.iter().enumerate() {
+                if slot
+                    .holder
+                    .compare_exchange(FREE, symbol_id as u64, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    return Some(SlotReservation {
+                        slot: index,
+                        symbol_id,
+                        quote_qty_e8: slot.quote_qty_e8,
+                    });
+                }
+            }
+        }
+        self.denied.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// Free the slot of `reservation`; false if it was not held by that
+    /// symbol (already released)
+    pub fn release(&self, reservation: &SlotReservation) -> bool {
+        self.slots.get(reservation.slot).is_some_and(|slot| {
+            slot.holder
+                .compare_exchange(
+                    reservation.symbol_id as u64,
+                    FREE,
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+        })
+    }
+
+    /// Slot held by `symbol_id`
+    pub fn slot_of(&self, symbol_id: u32) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| slot.holder.load(Ordering::Acquire) == symbol_id as u64)
+    }
+
+    /// symbol_id holding slot `index`
+    pub fn holder(&self, index: usize) -> Option<u32> {
+        match self.slots.get(index)?.holder.load(Ordering::Acquire) {
+            FREE => None,
+            symbol_id => Some(symbol_id as u32),
+        }
+    }
+
+    /// Config name of slot `index` ("?" if out of range)
+    pub fn name(&self, index: usize) -> &str {
+        self.slots.get(index).map_or("?", |slot| slot.name.as_str())
+    }
+
+    /// Number of slots
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Slots currently held
+    pub fn held(&self) -> usize {
+        (0..self.len())
+            .filter(|&i| self.holder(i).is_some())
+            .count()
+    }
+
+    /// Triggers refused so far
+    pub fn denied(&self) -> u64 {
+        self.denied.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Display for PositionSlots {
+    /// "A: #3 (20.00), B: free (20.00)"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, slot) in self.slots.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            match self.holder(index) {
+                Some(symbol_id) => write!(f, "{}: #{}", slot.name, symbol_id)?,
+                None => write!(f, "{}: free", slot.name)?,
+            }
+            write!(f, " ({:.2})", slot.quote_qty_e8 as f64 / 1e8)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SlotConfig;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::{Arc, Barrier};
+
+    fn slots() -> PositionSlots {
+        let mut config = Config::default();
+        config.risk.slots[1].quote_qty_e8 = Some(5_000_000_000);
+        PositionSlots::new(&config)
+    }
+
+    #[test]
+    fn test_reserve_and_release() {
+        let slots = slots();
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots.name(0), "A");
+
+        let a = slots.reserve(7).unwrap();
+        assert_eq!(
+            a,
+            SlotReservation {
+                slot: 0,
+                symbol_id: 7,
+                quote_qty_e8: 2_000_000_000,
+            }
+        );
+        // One slot per symbol
+        assert_eq!(slots.reserve(7), None);
+        let b = slots.reserve(8).unwrap();
+        assert_eq!((b.slot, b.quote_qty_e8), (1, 5_000_000_000));
+        assert_eq!(slots.reserve(9), None);
+        assert_eq!(slots.denied(), 2);
+        assert_eq!(slots.to_string(), "A: #7 (20.00), B: #8 (50.00)");
+
+        assert!(slots.release(&a));
+        assert!(!slots.release(&a));
+        // A stale reservation does not free a slot someone else holds now
+        assert_eq!(slots.reserve(9).map(|r| r.slot), Some(0));
+        assert!(!slots.release(&a));
+        assert_eq!(slots.slot_of(9), Some(0));
+        assert_eq!(slots.held(), 2);
+    }
+
+    #[test]
+    fn test_slot_count_follows_config() {
+        let mut config = Config::default();
+        config.risk.slots.push(SlotConfig {
+            name: "C".to_string(),
+            quote_qty_e8: Some(1_000_000_000),
+        });
+        let slots = PositionSlots::new(&config);
+        let held: Vec<usize> = (0..5)
+            .filter_map(|id| slots.reserve(id))
+            .map(|r| r.slot)
+            .collect();
+        assert_eq!(held, vec![0, 1, 2]);
+        assert_eq!(slots.holder(2), Some(2));
+        assert_eq!(slots.holder(3), None);
+    }
+
+    #[test]
+    fn test_bans_from_config_status_and_losses() {
+        let registry = SymbolRegistry::new(8);
+        registry
+            .reload_json(
+                r#"{"symbols": [
+                    {"symbol": "AAAUSDT", "status": "TRADING", "baseAsset": "AAA", "quoteAsset": "USDT"},
+                    {"symbol": "BBBUSDT", "status": "HALT", "baseAsset": "BBB", "quoteAsset": "USDT"},
+                    {"symbol": "CCCUSDT", "status": "TRADING", "baseAsset": "CCC", "quoteAsset": "USDT"}
+                ]}"#,
+                None,
+            )
+            .unwrap();
+        let mut config = Config {
+            max_symbols: 8,
+            ..Config::default()
+        };
+        config.banned_symbols = vec!["CCCUSDT".to_string(), "ZZZUSDT".to_string()];
+        let risk = Risk::new(&config);
+
+        assert_eq!(
+            risk.apply_config_bans(&config, &registry),
+            vec!["ZZZUSDT".to_string()]
+        );
+        assert_eq!(risk.apply_symbol_status(&registry), 1);
+        let t0 = 1_700_000_000_000;
+        let banned: Vec<u32> = (0..3).filter(|&id| risk.bans.is_banned(id, t0)).collect();
+        assert_eq!(banned, vec![1, 2]);
+
+        // A win bans nothing; a loss bans the symbol for loss_ban_secs
+        assert_eq!(risk.record_trade(0, 100, t0), TradeRisk::default());
+        let outcome = risk.record_trade(0, -100, t0);
+        assert_eq!(outcome.banned_until_unix_ms, Some(t0 + 86_400_000));
+        assert_eq!(outcome.freeze, None);
+        assert!(risk.bans.is_banned(0, t0));
+
+        // Config bans follow the config; the others stay
+        config.banned_symbols.clear();
+        assert!(risk.apply_config_bans(&config, &registry).is_empty());
+        assert_eq!(risk.bans.count(t0), 2);
+        assert_eq!(risk.bans.expire(t0 + 86_400_000), vec![0]);
+        let banned: Vec<u32> = (0..3)
+            .filter(|&id| risk.bans.is_banned(id, t0 + 86_400_000))
+            .collect();
+        assert_eq!(banned, vec![1]);
+    }
+
+    #[test]
+    fn test_concurrent_reservations_grant_each_slot_once() {
+        const THREADS: usize = 8;
+        for _ in 0..100 {
+            let slots = Arc::new(slots());
+            let barrier = Arc::new(Barrier::new(THREADS));
+            let handles: Vec<_> = (0..THREADS as u32)
+                .map(|symbol_id| {
+                    let (slots, barrier) = (Arc::clone(&slots), Arc::clone(&barrier));
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        slots.reserve(symbol_id)
+                    })
+                })
+                .collect();
+            let mut granted: Vec<SlotReservation> = handles
+                .into_iter()
+                .filter_map(|handle| handle.join().unwrap())
+                .collect();
+            granted.sort_by_key(|r| r.slot);
+
+            assert_eq!(granted.len(), 2);
+            assert_eq!((granted[0].slot, granted[1].slot), (0, 1));
+            for reservation in &granted {
+                assert_eq!(slots.holder(reservation.slot), Some(reservation.symbol_id));
+            }
+            assert_eq!(slots.denied(), THREADS as u64 - 2);
+        }
+    }
+
+    #[test]
+    fn test_concurrent_reserve_release_never_shares_a_slot() {
+        const THREADS: u32 = 4;
+        const ROUNDS: usize = 20_000;
+        let slots = Arc::new(slots());
+        // Threads currently inside each slot
+        let occupants: Arc<[AtomicUsize; 2]> = Arc::new(Default::default());
+        let barrier = Arc::new(Barrier::new(THREADS as usize));
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|symbol_id| {
+                let (slots, occupants, barrier) = (
+                    Arc::clone(&slots),
+                    Arc::clone(&occupants),
+                    Arc::clone(&barrier),
+                );
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    let mut granted = 0;
+                    for _ in 0..ROUNDS {
+                        let Some(reservation) = slots.reserve(symbol_id) else {
+                            std::hint::spin_loop();
+                            continue;
+                        };
+                        granted += 1;
+                        let occupant = &occupants[reservation.slot];
+                        assert_eq!(occupant.fetch_add(1, Ordering::AcqRel), 0);
+                        assert_eq!(slots.holder(reservation.slot), Some(symbol_id));
+                        occupant.fetch_sub(1, Ordering::AcqRel);
+                        assert!(slots.release(&reservation));
+                    }
+                    granted
+                })
+            })
+            .collect();
+
+        let granted: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        assert!(granted > 0);
+        assert_eq!(
+            granted as u64 + slots.denied(),
+            THREADS as u64 * ROUNDS as u64
+        );
+        assert_eq!(slots.held(), 0);
+    }
+}
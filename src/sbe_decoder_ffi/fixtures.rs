@@ -0,0 +1,14 @@
+//! Golden-byte SBE frames shared by decoder tests (see tests/fixtures/README.md)
+
+/// One message, one trade for symbol 7
+pub const TRADE_SINGLE: &[u8] = include_bytes!("../../tests/fixtures/trade_single.bin");
+/// One message, three trades for symbol 42
+pub const TRADE_GROUP: &[u8] = include_bytes!("../../tests/fixtures/trade_group.bin");
+/// Three messages: single trade, empty group, extended block lengths
+pub const TRADE_STREAM: &[u8] = include_bytes!("../../tests/fixtures/trade_stream.bin");
+/// Valid layout with template id 10001
+pub const TRADE_UNKNOWN_TEMPLATE: &[u8] =
+    include_bytes!("../../tests/fixtures/trade_unknown_template.bin");
+/// Valid layout with schema version 1
+pub const TRADE_SCHEMA_MISMATCH: &[u8] =
+    include_bytes!("../../tests/fixtures/trade_schema_mismatch.bin");
@@ -1,4 +1,12 @@
+#[cfg(feature = "c-sbe")]
 use crate::data_feed::TradeTick;
+use std::fmt;
+#[cfg(feature = "c-sbe")]
+use std::ptr::NonNull;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod wire;
 
 /// RawTick structure with C-compatible layout
 #[repr(C)]
@@ -21,32 +29,174 @@ impl RawTick {
     }
 }
 
+/// Error codes reported by the C decoder (mirrors SBE_ERR_* in decoder.h)
+#[allow(dead_code)]
+pub mod error_code {
+    pub const NONE: i32 = 0;
+    pub const NULL_POINTER: i32 = 1;
+    pub const TRUNCATED: i32 = 2;
+    pub const UNKNOWN_TEMPLATE: i32 = 3;
+    pub const SCHEMA_MISMATCH: i32 = 4;
+    pub const BAD_PRICE: i32 = 5;
+}
+
+/// Decoder failure, distinct from running out of data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A required pointer was NULL at the FFI boundary (C decoder only)
+    #[cfg_attr(not(feature = "c-sbe"), allow(dead_code))]
+    NullPointer,
+    /// Block or entry length shorter than the schema requires
+    TruncatedFrame,
+    /// Template id is not the trades template
+    UnknownTemplate,
+    /// Schema id or version not supported
+    SchemaVersionMismatch,
+    /// Price exponent out of range or price not representable in e8
+    InvalidPrice,
+    /// Error code not known to this build (C decoder only)
+    #[cfg_attr(not(feature = "c-sbe"), allow(dead_code))]
+    Unknown(i32),
+}
+
+impl DecodeError {
+    /// Map a C error code (SBE_ERR_*) to a DecodeError
+    #[cfg(feature = "c-sbe")]
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            error_code::NULL_POINTER => DecodeError::NullPointer,
+            error_code::TRUNCATED => DecodeError::TruncatedFrame,
+            error_code::UNKNOWN_TEMPLATE => DecodeError::UnknownTemplate,
+            error_code::SCHEMA_MISMATCH => DecodeError::SchemaVersionMismatch,
+            error_code::BAD_PRICE => DecodeError::InvalidPrice,
+            other => DecodeError::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::NullPointer => write!(f, "null pointer passed to SBE decoder"),
+            DecodeError::TruncatedFrame => write!(f, "truncated SBE frame"),
+            DecodeError::UnknownTemplate => write!(f, "unknown SBE template id"),
+            DecodeError::SchemaVersionMismatch => write!(f, "SBE schema id/version mismatch"),
+            DecodeError::InvalidPrice => write!(f, "invalid price in SBE frame"),
+            DecodeError::Unknown(code) => write!(f, "unknown SBE decoder error code {}", code),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[cfg(feature = "c-sbe")]
+/// Opaque C decoder context (struct SbeDecoder in decoder.h)
+#[repr(C)]
+struct SbeDecoderCtx {
+    _private: [u8; 0],
+}
+
+#[cfg(feature = "c-sbe")]
 extern "C" {
+    /// Create a decoder context owning its own buffer and cursor
+    fn sbe_decoder_create(initial_capacity: usize) -> *mut SbeDecoderCtx;
+
+    /// Destroy a decoder context
+    fn sbe_decoder_destroy(decoder: *mut SbeDecoderCtx);
+
+    /// Append bytes to the context's buffer
+    /// Returns: 0 on success, -1 on error
+    fn sbe_decoder_feed(decoder: *mut SbeDecoderCtx, bytes: *const u8, len: usize) -> i32;
+
     /// FFI binding to C SBE decoder
-    /// Returns: 1 on success, 0 if no more data, -1 on error
-    fn sbe_decode_next(out: *mut RawTick) -> i32;
+    /// Returns: 1 on success, 0 if no more data (or partial frame), -1 on error
+    #[allow(dead_code)]
+    fn sbe_decoder_next(decoder: *mut SbeDecoderCtx, out: *mut RawTick) -> i32;
+
+    /// Decode up to max_ticks ticks; status receives the result that ended the batch
+    fn sbe_decoder_next_many(
+        decoder: *mut SbeDecoderCtx,
+        out: *mut RawTick,
+        max_ticks: usize,
+        status: *mut i32,
+    ) -> usize;
+
+    /// Error code of the last failed call on this context
+    fn sbe_decoder_last_error(decoder: *const SbeDecoderCtx) -> i32;
 }
 
+#[cfg(feature = "c-sbe")]
+/// Initial capacity of the decoder's byte buffer
+const INITIAL_BUFFER_CAPACITY: usize = 64 * 1024;
+
+#[cfg(feature = "c-sbe")]
+/// Maximum ticks decoded per FFI call in `decode_many`
+const DECODE_BATCH: usize = 256;
+
+#[cfg(feature = "c-sbe")]
 /// Safe Rust wrapper for SBE decoder
-pub struct SbeDecoderFfi;
+///
+/// Each instance owns an independent C decoder context, so one decoder
+/// per shard can run on its own thread without sharing any state.
+pub struct SbeDecoderFfi {
+    ctx: NonNull<SbeDecoderCtx>,
+    /// Pre-allocated scratch buffer for batch decoding
+    batch: Vec<RawTick>,
+    /// Error hit after a partial batch, reported by the next decode call
+    pending_error: Option<DecodeError>,
+}
+
+#[cfg(feature = "c-sbe")]
+// SAFETY: the context is exclusively owned by this wrapper and the C side
+// keeps no global state, so moving it to another thread is sound. All
+// mutating calls take &mut self, so it is never accessed concurrently.
+unsafe impl Send for SbeDecoderFfi {}
 
+#[cfg(feature = "c-sbe")]
 impl SbeDecoderFfi {
     /// Create a new SBE decoder
     pub fn new() -> Self {
-        Self
+        // SAFETY: plain constructor call, result checked for NULL below
+        let ctx = unsafe { sbe_decoder_create(INITIAL_BUFFER_CAPACITY) };
+        let ctx = NonNull::new(ctx).expect("Failed to allocate SBE decoder context");
+
+        Self {
+            ctx,
+            batch: vec![RawTick::new(0, 0, 0); DECODE_BATCH],
+            pending_error: None,
+        }
+    }
+
+    /// Append raw SBE bytes (may end in a partial frame)
+    pub fn feed(&mut self, bytes: &[u8]) {
+        // SAFETY: ctx is a live context owned by self, bytes is a valid slice
+        let result = unsafe { sbe_decoder_feed(self.ctx.as_ptr(), bytes.as_ptr(), bytes.len()) };
+        assert_eq!(result, 0, "Failed to grow SBE decoder buffer");
+    }
+
+    fn last_error(&self) -> DecodeError {
+        // SAFETY: ctx is a live context owned by self
+        DecodeError::from_code(unsafe { sbe_decoder_last_error(self.ctx.as_ptr()) })
     }
 
     /// Decode next tick into TradeTick
-    /// Returns true if a tick was decoded, false if no more data
-    pub fn decode_into(&mut self, tick: &mut TradeTick) -> bool {
+    /// Returns Ok(true) if a tick was decoded, Ok(false) if no more data
+    /// (or only a partial frame), Err on a malformed frame
+    #[allow(dead_code)]
+    pub fn decode_into(&mut self, tick: &mut TradeTick) -> Result<bool, DecodeError> {
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
         let mut raw_tick = RawTick {
             symbol_id: 0,
             px_e8: 0,
             ts_unix_ms: 0,
         };
 
-        // SAFETY: raw_tick is a valid pointer to RawTick with C-compatible layout
-        let result = unsafe { sbe_decode_next(&mut raw_tick as *mut RawTick) };
+        // SAFETY: ctx is a live context owned by self, raw_tick is a valid
+        // pointer to RawTick with C-compatible layout
+        let result = unsafe { sbe_decoder_next(self.ctx.as_ptr(), &mut raw_tick as *mut RawTick) };
 
         match result {
             1 => {
@@ -54,30 +204,95 @@ impl SbeDecoderFfi {
                 tick.symbol_id = raw_tick.symbol_id;
                 tick.px_e8 = raw_tick.px_e8;
                 tick.ts_unix_ms = raw_tick.ts_unix_ms;
-                true
+                Ok(true)
             }
             0 => {
                 // No more data
-                false
+                Ok(false)
             }
-            _ => {
-                // Error
-                false
+            _ => Err(self.last_error()),
+        }
+    }
+
+    /// Decode up to `ticks.len()` ticks, amortising the FFI call over a batch
+    /// Returns the number of ticks written; fewer than requested means the
+    /// decoder ran out of data. An error hit after some ticks were decoded
+    /// is returned by the following call so no decoded tick is lost.
+    pub fn decode_many(&mut self, ticks: &mut [TradeTick]) -> Result<usize, DecodeError> {
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
+        let mut decoded = 0;
+
+        while decoded < ticks.len() {
+            let want = (ticks.len() - decoded).min(self.batch.len());
+            let mut status = 0;
+
+            // SAFETY: ctx is a live context owned by self, batch holds at least
+            // `want` initialised RawTick structs
+            let n = unsafe {
+                sbe_decoder_next_many(
+                    self.ctx.as_ptr(),
+                    self.batch.as_mut_ptr(),
+                    want,
+                    &mut status as *mut i32,
+                )
+            };
+
+            for (tick, raw) in ticks[decoded..decoded + n].iter_mut().zip(&self.batch[..n]) {
+                tick.symbol_id = raw.symbol_id;
+                tick.px_e8 = raw.px_e8;
+                tick.ts_unix_ms = raw.ts_unix_ms;
+            }
+            decoded += n;
+
+            if status < 0 {
+                let error = self.last_error();
+                if decoded == 0 {
+                    return Err(error);
+                }
+                self.pending_error = Some(error);
+                break;
+            }
+            if status == 0 {
+                break;
             }
         }
+
+        Ok(decoded)
     }
 }
 
+#[cfg(feature = "c-sbe")]
+impl Drop for SbeDecoderFfi {
+    fn drop(&mut self) {
+        // SAFETY: ctx was created by sbe_decoder_create and is destroyed exactly once
+        unsafe { sbe_decoder_destroy(self.ctx.as_ptr()) };
+    }
+}
+
+#[cfg(feature = "c-sbe")]
 impl Default for SbeDecoderFfi {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "c-sbe"))]
 mod tests {
+    use super::fixtures::*;
     use super::*;
 
+    fn decode_all(decoder: &mut SbeDecoderFfi) -> Vec<(u32, u64, u64)> {
+        let mut out = Vec::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+        while decoder.decode_into(&mut tick).unwrap() {
+            out.push((tick.symbol_id, tick.px_e8, tick.ts_unix_ms));
+        }
+        out
+    }
+
     #[test]
     fn test_raw_tick_layout() {
         // Verify RawTick has C-compatible layout
@@ -87,31 +302,308 @@ mod tests {
     }
 
     #[test]
-    fn test_sbe_decoder_decode() {
+    fn test_decode_error_from_code() {
+        assert_eq!(
+            DecodeError::from_code(error_code::NULL_POINTER),
+            DecodeError::NullPointer
+        );
+        assert_eq!(
+            DecodeError::from_code(error_code::BAD_PRICE),
+            DecodeError::InvalidPrice
+        );
+        assert_eq!(DecodeError::from_code(42), DecodeError::Unknown(42));
+    }
+
+    #[test]
+    fn test_sbe_decoder_empty() {
+        let mut decoder = SbeDecoderFfi::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+
+        assert_eq!(decoder.decode_into(&mut tick), Ok(false));
+    }
+
+    #[test]
+    fn test_sbe_decoder_single_trade() {
+        let mut decoder = SbeDecoderFfi::new();
+        decoder.feed(TRADE_SINGLE);
+
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![(7, 2_512_345_000_000, 1_700_000_000_123)]
+        );
+    }
+
+    #[test]
+    fn test_sbe_decoder_trade_group() {
+        let mut decoder = SbeDecoderFfi::new();
+        decoder.feed(TRADE_GROUP);
+
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![
+                (42, 15_000_000_000, 1_700_000_001_000),
+                (42, 15_001_000_000, 1_700_000_001_000),
+                (42, 14_999_000_000, 1_700_000_001_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sbe_decoder_stream_skips_empty_and_extended_blocks() {
+        let mut decoder = SbeDecoderFfi::new();
+        decoder.feed(TRADE_STREAM);
+
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![
+                (7, 2_512_345_000_000, 1_700_000_000_123),
+                (3, 6_400_000_000_000, 1_700_000_002_500),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sbe_decoder_partial_frames() {
+        let mut decoder = SbeDecoderFfi::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+
+        // Feed one byte at a time; nothing decodes until the frame is complete
+        let (head, last) = TRADE_GROUP.split_at(TRADE_GROUP.len() - 1);
+        for byte in head.chunks(1) {
+            decoder.feed(byte);
+            assert_eq!(decoder.decode_into(&mut tick), Ok(false));
+        }
+
+        decoder.feed(last);
+        assert_eq!(decode_all(&mut decoder).len(), 3);
+    }
+
+    #[test]
+    fn test_sbe_decoder_frames_across_feeds() {
+        let mut decoder = SbeDecoderFfi::new();
+        let (first, second) = TRADE_STREAM.split_at(40);
+
+        decoder.feed(first);
+        assert!(decode_all(&mut decoder).is_empty());
+
+        decoder.feed(second);
+        assert_eq!(decode_all(&mut decoder).len(), 2);
+
+        // Buffer is compacted on the next feed and decoding carries on
+        decoder.feed(TRADE_SINGLE);
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![(7, 2_512_345_000_000, 1_700_000_000_123)]
+        );
+    }
+
+    #[test]
+    fn test_sbe_decoder_unknown_template() {
+        let mut decoder = SbeDecoderFfi::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+        decoder.feed(TRADE_UNKNOWN_TEMPLATE);
+
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::UnknownTemplate)
+        );
+    }
+
+    #[test]
+    fn test_sbe_decoder_schema_mismatch() {
+        let mut decoder = SbeDecoderFfi::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+        decoder.feed(TRADE_SCHEMA_MISMATCH);
+
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::SchemaVersionMismatch)
+        );
+    }
+
+    #[test]
+    fn test_sbe_decoder_truncated_block() {
+        let mut decoder = SbeDecoderFfi::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+        let mut frame = TRADE_SINGLE.to_vec();
+        frame[0] = 16; // block_length below the 24-byte root block
+        decoder.feed(&frame);
+
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::TruncatedFrame)
+        );
+    }
+
+    #[test]
+    fn test_sbe_decoder_recovers_after_error() {
+        let mut decoder = SbeDecoderFfi::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+        decoder.feed(TRADE_UNKNOWN_TEMPLATE);
+        assert!(decoder.decode_into(&mut tick).is_err());
+
+        // The bad frame is discarded and the next one decodes normally
+        decoder.feed(TRADE_SINGLE);
+        assert_eq!(decoder.decode_into(&mut tick), Ok(true));
+        assert_eq!(tick.symbol_id, 7);
+    }
+
+    #[test]
+    fn test_sbe_decoder_keeps_frames_behind_a_bad_one() {
         let mut decoder = SbeDecoderFfi::new();
         let mut tick = TradeTick::new(0, 0, 0);
+        let mut bad_exponent = TRADE_SINGLE.to_vec();
+        bad_exponent[24] = 0xf0; // price exponent -16
+        decoder.feed(TRADE_UNKNOWN_TEMPLATE);
+        decoder.feed(&bad_exponent);
+        decoder.feed(TRADE_GROUP);
 
-        // Should be able to decode at least one tick (stub returns synthetic data)
-        let success = decoder.decode_into(&mut tick);
-        assert!(success);
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::UnknownTemplate)
+        );
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::InvalidPrice)
+        );
+        assert_eq!(decode_all(&mut decoder).len(), 3);
 
-        // Check that tick has reasonable values
-        assert!(tick.px_e8 > 0);
-        assert!(tick.ts_unix_ms > 0);
+        // A header that cannot be sized still drops the rest of the buffer
+        decoder.feed(TRADE_SCHEMA_MISMATCH);
+        decoder.feed(TRADE_SINGLE);
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::SchemaVersionMismatch)
+        );
+        assert_eq!(decoder.decode_into(&mut tick), Ok(false));
     }
 
     #[test]
-    fn test_sbe_decoder_multiple_decodes() {
+    fn test_sbe_decoder_decode_many() {
         let mut decoder = SbeDecoderFfi::new();
+        decoder.feed(TRADE_STREAM);
+        decoder.feed(TRADE_GROUP);
 
-        // Decode multiple ticks and verify they're different
-        let mut tick1 = TradeTick::new(0, 0, 0);
-        let mut tick2 = TradeTick::new(0, 0, 0);
+        let mut ticks = [TradeTick::new(0, 0, 0); 8];
+        assert_eq!(decoder.decode_many(&mut ticks[..3]), Ok(3));
+        assert_eq!(ticks[0].symbol_id, 7);
+        assert_eq!(ticks[1].symbol_id, 3);
+        assert_eq!(ticks[2].symbol_id, 42);
 
-        assert!(decoder.decode_into(&mut tick1));
-        assert!(decoder.decode_into(&mut tick2));
+        // Short batch once the buffer runs dry
+        assert_eq!(decoder.decode_many(&mut ticks), Ok(2));
+        assert_eq!(ticks[1].px_e8, 14_999_000_000);
+        assert_eq!(decoder.decode_many(&mut ticks), Ok(0));
+    }
+
+    #[test]
+    fn test_sbe_decoder_decode_many_larger_than_batch() {
+        let mut decoder = SbeDecoderFfi::new();
+        let frames = DECODE_BATCH / 3 + 10;
+        for _ in 0..frames {
+            decoder.feed(TRADE_GROUP);
+        }
 
-        // Ticks should be different (timestamps at minimum)
-        assert_ne!(tick1.ts_unix_ms, tick2.ts_unix_ms);
+        let mut ticks = vec![TradeTick::new(0, 0, 0); frames * 3 + 5];
+        assert_eq!(decoder.decode_many(&mut ticks), Ok(frames * 3));
+    }
+
+    #[test]
+    fn test_sbe_decoder_decode_many_stops_on_error() {
+        let mut decoder = SbeDecoderFfi::new();
+        decoder.feed(TRADE_SINGLE);
+        decoder.feed(TRADE_UNKNOWN_TEMPLATE);
+
+        let mut ticks = [TradeTick::new(0, 0, 0); 4];
+        // The decoded tick is returned first, the error on the next call
+        assert_eq!(decoder.decode_many(&mut ticks), Ok(1));
+        assert_eq!(ticks[0].symbol_id, 7);
+        assert_eq!(
+            decoder.decode_many(&mut ticks),
+            Err(DecodeError::UnknownTemplate)
+        );
+        assert_eq!(decoder.decode_many(&mut ticks), Ok(0));
+    }
+
+    #[test]
+    fn test_sbe_decoders_are_independent_across_threads() {
+        let handles: Vec<_> = (0..4)
+            .map(|shard| {
+                let mut decoder = SbeDecoderFfi::new();
+                std::thread::spawn(move || {
+                    let mut ticks = [TradeTick::new(0, 0, 0); 3];
+                    let mut total = 0;
+                    for _ in 0..1000 {
+                        // Each shard interleaves feeds and decodes on its own context
+                        decoder.feed(if shard % 2 == 0 {
+                            TRADE_GROUP
+                        } else {
+                            TRADE_SINGLE
+                        });
+                        total += decoder.decode_many(&mut ticks).unwrap();
+                    }
+                    total
+                })
+            })
+            .collect();
+
+        let totals: Vec<usize> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(totals, vec![3000, 1000, 3000, 1000]);
+    }
+
+    #[test]
+    fn test_encode_trade_roundtrip() {
+        let mut decoder = SbeDecoderFfi::new();
+        let mut frames = Vec::new();
+        wire::encode_trade(&mut frames, 299, 123_456_789, 1_700_000_000_001);
+        wire::encode_trade(&mut frames, 0, 1, 1_700_000_000_002);
+        decoder.feed(&frames);
+
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![
+                (299, 123_456_789, 1_700_000_000_001),
+                (0, 1, 1_700_000_000_002)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_peek_frame() {
+        use wire::{peek_frame, FramePeek};
+
+        // Walk the stream frame by frame, including the extended-block message
+        let mut rest = TRADE_STREAM;
+        let mut symbols = Vec::new();
+        while let FramePeek::Frame { len, symbol_id } = peek_frame(rest) {
+            symbols.push(symbol_id);
+            rest = &rest[len..];
+        }
+        assert!(rest.is_empty());
+        assert_eq!(symbols.len(), 3);
+
+        let FramePeek::Frame { len, symbol_id } = peek_frame(TRADE_GROUP) else {
+            panic!("group frame not recognised");
+        };
+        assert_eq!((len, symbol_id), (TRADE_GROUP.len(), 42));
+        assert_eq!(
+            peek_frame(&TRADE_GROUP[..TRADE_GROUP.len() - 1]),
+            FramePeek::Incomplete
+        );
+        assert_eq!(peek_frame(&TRADE_GROUP[..4]), FramePeek::Incomplete);
+        assert_eq!(
+            peek_frame(TRADE_UNKNOWN_TEMPLATE),
+            FramePeek::Invalid {
+                len: Some(TRADE_UNKNOWN_TEMPLATE.len())
+            }
+        );
+        assert_eq!(
+            peek_frame(&TRADE_UNKNOWN_TEMPLATE[..20]),
+            FramePeek::Incomplete
+        );
+        assert_eq!(
+            peek_frame(TRADE_SCHEMA_MISMATCH),
+            FramePeek::Invalid { len: None }
+        );
     }
 }
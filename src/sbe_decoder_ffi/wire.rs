@@ -0,0 +1,99 @@
+//! SBE trade stream wire format shared by both decoders (mirrors decoder.h)
+
+pub const SCHEMA_ID: u16 = 1;
+pub const SCHEMA_VERSION: u16 = 0;
+pub const TEMPLATE_TRADES: u16 = 10000;
+
+pub const MESSAGE_HEADER_LENGTH: usize = 8;
+pub const TRADES_BLOCK_LENGTH: usize = 24;
+pub const GROUP_HEADER_LENGTH: usize = 6;
+pub const TRADE_ENTRY_LENGTH: usize = 25;
+
+/// Offset of symbol_id within the trades root block
+const SYMBOL_ID_OFFSET: usize = 20;
+
+/// Framing of the first message in a buffer, without decoding its trades
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramePeek {
+    /// Complete message of `len` bytes carrying trades for `symbol_id`
+    Frame { len: usize, symbol_id: u32 },
+    /// More bytes are needed
+    Incomplete,
+    /// Not a trades message of this schema; the decoder will reject it.
+    /// `len` is the message length, or None if its header cannot be sized
+    /// (the decoder then drops the rest of its buffer)
+    Invalid { len: Option<usize> },
+}
+
+/// Find the length and symbol of the first message in `buf` (for routing)
+pub fn peek_frame(buf: &[u8]) -> FramePeek {
+    if buf.len() < MESSAGE_HEADER_LENGTH {
+        return FramePeek::Incomplete;
+    }
+    let read_u16 = |at: usize| u16::from_le_bytes([buf[at], buf[at + 1]]);
+
+    // Sized by the same rules as the decoders' error recovery
+    let block_length = read_u16(0) as usize;
+    let is_trades = read_u16(2) == TEMPLATE_TRADES;
+    if read_u16(4) != SCHEMA_ID
+        || read_u16(6) > SCHEMA_VERSION
+        || (is_trades && block_length < TRADES_BLOCK_LENGTH)
+    {
+        return FramePeek::Invalid { len: None };
+    }
+
+    let group_header = MESSAGE_HEADER_LENGTH + block_length;
+    if buf.len() < group_header + GROUP_HEADER_LENGTH {
+        return FramePeek::Incomplete;
+    }
+    let entry_length = read_u16(group_header) as usize;
+    let mut num_in_group = [0u8; 4];
+    num_in_group.copy_from_slice(&buf[group_header + 2..group_header + 6]);
+    let len = group_header
+        + GROUP_HEADER_LENGTH
+        + entry_length * u32::from_le_bytes(num_in_group) as usize;
+    if buf.len() < len {
+        return FramePeek::Incomplete;
+    }
+    if !is_trades || entry_length < TRADE_ENTRY_LENGTH {
+        return FramePeek::Invalid { len: Some(len) };
+    }
+
+    let at = MESSAGE_HEADER_LENGTH + SYMBOL_ID_OFFSET;
+    let mut symbol_id = [0u8; 4];
+    symbol_id.copy_from_slice(&buf[at..at + 4]);
+    FramePeek::Frame {
+        len,
+        symbol_id: u32::from_le_bytes(symbol_id),
+    }
+}
+
+/// Price exponent used by `encode_trade`, so mantissas are e8 prices as-is
+const ENCODE_PRICE_EXPONENT: i8 = -8;
+
+/// Append a single-trade message for `symbol_id` to `out` (benchmarks and tests)
+pub fn encode_trade(out: &mut Vec<u8>, symbol_id: u32, px_e8: u64, ts_unix_ms: u64) {
+    let transact_time_us = ts_unix_ms as i64 * 1000;
+
+    // Message header
+    out.extend_from_slice(&(TRADES_BLOCK_LENGTH as u16).to_le_bytes());
+    out.extend_from_slice(&TEMPLATE_TRADES.to_le_bytes());
+    out.extend_from_slice(&SCHEMA_ID.to_le_bytes());
+    out.extend_from_slice(&SCHEMA_VERSION.to_le_bytes());
+
+    // Root block
+    out.extend_from_slice(&transact_time_us.to_le_bytes());
+    out.extend_from_slice(&transact_time_us.to_le_bytes());
+    out.push(ENCODE_PRICE_EXPONENT as u8);
+    out.push(ENCODE_PRICE_EXPONENT as u8);
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&symbol_id.to_le_bytes());
+
+    // Trades group with one entry
+    out.extend_from_slice(&(TRADE_ENTRY_LENGTH as u16).to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+    out.extend_from_slice(&0i64.to_le_bytes());
+    out.extend_from_slice(&(px_e8 as i64).to_le_bytes());
+    out.extend_from_slice(&0i64.to_le_bytes());
+    out.push(0);
+}
@@ -0,0 +1,417 @@
+//! Pure-Rust SBE decoder with the same contract as `SbeDecoderFfi`
+//!
+//! Decodes directly out of its byte buffer (no intermediate copies) and
+//! contains no `unsafe`. Enabled with the `rust-sbe` cargo feature.
+#![forbid(unsafe_code)]
+
+use crate::data_feed::TradeTick;
+use crate::sbe_decoder_ffi::wire::*;
+use crate::sbe_decoder_ffi::DecodeError;
+
+/// Initial capacity of the decoder's byte buffer
+const INITIAL_BUFFER_CAPACITY: usize = 64 * 1024;
+
+/// Powers of ten for price exponents in [-8, 8] relative to e8
+const POW10: [u64; 17] = {
+    let mut table = [1u64; 17];
+    let mut i = 1;
+    while i < 17 {
+        table[i] = table[i - 1] * 10;
+        i += 1;
+    }
+    table
+};
+
+/// Result of a single decode step (mirrors the C 1 / 0 / -1 convention)
+enum Step {
+    Tick,
+    NoData,
+    Error(DecodeError),
+}
+
+fn read_u16(buf: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([buf[at], buf[at + 1]])
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&buf[at..at + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_i64(buf: &[u8], at: usize) -> i64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[at..at + 8]);
+    i64::from_le_bytes(bytes)
+}
+
+/// Pure-Rust SBE decoder
+pub struct SbeDecoderRust {
+    buf: Vec<u8>,
+    /// Offset of the next unread byte
+    offset: usize,
+    /// Offset just past the current message
+    group_end: usize,
+    /// Transact time of the current message
+    ts_unix_ms: u64,
+    /// Trades left in the current group
+    remaining: u32,
+    /// Symbol of the current message
+    symbol_id: u32,
+    /// Block length of each group entry
+    entry_length: usize,
+    /// Price exponent of the current message
+    price_exponent: i8,
+    /// Error hit after a partial batch, reported by the next decode call
+    pending_error: Option<DecodeError>,
+}
+
+impl SbeDecoderRust {
+    /// Create a new SBE decoder
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::with_capacity(INITIAL_BUFFER_CAPACITY),
+            offset: 0,
+            group_end: 0,
+            ts_unix_ms: 0,
+            remaining: 0,
+            symbol_id: 0,
+            entry_length: 0,
+            price_exponent: 0,
+            pending_error: None,
+        }
+    }
+
+    /// Append raw SBE bytes (may end in a partial frame)
+    pub fn feed(&mut self, bytes: &[u8]) {
+        // Drop bytes the cursor has already moved past before growing the buffer
+        let consumed = self.offset.min(self.buf.len());
+        if consumed > 0 {
+            self.buf.drain(..consumed);
+            self.offset = 0;
+            self.group_end = self.group_end.saturating_sub(consumed);
+        }
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Decode next tick into TradeTick
+    /// Returns Ok(true) if a tick was decoded, Ok(false) if no more data
+    /// (or only a partial frame), Err on a malformed frame
+    #[allow(dead_code)]
+    pub fn decode_into(&mut self, tick: &mut TradeTick) -> Result<bool, DecodeError> {
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
+        match self.step(tick) {
+            Step::Tick => Ok(true),
+            Step::NoData => Ok(false),
+            Step::Error(error) => Err(error),
+        }
+    }
+
+    /// Decode up to `ticks.len()` ticks
+    /// Returns the number of ticks written; fewer than requested means the
+    /// decoder ran out of data. An error hit after some ticks were decoded
+    /// is returned by the following call so no decoded tick is lost.
+    pub fn decode_many(&mut self, ticks: &mut [TradeTick]) -> Result<usize, DecodeError> {
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
+        let mut decoded = 0;
+        for tick in ticks.iter_mut() {
+            match self.step(tick) {
+                Step::Tick => decoded += 1,
+                Step::NoData => break,
+                Step::Error(error) if decoded == 0 => return Err(error),
+                Step::Error(error) => {
+                    self.pending_error = Some(error);
+                    break;
+                }
+            }
+        }
+        Ok(decoded)
+    }
+
+    /// Report `error` and resume at `resume_at`: the end of the bad message
+    /// when it can be sized, otherwise the end of the buffer
+    fn fail(&mut self, resume_at: usize, error: DecodeError) -> Step {
+        self.offset = resume_at;
+        self.remaining = 0;
+        Step::Error(error)
+    }
+
+    /// Enter the message at `offset` once all of its bytes are buffered
+    fn enter_message(&mut self) -> Step {
+        let buf = &self.buf;
+        let start = self.offset;
+        if buf.len() - start < MESSAGE_HEADER_LENGTH {
+            return Step::NoData;
+        }
+
+        let block_length = read_u16(buf, start) as usize;
+        let template_id = read_u16(buf, start + 2);
+        let schema_id = read_u16(buf, start + 4);
+        let version = read_u16(buf, start + 6);
+
+        // Neither a foreign schema nor a root block too short to hold the
+        // group header can be sized, so the rest of the buffer is dropped
+        if schema_id != SCHEMA_ID || version > SCHEMA_VERSION {
+            return self.fail(buf.len(), DecodeError::SchemaVersionMismatch);
+        }
+        let is_trades = template_id == TEMPLATE_TRADES;
+        if is_trades && block_length < TRADES_BLOCK_LENGTH {
+            return self.fail(buf.len(), DecodeError::TruncatedFrame);
+        }
+
+        let group_header = start + MESSAGE_HEADER_LENGTH + block_length;
+        if buf.len() < group_header + GROUP_HEADER_LENGTH {
+            return Step::NoData;
+        }
+
+        let entry_length = read_u16(buf, group_header) as usize;
+        let num_in_group = read_u32(buf, group_header + 2);
+        let entries = group_header + GROUP_HEADER_LENGTH;
+        let end = entries + entry_length * num_in_group as usize;
+        if buf.len() < end {
+            return Step::NoData;
+        }
+
+        // Every message of the schema shares this layout, so from here on a
+        // bad message is skipped and the frames behind it still decode
+        if !is_trades {
+            return self.fail(end, DecodeError::UnknownTemplate);
+        }
+        if entry_length < TRADE_ENTRY_LENGTH {
+            return self.fail(end, DecodeError::TruncatedFrame);
+        }
+
+        let block = start + MESSAGE_HEADER_LENGTH;
+        let transact_time_us = read_i64(buf, block + 8);
+        let price_exponent = buf[block + 16] as i8;
+        if !(-8..=8).contains(&price_exponent) {
+            return self.fail(end, DecodeError::InvalidPrice);
+        }
+
+        self.symbol_id = read_u32(buf, block + 20);
+        self.offset = entries;
+        self.group_end = end;
+        self.ts_unix_ms = if transact_time_us > 0 {
+            transact_time_us as u64 / 1000
+        } else {
+            0
+        };
+        self.remaining = num_in_group;
+        self.entry_length = entry_length;
+        self.price_exponent = price_exponent;
+        Step::Tick
+    }
+
+    fn step(&mut self, tick: &mut TradeTick) -> Step {
+        if self.offset > self.buf.len() {
+            return Step::NoData;
+        }
+
+        // Skip over messages whose group has been fully consumed (or is empty)
+        while self.remaining == 0 {
+            match self.enter_message() {
+                Step::Tick => {}
+                other => return other,
+            }
+            if self.remaining == 0 {
+                self.offset = self.group_end;
+            }
+        }
+
+        let price_mantissa = read_i64(&self.buf, self.offset + 8);
+        if price_mantissa <= 0 {
+            return self.fail(self.group_end, DecodeError::InvalidPrice);
+        }
+
+        let scale = POW10[(self.price_exponent + 8) as usize];
+        let Some(px_e8) = (price_mantissa as u64).checked_mul(scale) else {
+            return self.fail(self.group_end, DecodeError::InvalidPrice);
+        };
+
+        tick.symbol_id = self.symbol_id;
+        tick.px_e8 = px_e8;
+        tick.ts_unix_ms = self.ts_unix_ms;
+
+        self.offset += self.entry_length;
+        self.remaining -= 1;
+        Step::Tick
+    }
+}
+
+impl Default for SbeDecoderRust {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sbe_decoder_ffi::fixtures::*;
+
+    fn decode_all(decoder: &mut SbeDecoderRust) -> Vec<(u32, u64, u64)> {
+        let mut out = Vec::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+        while decoder.decode_into(&mut tick).unwrap() {
+            out.push((tick.symbol_id, tick.px_e8, tick.ts_unix_ms));
+        }
+        out
+    }
+
+    #[test]
+    fn test_rust_decoder_fixtures() {
+        let mut decoder = SbeDecoderRust::new();
+        decoder.feed(TRADE_STREAM);
+        decoder.feed(TRADE_GROUP);
+
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![
+                (7, 2_512_345_000_000, 1_700_000_000_123),
+                (3, 6_400_000_000_000, 1_700_000_002_500),
+                (42, 15_000_000_000, 1_700_000_001_000),
+                (42, 15_001_000_000, 1_700_000_001_000),
+                (42, 14_999_000_000, 1_700_000_001_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rust_decoder_partial_frames() {
+        let mut decoder = SbeDecoderRust::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+
+        let (head, last) = TRADE_SINGLE.split_at(TRADE_SINGLE.len() - 1);
+        decoder.feed(head);
+        assert_eq!(decoder.decode_into(&mut tick), Ok(false));
+
+        decoder.feed(last);
+        assert_eq!(decoder.decode_into(&mut tick), Ok(true));
+        assert_eq!(tick.symbol_id, 7);
+    }
+
+    #[test]
+    fn test_rust_decoder_errors() {
+        let mut decoder = SbeDecoderRust::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+
+        decoder.feed(TRADE_UNKNOWN_TEMPLATE);
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::UnknownTemplate)
+        );
+
+        decoder.feed(TRADE_SCHEMA_MISMATCH);
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::SchemaVersionMismatch)
+        );
+
+        // Recovers on the next clean frame
+        decoder.feed(TRADE_SINGLE);
+        assert_eq!(decoder.decode_into(&mut tick), Ok(true));
+    }
+
+    #[test]
+    fn test_rust_decoder_skips_bad_message_by_length() {
+        let mut decoder = SbeDecoderRust::new();
+        let mut tick = TradeTick::new(0, 0, 0);
+        let mut bad_entry = TRADE_GROUP.to_vec();
+        bad_entry[TRADE_GROUP.len() - 10 - 25] = 0x80; // second trade's price negative
+
+        decoder.feed(TRADE_UNKNOWN_TEMPLATE);
+        decoder.feed(&bad_entry);
+        decoder.feed(TRADE_SINGLE);
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::UnknownTemplate)
+        );
+        assert_eq!(decoder.decode_into(&mut tick), Ok(true));
+        assert_eq!(tick.px_e8, 15_000_000_000);
+        // The rest of the bad message is dropped, the frame behind it kept
+        assert_eq!(
+            decoder.decode_into(&mut tick),
+            Err(DecodeError::InvalidPrice)
+        );
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![(7, 2_512_345_000_000, 1_700_000_000_123)]
+        );
+    }
+
+    /// Feed identical byte streams (in identical chunks) to both decoders and
+    /// compare every decoded tick and error code
+    #[cfg(feature = "c-sbe")]
+    #[test]
+    fn test_differential_against_c_decoder() {
+        use crate::sbe_decoder_ffi::{wire, SbeDecoderFfi};
+
+        let mut bad_price = TRADE_SINGLE.to_vec();
+        bad_price[TRADE_SINGLE.len() - 10] = 0x80; // price mantissa high byte -> negative
+        let mut truncated = TRADE_GROUP.to_vec();
+        truncated[0] = 8;
+
+        let mut encoded = Vec::new();
+        for i in 0..50u64 {
+            wire::encode_trade(
+                &mut encoded,
+                (i % 7) as u32,
+                1_000 + i * 997,
+                1_700_000_000_000 + i,
+            );
+        }
+
+        let queued = [
+            TRADE_SINGLE,
+            TRADE_UNKNOWN_TEMPLATE,
+            bad_price.as_slice(),
+            TRADE_GROUP,
+        ]
+        .concat();
+
+        let streams: Vec<Vec<u8>> = vec![
+            queued,
+            TRADE_SINGLE.to_vec(),
+            TRADE_GROUP.to_vec(),
+            TRADE_STREAM.to_vec(),
+            TRADE_UNKNOWN_TEMPLATE.to_vec(),
+            TRADE_SCHEMA_MISMATCH.to_vec(),
+            bad_price,
+            truncated,
+            encoded,
+        ];
+
+        for chunk_size in [1, 7, 64, 4096] {
+            let mut rust = SbeDecoderRust::new();
+            let mut c = SbeDecoderFfi::new();
+
+            for stream in &streams {
+                for chunk in stream.chunks(chunk_size) {
+                    rust.feed(chunk);
+                    c.feed(chunk);
+
+                    let mut rust_tick = TradeTick::new(0, 0, 0);
+                    let mut c_tick = TradeTick::new(0, 0, 0);
+                    loop {
+                        let rust_result = rust.decode_into(&mut rust_tick);
+                        let c_result = c.decode_into(&mut c_tick);
+                        assert_eq!(rust_result, c_result, "chunk_size={}", chunk_size);
+                        if rust_result != Ok(true) {
+                            break;
+                        }
+                        assert_eq!(
+                            (rust_tick.symbol_id, rust_tick.px_e8, rust_tick.ts_unix_ms),
+                            (c_tick.symbol_id, c_tick.px_e8, c_tick.ts_unix_ms)
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
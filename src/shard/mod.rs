@@ -0,0 +1,440 @@
+//! Sharded hot path: a router splits the SBE stream by symbol_id into one
+//! SPSC channel per shard; each shard thread owns its decoder, `HotPath`
+//! slice and worker state, so shards share nothing on the tick path
+
+use crate::config::ThreadRole;
+use crate::cpu::{self, PlacementReport};
+use crate::data_feed::{DataFeed, DecodeErrorPolicy, TradeTick};
+use crate::metrics::{monotonic_ns, DecodeErrorCounters};
+use crate::sbe_decoder_ffi::wire::{peek_frame, FramePeek};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::ops::Range;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Ticks decoded between drains of a shard's tick channel
+const TICKS_PER_DRAIN: usize = 256;
+
+/// Contiguous partition of symbol ids across shards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardPlan {
+    num_symbols: u32,
+    symbols_per_shard: u32,
+}
+
+impl ShardPlan {
+    /// Split `0..num_symbols` into shards of `symbols_per_shard` ids
+    pub fn new(num_symbols: u32, symbols_per_shard: usize) -> Result<Self, String> {
+        if symbols_per_shard == 0 || num_symbols == 0 {
+            return Err("symbols per shard and symbol count must be > 0".to_string());
+        }
+        Ok(Self {
+            num_symbols,
+            symbols_per_shard: symbols_per_shard.min(num_symbols as usize) as u32,
+        })
+    }
+
+    /// Number of shards
+    pub fn num_shards(&self) -> usize {
+        self.num_symbols.div_ceil(self.symbols_per_shard) as usize
+    }
+
+    /// Shard owning `symbol_id` (None past the last symbol)
+    pub fn shard_of(&self, symbol_id: u32) -> Option<usize> {
+        (symbol_id < self.num_symbols).then(|| (symbol_id / self.symbols_per_shard) as usize)
+    }
+
+    /// Symbol ids owned by `shard`
+    pub fn range(&self, shard: usize) -> Range<u32> {
+        let start = (shard as u32 * self.symbols_per_shard).min(self.num_symbols);
+        start..(start + self.symbols_per_shard).min(self.num_symbols)
+    }
+}
+
+/// Frames routed to one shard, with the time the router received them
+#[derive(Debug)]
+pub struct RoutedBatch {
+    pub recv_ns: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// Per-tick work done on a shard thread
+pub trait ShardWorker: Send + 'static {
+    /// Handle one decoded tick for a symbol this shard owns
+    fn on_tick(&mut self, tick: &TradeTick);
+}
+
+/// Router counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RouterStats {
+    /// Complete frames routed
+    pub frames: u64,
+    /// Batches sent to shards
+    pub batches: u64,
+    /// Frames for symbols outside the plan (dropped)
+    pub unrouted: u64,
+    /// Unparseable stretches handed to shard 0's decoder
+    pub invalid: u64,
+}
+
+/// Splits SBE bytes into frames and forwards each shard's frames as one
+/// batch per `route` call; batch buffers are recycled by the shards
+pub struct FrameRouter {
+    plan: ShardPlan,
+    /// Partial frame carried over to the next `route` call
+    pending: Vec<u8>,
+    /// Frames collected for each shard during the current call
+    batches: Vec<Vec<u8>>,
+    senders: Vec<Sender<RoutedBatch>>,
+    /// Emptied batch buffers returned by the shards
+    free: Receiver<Vec<u8>>,
+    stats: RouterStats,
+}
+
+impl FrameRouter {
+    /// Route `bytes` (may start or end mid-frame) to the owning shards
+    pub fn route(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let recv_ns = monotonic_ns();
+
+        let mut pending = std::mem::take(&mut self.pending);
+        if pending.is_empty() {
+            let consumed = self.split_frames(bytes);
+            pending.extend_from_slice(&bytes[consumed..]);
+        } else {
+            pending.extend_from_slice(bytes);
+            let consumed = self.split_frames(&pending);
+            pending.drain(..consumed);
+        }
+        self.pending = pending;
+
+        self.flush(recv_ns)
+    }
+
+    /// Router counters so far
+    pub fn stats(&self) -> RouterStats {
+        self.stats
+    }
+
+    /// Append the complete frames at the start of `input` to their shard's
+    /// batch; returns the number of bytes consumed
+    fn split_frames(&mut self, input: &[u8]) -> usize {
+        let mut offset = 0;
+        loop {
+            match peek_frame(&input[offset..]) {
+                FramePeek::Frame { len, symbol_id } => {
+                    match self.plan.shard_of(symbol_id) {
+                        Some(shard) => {
+                            self.batches[shard].extend_from_slice(&input[offset..offset + len])
+                        }
+                        None => self.stats.unrouted += 1,
+                    }
+                    self.stats.frames += 1;
+                    offset += len;
+                }
+                FramePeek::Incomplete => return offset,
+                // Shard 0's decoder reports and drops it, as an unsharded feed would
+                FramePeek::Invalid { len: Some(len) } => {
+                    self.batches[0].extend_from_slice(&input[offset..offset + len]);
+                    self.stats.invalid += 1;
+                    offset += len;
+                }
+                FramePeek::Invalid { len: None } => {
+                    // Cannot be sized: the decoder drops everything after it too
+                    self.batches[0].extend_from_slice(&input[offset..]);
+                    self.stats.invalid += 1;
+                    return input.len();
+                }
+            }
+        }
+    }
+
+    /// Send every non-empty batch to its shard
+    fn flush(&mut self, recv_ns: u64) -> Result<(), String> {
+        for (shard, batch) in self.batches.iter_mut().enumerate() {
+            if batch.is_empty() {
+                continue;
+            }
+            let spare = self.free.try_recv().unwrap_or_default();
+            let bytes = std::mem::replace(batch, spare);
+            self.senders[shard]
+                .send(RoutedBatch { recv_ns, bytes })
+                .map_err(|_| format!("shard {} stopped", shard))?;
+            self.stats.batches += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Shard thread settings
+#[derive(Debug, Clone)]
+pub struct ShardOptions {
+    /// Batches buffered per shard channel
+    pub channel_capacity: usize,
+    /// Shard i is pinned to `placement.cores[i % len]` (empty = no pinning)
+    pub placement: ThreadRole,
+    /// Decode error counters shared by all shard decoders
+    pub decode_errors: Arc<DecodeErrorCounters>,
+    /// Reaction of each shard decoder to a malformed frame
+    pub decode_error_policy: DecodeErrorPolicy,
+}
+
+/// Router plus one running thread per shard
+pub struct ShardRuntime<W> {
+    router: FrameRouter,
+    handles: Vec<JoinHandle<W>>,
+    /// Affinity and scheduling each shard thread ended up with
+    placements: Vec<PlacementReport>,
+}
+
+impl<W: ShardWorker> ShardRuntime<W> {
+    /// Start one thread per worker; `workers[i]` receives the ticks of
+    /// `plan.range(i)`. Returns once every shard thread has applied its
+    /// placement.
+    pub fn start(plan: ShardPlan, workers: Vec<W>, options: ShardOptions) -> Result<Self, String> {
+        let num_shards = plan.num_shards();
+        if workers.len() != num_shards {
+            return Err(format!(
+                "{} workers for {} shards",
+                workers.len(),
+                num_shards
+            ));
+        }
+
+        let (free_tx, free_rx) = bounded(options.channel_capacity * num_shards);
+        let mut senders = Vec::with_capacity(num_shards);
+        let mut handles = Vec::with_capacity(num_shards);
+        let (placed_tx, placed_rx) = bounded(num_shards);
+        for (shard, mut worker) in workers.into_iter().enumerate() {
+            let (tx, rx) = bounded::<RoutedBatch>(options.channel_capacity);
+            senders.push(tx);
+
+            let free_tx = free_tx.clone();
+            let decode_errors = Arc::clone(&options.decode_errors);
+            let decode_error_policy = options.decode_error_policy;
+            let placed_tx = placed_tx.clone();
+            let cores = options.placement.core_for(shard);
+            let fifo_priority = options.placement.fifo_priority;
+            let handle = std::thread::Builder::new()
+                .name(format!("shard-{}", shard))
+                .spawn(move || {
+                    let name = format!("shard-{}", shard);
+                    let report = cpu::place_current_thread(&name, &cores, fifo_priority);
+                    let _ = placed_tx.send((shard, report));
+
+                    let mut feed = DataFeed::new(true, TICKS_PER_DRAIN);
+                    feed.set_error_counters(decode_errors);
+                    feed.set_error_policy(decode_error_policy);
+                    let ticks = feed.get_receiver().expect("receiver already taken");
+
+                    // Ends when the router is dropped, or when the decoder
+                    // halts (the router then sees the shard as stopped)
+                    for batch in rx {
+                        feed.feed_bytes_at(&batch.bytes, batch.recv_ns);
+                        while feed.decode_and_send(TICKS_PER_DRAIN) > 0 {
+                            for tick in ticks.try_iter() {
+                                worker.on_tick(&tick);
+                            }
+                        }
+                        if let Some(error) = feed.halted_on() {
+                            eprintln!("{} halted: {}", name, error);
+                            break;
+                        }
+                        let mut bytes = batch.bytes;
+                        bytes.clear();
+                        let _ = free_tx.try_send(bytes);
+                    }
+                    worker
+                })
+                .map_err(|e| format!("Failed to spawn shard {}: {}", shard, e))?;
+            handles.push(handle);
+        }
+        drop(placed_tx);
+
+        let mut placements: Vec<Option<PlacementReport>> = vec![None; num_shards];
+        for (shard, report) in placed_rx.iter().take(num_shards) {
+            placements[shard] = Some(report);
+        }
+        let placements = placements
+            .into_iter()
+            .enumerate()
+            .map(|(shard, report)| report.ok_or_else(|| format!("shard {} failed to start", shard)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self {
+            router: FrameRouter {
+                plan,
+                pending: Vec::new(),
+                batches: vec![Vec::new(); num_shards],
+                senders,
+                free: free_rx,
+                stats: RouterStats::default(),
+            },
+            handles,
+            placements,
+        })
+    }
+
+    /// Placement report of each shard thread, in shard order
+    pub fn placements(&self) -> &[PlacementReport] {
+        &self.placements
+    }
+
+    /// Route SBE bytes to the shards
+    pub fn route(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.router.route(bytes)
+    }
+
+    /// Close the shard channels, wait for the shards to drain them and
+    /// return the workers (in shard order) with the router counters
+    pub fn finish(self) -> Result<(Vec<W>, RouterStats), String> {
+        let stats = self.router.stats();
+        drop(self.router);
+        let workers = self
+            .handles
+            .into_iter()
+            .enumerate()
+            .map(|(shard, handle)| {
+                handle
+                    .join()
+                    .map_err(|_| format!("shard {} panicked", shard))
+            })
+            .collect::<Result<Vec<W>, String>>()?;
+        Ok((workers, stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sbe_decoder_ffi::fixtures::TRADE_UNKNOWN_TEMPLATE;
+    use crate::sbe_decoder_ffi::wire::encode_trade;
+
+    /// Records every tick it sees
+    struct Recorder(Vec<TradeTick>);
+
+    impl ShardWorker for Recorder {
+        fn on_tick(&mut self, tick: &TradeTick) {
+            self.0.push(*tick);
+        }
+    }
+
+    fn options() -> ShardOptions {
+        ShardOptions {
+            channel_capacity: 4,
+            placement: ThreadRole::default(),
+            decode_errors: Arc::new(DecodeErrorCounters::default()),
+            decode_error_policy: DecodeErrorPolicy::default(),
+        }
+    }
+
+    #[test]
+    fn test_shard_plan() {
+        let plan = ShardPlan::new(300, 128).unwrap();
+        assert_eq!(plan.num_shards(), 3);
+        assert_eq!(plan.range(0), 0..128);
+        assert_eq!(plan.range(2), 256..300);
+        assert_eq!(plan.shard_of(127), Some(0));
+        assert_eq!(plan.shard_of(299), Some(2));
+        assert_eq!(plan.shard_of(300), None);
+
+        assert_eq!(ShardPlan::new(10, 50).unwrap().num_shards(), 1);
+        assert!(ShardPlan::new(10, 0).is_err());
+    }
+
+    #[test]
+    fn test_runtime_routes_by_symbol() {
+        let plan = ShardPlan::new(10, 4).unwrap();
+        let mut frames = Vec::new();
+        for i in 0..100u64 {
+            encode_trade(
+                &mut frames,
+                (i % 10) as u32,
+                100_000_000 + i,
+                1_700_000_000_000 + i,
+            );
+        }
+
+        let workers = (0..plan.num_shards())
+            .map(|_| Recorder(Vec::new()))
+            .collect();
+        let mut runtime = ShardRuntime::start(plan, workers, options()).unwrap();
+        let names: Vec<&str> = runtime
+            .placements()
+            .iter()
+            .map(|report| report.thread.as_str())
+            .collect();
+        assert_eq!(names, ["shard-0", "shard-1", "shard-2"]);
+        assert!(runtime.placements().iter().all(|report| report.honoured()));
+        // Odd chunk size so frames straddle route calls
+        let before_ns = monotonic_ns();
+        for chunk in frames.chunks(7) {
+            runtime.route(chunk).unwrap();
+        }
+        let (workers, stats) = runtime.finish().unwrap();
+
+        assert_eq!(stats.frames, 100);
+        assert_eq!((stats.unrouted, stats.invalid), (0, 0));
+        for (shard, Recorder(ticks)) in workers.iter().enumerate() {
+            let range = plan.range(shard);
+            assert_eq!(ticks.len(), 10 * range.len());
+            assert!(ticks.iter().all(|t| range.contains(&t.symbol_id)));
+            // Per-symbol order is preserved
+            assert!(ticks.windows(2).all(|w| w[0].ts_unix_ms < w[1].ts_unix_ms));
+            assert!(ticks.iter().all(|t| t.recv_ns >= before_ns));
+        }
+    }
+
+    #[test]
+    fn test_runtime_unrouted_and_invalid_frames() {
+        let plan = ShardPlan::new(4, 2).unwrap();
+        let options = options();
+        let decode_errors = Arc::clone(&options.decode_errors);
+
+        let mut frames = Vec::new();
+        encode_trade(&mut frames, 3, 100_000_000, 1_700_000_000_000);
+        encode_trade(&mut frames, 99, 100_000_000, 1_700_000_000_000);
+        // Unknown template: only that frame goes to shard 0, whose decoder
+        // counts the error; the frame behind it still reaches its owner
+        frames.extend_from_slice(TRADE_UNKNOWN_TEMPLATE);
+        encode_trade(&mut frames, 2, 100_000_000, 1_700_000_000_001);
+        let workers = (0..2).map(|_| Recorder(Vec::new())).collect();
+        let mut runtime = ShardRuntime::start(plan, workers, options).unwrap();
+        runtime.route(&frames).unwrap();
+        let (workers, stats) = runtime.finish().unwrap();
+
+        assert_eq!(stats.frames, 3);
+        assert_eq!(stats.unrouted, 1);
+        assert_eq!(stats.invalid, 1);
+        assert!(workers[0].0.is_empty());
+        assert_eq!(workers[1].0.len(), 2);
+        assert_eq!(decode_errors.summary().unknown_template, 1);
+    }
+
+    #[test]
+    fn test_runtime_shard_halts_on_decode_error() {
+        let plan = ShardPlan::new(2, 2).unwrap();
+        let options = ShardOptions {
+            decode_error_policy: DecodeErrorPolicy::Halt,
+            ..options()
+        };
+
+        let mut frames = Vec::new();
+        encode_trade(&mut frames, 0, 100_000_000, 1_700_000_000_000);
+        frames.extend_from_slice(TRADE_UNKNOWN_TEMPLATE);
+        encode_trade(&mut frames, 1, 100_000_000, 1_700_000_000_001);
+        let workers = vec![Recorder(Vec::new())];
+        let mut runtime = ShardRuntime::start(plan, workers, options).unwrap();
+        runtime.route(&frames).unwrap();
+        // The halted shard stops taking batches
+        let mut next = Vec::new();
+        encode_trade(&mut next, 1, 100_000_000, 1_700_000_000_002);
+        let stopped = (0..100).any(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            runtime.route(&next).is_err()
+        });
+        assert!(stopped);
+
+        let (workers, _) = runtime.finish().unwrap();
+        assert_eq!(workers[0].0.len(), 1);
+    }
+}
@@ -0,0 +1,458 @@
+use crate::ws_feed::parse_px_e8;
+use arc_swap::ArcSwap;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Exchange status of a tradable symbol
+pub const STATUS_TRADING: &str = "TRADING";
+/// Status given to symbols that disappeared from a reloaded exchangeInfo
+pub const STATUS_DELISTED: &str = "DELISTED";
+
+/// exchangeInfo document (only the fields the bot needs)
+#[derive(Debug, Deserialize)]
+struct ExchangeInfo {
+    symbols: Vec<ExchangeSymbol>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExchangeSymbol {
+    symbol: String,
+    status: String,
+    base_asset: String,
+    quote_asset: String,
+    #[serde(default)]
+    filters: Vec<ExchangeFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "filterType")]
+enum ExchangeFilter {
+    #[serde(rename = "PRICE_FILTER", rename_all = "camelCase")]
+    Price { tick_size: String },
+    #[serde(rename = "LOT_SIZE", rename_all = "camelCase")]
+    LotSize { step_size: String },
+    #[serde(rename = "NOTIONAL", rename_all = "camelCase")]
+    Notional { min_notional: String },
+    #[serde(rename = "MIN_NOTIONAL", rename_all = "camelCase")]
+    MinNotional { min_notional: String },
+    #[serde(other)]
+    Other,
+}
+
+/// Trading rules and metadata for one symbol (prices/sizes in e8 fixed point)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolInfo {
+    /// Dense id used as `TradeTick::symbol_id`
+    pub symbol_id: u32,
+    /// Exchange symbol, e.g. "SOLUSDT"
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    /// Exchange status, e.g. "TRADING"
+    pub status: String,
+    /// Minimum price increment (0 = unrestricted)
+    pub tick_size_e8: u64,
+    /// Minimum quantity increment (0 = unrestricted)
+    pub step_size_e8: u64,
+    /// Minimum order notional in quote asset (0 = unrestricted)
+    pub min_notional_e8: u64,
+}
+
+impl SymbolInfo {
+    /// Symbol with no trading filters (used when no exchangeInfo is loaded)
+    pub fn unrestricted(symbol_id: u32, symbol: &str) -> Self {
+        Self {
+            symbol_id,
+            symbol: symbol.to_uppercase(),
+            base_asset: String::new(),
+            quote_asset: String::new(),
+            status: STATUS_TRADING.to_string(),
+            tick_size_e8: 0,
+            step_size_e8: 0,
+            min_notional_e8: 0,
+        }
+    }
+
+    /// Whether the exchange currently accepts orders for this symbol
+    pub fn is_trading(&self) -> bool {
+        self.status == STATUS_TRADING
+    }
+
+    /// Round a price down to the symbol's tick size
+    pub fn round_price_down(&self, px_e8: u64) -> u64 {
+        round_down(px_e8, self.tick_size_e8)
+    }
+
+    /// Round a price up to the symbol's tick size
+    pub fn round_price_up(&self, px_e8: u64) -> u64 {
+        let down = round_down(px_e8, self.tick_size_e8);
+        if down == px_e8 {
+            px_e8
+        } else {
+            down + self.tick_size_e8
+        }
+    }
+
+    /// Round a quantity down to the symbol's step size
+    pub fn round_qty_down(&self, qty_e8: u64) -> u64 {
+        round_down(qty_e8, self.step_size_e8)
+    }
+
+    fn from_exchange(symbol_id: u32, raw: ExchangeSymbol) -> Result<Self, String> {
+        let mut info = Self {
+            symbol_id,
+            symbol: raw.symbol,
+            base_asset: raw.base_asset,
+            quote_asset: raw.quote_asset,
+            status: raw.status,
+            tick_size_e8: 0,
+            step_size_e8: 0,
+            min_notional_e8: 0,
+        };
+
+        let parse = |field: &str, value: &str, symbol: &str| {
+            parse_px_e8(value)
+                .ok_or_else(|| format!("Invalid {} '{}' for {}", field, value, symbol))
+        };
+        for filter in &raw.filters {
+            match filter {
+                ExchangeFilter::Price { tick_size } => {
+                    info.tick_size_e8 = parse("tickSize", tick_size, &info.symbol)?
+                }
+                ExchangeFilter::LotSize { step_size } => {
+                    info.step_size_e8 = parse("stepSize", step_size, &info.symbol)?
+                }
+                ExchangeFilter::Notional { min_notional }
+                | ExchangeFilter::MinNotional { min_notional } => {
+                    info.min_notional_e8 = parse("minNotional", min_notional, &info.symbol)?
+                }
+                ExchangeFilter::Other => {}
+            }
+        }
+
+        Ok(info)
+    }
+}
+
+fn round_down(value_e8: u64, increment_e8: u64) -> u64 {
+    if increment_e8 == 0 {
+        value_e8
+    } else {
+        value_e8 - value_e8 % increment_e8
+    }
+}
+
+/// Immutable id <-> symbol table published through the registry
+#[derive(Debug, Default)]
+struct SymbolTable {
+    /// Indexed by symbol_id
+    by_id: Vec<Arc<SymbolInfo>>,
+    by_name: HashMap<String, u32>,
+}
+
+/// Outcome of a registry reload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReloadSummary {
+    /// Symbols that received a new id
+    pub added: usize,
+    /// Existing symbols whose metadata was refreshed
+    pub updated: usize,
+    /// Existing symbols missing from the new document (kept as DELISTED)
+    pub delisted: usize,
+}
+
+/// Maps exchange symbols to dense `symbol_id`s below `max_symbols`
+///
+/// Ids are never reused or reassigned: a reload only refreshes metadata and
+/// appends new symbols, so per-symbol state indexed by id (e.g.
+/// `HotPath::slots`) stays valid without reallocation.
+pub struct SymbolRegistry {
+    table: ArcSwap<SymbolTable>,
+    max_symbols: usize,
+    /// Serialises reloads (readers never take it)
+    reload_lock: Mutex<()>,
+}
+
+impl SymbolRegistry {
+    /// Create an empty registry
+    pub fn new(max_symbols: usize) -> Self {
+        Self {
+            table: ArcSwap::new(Arc::new(SymbolTable::default())),
+            max_symbols,
+            reload_lock: Mutex::new(()),
+        }
+    }
+
+    /// Registry of unrestricted symbols with ids in list order
+    pub fn from_symbols(symbols: &[String], max_symbols: usize) -> Result<Self, String> {
+        let registry = Self::new(max_symbols);
+        registry.publish(
+            symbols
+                .iter()
+                .map(|s| SymbolInfo::unrestricted(0, s))
+                .collect(),
+        )?;
+        Ok(registry)
+    }
+
+    /// Load an exchangeInfo JSON file, keeping only `allow` symbols if given
+    pub fn load_file(
+        path: &Path,
+        max_symbols: usize,
+        allow: Option<&[String]>,
+    ) -> Result<Self, String> {
+        let registry = Self::new(max_symbols);
+        registry.reload_file(path, allow)?;
+        Ok(registry)
+    }
+
+    /// Reload from an exchangeInfo JSON file (hot, ids stay stable)
+    pub fn reload_file(
+        &self,
+        path: &Path,
+        allow: Option<&[String]>,
+    ) -> Result<ReloadSummary, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        self.reload_json(&json, allow)
+    }
+
+    /// Reload from an exchangeInfo JSON document (hot, ids stay stable)
+    pub fn reload_json(
+        &self,
+        json: &str,
+        allow: Option<&[String]>,
+    ) -> Result<ReloadSummary, String> {
+        let info: ExchangeInfo = serde_json::from_str(json)
+            .map_err(|e| format!("Failed to parse exchangeInfo: {}", e))?;
+
+        let mut symbols = Vec::with_capacity(info.symbols.len());
+        for raw in info.symbols {
+            let allowed =
+                allow.is_none_or(|list| list.iter().any(|s| s.eq_ignore_ascii_case(&raw.symbol)));
+            if allowed {
+                symbols.push(SymbolInfo::from_exchange(0, raw)?);
+            }
+        }
+
+        self.publish(symbols)
+    }
+
+    /// Merge `symbols` into the current table and publish it atomically
+    fn publish(&self, symbols: Vec<SymbolInfo>) -> Result<ReloadSummary, String> {
+        let _guard = self.reload_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let current = self.table.load();
+        let mut by_id: Vec<Arc<SymbolInfo>> = current.by_id.clone();
+        let mut by_name = current.by_name.clone();
+        let mut seen = vec![false; by_id.len()];
+        let mut summary = ReloadSummary::default();
+
+        for mut info in symbols {
+            info.symbol = info.symbol.to_uppercase();
+            if let Some(&id) = by_name.get(&info.symbol) {
+                info.symbol_id = id;
+                seen[id as usize] = true;
+                by_id[id as usize] = Arc::new(info);
+                summary.updated += 1;
+            } else {
+                if by_id.len() >= self.max_symbols {
+                    return Err(format!(
+                        "Symbol {} does not fit: max_symbols is {}",
+                        info.symbol, self.max_symbols
+                    ));
+                }
+                let id = by_id.len() as u32;
+                info.symbol_id = id;
+                by_name.insert(info.symbol.clone(), id);
+                by_id.push(Arc::new(info));
+                summary.added += 1;
+            }
+        }
+
+        for (id, seen) in seen.iter().enumerate() {
+            if !seen && by_id[id].status != STATUS_DELISTED {
+                let mut info = (*by_id[id]).clone();
+                info.status = STATUS_DELISTED.to_string();
+                by_id[id] = Arc::new(info);
+                summary.delisted += 1;
+            }
+        }
+
+        self.table.store(Arc::new(SymbolTable { by_id, by_name }));
+        Ok(summary)
+    }
+
+    /// Look up the id of an exchange symbol (case-sensitive, upper case)
+    pub fn id_of(&self, symbol: &str) -> Option<u32> {
+        self.table.load().by_name.get(symbol).copied()
+    }
+
+    /// Metadata for a symbol id
+    pub fn info(&self, symbol_id: u32) -> Option<Arc<SymbolInfo>> {
+        self.table.load().by_id.get(symbol_id as usize).cloned()
+    }
+
+    /// Exchange symbol for an id, or "#<id>" if unknown (for logging)
+    pub fn name_of(&self, symbol_id: u32) -> String {
+        match self.info(symbol_id) {
+            Some(info) => info.symbol.clone(),
+            None => format!("#{}", symbol_id),
+        }
+    }
+
+    /// Number of ids assigned so far
+    pub fn len(&self) -> usize {
+        self.table.load().by_id.len()
+    }
+
+    /// Whether no symbol has been registered
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Symbols currently in TRADING status
+    pub fn trading_symbols(&self) -> Vec<String> {
+        self.table
+            .load()
+            .by_id
+            .iter()
+            .filter(|info| info.is_trading())
+            .map(|info| info.symbol.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXCHANGE_INFO: &str = r#"{
+        "timezone": "UTC",
+        "symbols": [
+            {
+                "symbol": "BTCUSDT", "status": "TRADING",
+                "baseAsset": "BTC", "quoteAsset": "USDT",
+                "filters": [
+                    {"filterType": "PRICE_FILTER", "minPrice": "0.01", "maxPrice": "1000000.00", "tickSize": "0.01"},
+                    {"filterType": "LOT_SIZE", "minQty": "0.00001", "maxQty": "9000.0", "stepSize": "0.00001"},
+                    {"filterType": "ICEBERG_PARTS", "limit": 10},
+                    {"filterType": "NOTIONAL", "minNotional": "5.00000000", "applyMinToMarket": true}
+                ]
+            },
+            {
+                "symbol": "SOLUSDT", "status": "TRADING",
+                "baseAsset": "SOL", "quoteAsset": "USDT",
+                "filters": [
+                    {"filterType": "PRICE_FILTER", "tickSize": "0.01000000"},
+                    {"filterType": "LOT_SIZE", "stepSize": "0.00100000"},
+                    {"filterType": "MIN_NOTIONAL", "minNotional": "10.0"}
+                ]
+            },
+            {
+                "symbol": "LUNAUSDT", "status": "BREAK",
+                "baseAsset": "LUNA", "quoteAsset": "USDT",
+                "filters": []
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_load_exchange_info() {
+        let registry = SymbolRegistry::new(10);
+        let summary = registry.reload_json(EXCHANGE_INFO, None).unwrap();
+        assert_eq!(summary.added, 3);
+        assert_eq!(registry.len(), 3);
+
+        assert_eq!(registry.id_of("BTCUSDT"), Some(0));
+        assert_eq!(registry.id_of("SOLUSDT"), Some(1));
+        assert_eq!(registry.name_of(1), "SOLUSDT");
+        assert_eq!(registry.name_of(99), "#99");
+
+        let sol = registry.info(1).unwrap();
+        assert_eq!(sol.base_asset, "SOL");
+        assert_eq!(sol.quote_asset, "USDT");
+        assert_eq!(sol.tick_size_e8, 1_000_000);
+        assert_eq!(sol.step_size_e8, 100_000);
+        assert_eq!(sol.min_notional_e8, 1_000_000_000);
+
+        let btc = registry.info(0).unwrap();
+        assert_eq!(btc.min_notional_e8, 500_000_000);
+        assert!(!registry.info(2).unwrap().is_trading());
+        assert_eq!(registry.trading_symbols(), vec!["BTCUSDT", "SOLUSDT"]);
+    }
+
+    #[test]
+    fn test_allow_list_and_max_symbols() {
+        let registry = SymbolRegistry::new(10);
+        let allow = vec!["solusdt".to_string()];
+        registry.reload_json(EXCHANGE_INFO, Some(&allow)).unwrap();
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.id_of("SOLUSDT"), Some(0));
+
+        let small = SymbolRegistry::new(2);
+        assert!(small.reload_json(EXCHANGE_INFO, None).is_err());
+        // A rejected reload publishes nothing
+        assert!(small.is_empty());
+    }
+
+    #[test]
+    fn test_hot_reload_keeps_ids_stable() {
+        let registry = SymbolRegistry::new(10);
+        registry.reload_json(EXCHANGE_INFO, None).unwrap();
+
+        let reloaded = r#"{"symbols": [
+            {"symbol": "ETHUSDT", "status": "TRADING", "baseAsset": "ETH", "quoteAsset": "USDT"},
+            {"symbol": "SOLUSDT", "status": "HALT", "baseAsset": "SOL", "quoteAsset": "USDT",
+             "filters": [{"filterType": "PRICE_FILTER", "tickSize": "0.001"}]}
+        ]}"#;
+        let summary = registry.reload_json(reloaded, None).unwrap();
+        assert_eq!(
+            summary,
+            ReloadSummary {
+                added: 1,
+                updated: 1,
+                delisted: 2
+            }
+        );
+
+        // Existing ids unchanged, new symbol appended
+        assert_eq!(registry.id_of("BTCUSDT"), Some(0));
+        assert_eq!(registry.id_of("SOLUSDT"), Some(1));
+        assert_eq!(registry.id_of("ETHUSDT"), Some(3));
+        assert_eq!(registry.info(0).unwrap().status, STATUS_DELISTED);
+        assert_eq!(registry.info(1).unwrap().status, "HALT");
+        assert_eq!(registry.info(1).unwrap().tick_size_e8, 100_000);
+        assert_eq!(registry.trading_symbols(), vec!["ETHUSDT"]);
+    }
+
+    #[test]
+    fn test_from_symbols_and_rounding() {
+        let symbols = vec!["btcusdt".to_string(), "SOLUSDT".to_string()];
+        let registry = SymbolRegistry::from_symbols(&symbols, 300).unwrap();
+        assert_eq!(registry.id_of("BTCUSDT"), Some(0));
+        assert_eq!(registry.id_of("SOLUSDT"), Some(1));
+
+        let mut info = SymbolInfo::unrestricted(0, "SOLUSDT");
+        assert_eq!(info.round_price_down(123_456_789), 123_456_789);
+        info.tick_size_e8 = 1_000_000;
+        info.step_size_e8 = 100_000;
+        assert_eq!(info.round_price_down(123_456_789), 123_000_000);
+        assert_eq!(info.round_qty_down(123_456_789), 123_400_000);
+        assert_eq!(info.round_price_up(123_456_789), 124_000_000);
+        assert_eq!(info.round_price_up(123_000_000), 123_000_000);
+    }
+
+    #[test]
+    fn test_invalid_exchange_info() {
+        let registry = SymbolRegistry::new(10);
+        assert!(registry.reload_json("{", None).is_err());
+        let bad_filter = r#"{"symbols": [{"symbol": "X", "status": "TRADING", "baseAsset": "X",
+            "quoteAsset": "USDT", "filters": [{"filterType": "PRICE_FILTER", "tickSize": "abc"}]}]}"#;
+        assert!(registry.reload_json(bad_filter, None).is_err());
+    }
+}
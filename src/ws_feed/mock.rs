@@ -0,0 +1,73 @@
+//! In-process mock of a Binance trade stream for offline runs and tests
+
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+use tungstenite::Message;
+
+/// Build a raw `<symbol>@trade` payload as Binance sends it
+pub fn trade_message(symbol: &str, trade_id: u64, price: &str, trade_time_ms: u64) -> String {
+    format!(
+        r#"{{"e":"trade","E":{},"s":"{}","t":{},"p":"{}","q":"1.00000000","T":{},"m":false,"M":true}}"#,
+        trade_time_ms + 1,
+        symbol,
+        trade_id,
+        price,
+        trade_time_ms
+    )
+}
+
+/// WebSocket server on 127.0.0.1 that replays a script of text messages
+/// to the first client that connects, then closes the connection
+pub struct MockTradeServer {
+    port: u16,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockTradeServer {
+    /// Bind an ephemeral port and start serving `script` in the background
+    pub fn start(script: Vec<String>) -> Result<Self, String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .map_err(|e| format!("Failed to bind mock server: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read mock server address: {}", e))?
+            .port();
+
+        let handle = thread::spawn(move || {
+            let Ok((stream, _)) = listener.accept() else {
+                return;
+            };
+            let Ok(mut socket) = tungstenite::accept(stream) else {
+                return;
+            };
+
+            for text in script {
+                if socket.send(Message::text(text)).is_err() {
+                    return;
+                }
+            }
+
+            // Close handshake: wait for the client's close reply
+            let _ = socket.close(None);
+            while socket.read().is_ok() {}
+        });
+
+        Ok(Self {
+            port,
+            handle: Some(handle),
+        })
+    }
+
+    /// Base URL to hand to `WsFeedConfig::base_url`
+    pub fn url(&self) -> String {
+        format!("ws://127.0.0.1:{}", self.port)
+    }
+
+    /// Wait until the script has been served
+    #[allow(dead_code)]
+    pub fn join(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
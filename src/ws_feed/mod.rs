@@ -0,0 +1,358 @@
+use crate::data_feed::TradeTick;
+use crate::metrics::monotonic_ns;
+use crate::symbols::SymbolRegistry;
+use crossbeam_channel::Sender;
+use serde::Deserialize;
+use std::sync::Arc;
+use tungstenite::Message;
+
+pub mod mock;
+
+/// Binance `<symbol>@trade` payload (only the fields the bot needs)
+#[derive(Debug, Deserialize)]
+struct TradeEvent<'a> {
+    /// Event type, "trade" for trade streams
+    #[serde(rename = "e")]
+    event_type: &'a str,
+    /// Exchange symbol, e.g. "SOLUSDT"
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    /// Price as a decimal string
+    #[serde(rename = "p")]
+    price: &'a str,
+    /// Trade time in milliseconds
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+}
+
+/// Raw-stream trade or combined-stream envelope {"stream": "...", "data": {...}},
+/// told apart by the `data` key whatever the field order or whitespace
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StreamMessage<'a> {
+    Combined {
+        #[serde(borrow)]
+        data: TradeEvent<'a>,
+    },
+    Raw(#[serde(borrow)] TradeEvent<'a>),
+}
+
+/// Result of parsing one WebSocket text message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParsedMessage {
+    /// A trade for a subscribed symbol
+    Trade(TradeTick),
+    /// Valid message that is not a trade we track (subscription acks, other symbols)
+    Ignored,
+    /// Malformed payload
+    Invalid,
+}
+
+/// Parse a decimal price string ("123.45600000") into e8 fixed point
+/// Digits beyond the 8th decimal are truncated
+pub fn parse_px_e8(s: &str) -> Option<u64> {
+    let (int_part, frac_part) = match s.split_once('.') {
+        Some((i, f)) => (i, f),
+        None => (s, ""),
+    };
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+
+    let mut px: u64 = 0;
+    for b in int_part.bytes() {
+        if !b.is_ascii_digit() {
+            return None;
+        }
+        px = px.checked_mul(10)?.checked_add((b - b'0') as u64)?;
+    }
+
+    let mut frac_digits = 0;
+    for b in frac_part.bytes() {
+        if !b.is_ascii_digit() {
+            return None;
+        }
+        if frac_digits < 8 {
+            px = px.checked_mul(10)?.checked_add((b - b'0') as u64)?;
+            frac_digits += 1;
+        }
+    }
+    while frac_digits < 8 {
+        px = px.checked_mul(10)?;
+        frac_digits += 1;
+    }
+
+    Some(px)
+}
+
+/// Configuration for the WebSocket trade stream client
+#[derive(Debug, Clone)]
+pub struct WsFeedConfig {
+    /// Base URL, e.g. "wss://stream.binance.com:9443"
+    pub base_url: String,
+    /// Exchange symbols to subscribe to (must be in the symbol registry)
+    pub symbols: Vec<String>,
+}
+
+impl WsFeedConfig {
+    /// Stream URL: single raw stream for one symbol, combined stream otherwise
+    pub fn stream_url(&self) -> String {
+        let base = self.base_url.trim_end_matches('/');
+        let streams: Vec<String> = self
+            .symbols
+            .iter()
+            .map(|s| format!("{}@trade", s.to_lowercase()))
+            .collect();
+
+        if streams.len() == 1 {
+            format!("{}/ws/{}", base, streams[0])
+        } else {
+            format!("{}/stream?streams={}", base, streams.join("/"))
+        }
+    }
+}
+
+/// Counters for one WebSocket session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WsFeedStats {
+    /// Trades pushed into the channel
+    pub trades: u64,
+    /// Messages that were valid but not tracked trades
+    pub ignored: u64,
+    /// Messages that failed to parse
+    pub invalid: u64,
+}
+
+/// Binance-compatible trade stream client feeding a `TradeTick` channel
+pub struct WsFeed {
+    config: WsFeedConfig,
+    /// Exchange symbol -> symbol_id mapping (hot-reloadable)
+    registry: Arc<SymbolRegistry>,
+}
+
+impl WsFeed {
+    /// Create a new client for the configured symbols
+    pub fn new(config: WsFeedConfig, registry: Arc<SymbolRegistry>) -> Self {
+        Self { config, registry }
+    }
+
+    /// Parse one text message from a raw or combined trade stream
+    pub fn parse_message(&self, text: &str) -> ParsedMessage {
+        let event = match serde_json::from_str::<StreamMessage>(text) {
+            Ok(StreamMessage::Combined { data: event } | StreamMessage::Raw(event)) => event,
+            Err(_) => {
+                // Subscription acks and other non-trade payloads are valid JSON
+                return match serde_json::from_str::<serde_json::Value>(text) {
+                    Ok(_) => ParsedMessage::Ignored,
+                    Err(_) => ParsedMessage::Invalid,
+                };
+            }
+        };
+
+        if event.event_type != "trade" {
+            return ParsedMessage::Ignored;
+        }
+        let Some(symbol_id) = self.registry.id_of(event.symbol) else {
+            return ParsedMessage::Ignored;
+        };
+        match parse_px_e8(event.price) {
+            Some(px_e8) if px_e8 > 0 => {
+                ParsedMessage::Trade(TradeTick::new(symbol_id, px_e8, event.trade_time_ms))
+            }
+            _ => ParsedMessage::Invalid,
+        }
+    }
+
+    /// Connect and push trades into `tx` until the server closes the stream
+    /// or the receiving side of the channel is dropped; `on_trade` sees each
+    /// trade once it has been handed to the consumer
+    pub fn run(
+        &self,
+        tx: &Sender<TradeTick>,
+        on_trade: &mut dyn FnMut(&TradeTick),
+    ) -> Result<WsFeedStats, String> {
+        let url = self.config.stream_url();
+        let (mut socket, _response) = tungstenite::connect(url.as_str())
+            .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+
+        let mut stats = WsFeedStats::default();
+        loop {
+            let message = match socket.read() {
+                Ok(message) => message,
+                Err(tungstenite::Error::ConnectionClosed) => break,
+                Err(e) => return Err(format!("WebSocket read failed: {}", e)),
+            };
+            let recv_ns = monotonic_ns();
+
+            match message {
+                Message::Text(text) => match self.parse_message(text.as_str()) {
+                    ParsedMessage::Trade(mut tick) => {
+                        tick.recv_ns = recv_ns;
+                        tick.decoded_ns = monotonic_ns();
+                        if tx.send(tick).is_err() {
+                            // Consumer gone
+                            let _ = socket.close(None);
+                            break;
+                        }
+                        on_trade(&tick);
+                        stats.trades += 1;
+                    }
+                    ParsedMessage::Ignored => stats.ignored += 1,
+                    ParsedMessage::Invalid => stats.invalid += 1,
+                },
+                // Pings are answered by tungstenite on the next read
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{trade_message, MockTradeServer};
+    use super::*;
+    use crate::data_feed::DataFeed;
+
+    fn feed_for(symbols: &[&str], base_url: &str) -> WsFeed {
+        let symbols: Vec<String> = symbols.iter().map(|s| s.to_string()).collect();
+        let registry = Arc::new(SymbolRegistry::from_symbols(&symbols, 300).unwrap());
+        WsFeed::new(
+            WsFeedConfig {
+                base_url: base_url.to_string(),
+                symbols,
+            },
+            registry,
+        )
+    }
+
+    #[test]
+    fn test_parse_px_e8() {
+        assert_eq!(parse_px_e8("1"), Some(100_000_000));
+        assert_eq!(parse_px_e8("123.456"), Some(12_345_600_000));
+        assert_eq!(parse_px_e8("0.00000001"), Some(1));
+        assert_eq!(parse_px_e8("0.000000019"), Some(1));
+        assert_eq!(parse_px_e8(".5"), Some(50_000_000));
+        assert_eq!(parse_px_e8(""), None);
+        assert_eq!(parse_px_e8("1.2.3"), None);
+        assert_eq!(parse_px_e8("-1"), None);
+        assert_eq!(parse_px_e8("99999999999999999999"), None);
+    }
+
+    #[test]
+    fn test_stream_url() {
+        let single = feed_for(&["SOLUSDT"], "wss://stream.binance.com:9443/");
+        assert_eq!(
+            single.config.stream_url(),
+            "wss://stream.binance.com:9443/ws/solusdt@trade"
+        );
+
+        let combined = feed_for(&["SOLUSDT", "BTCUSDT"], "wss://stream.binance.com:9443");
+        assert_eq!(
+            combined.config.stream_url(),
+            "wss://stream.binance.com:9443/stream?streams=solusdt@trade/btcusdt@trade"
+        );
+    }
+
+    #[test]
+    fn test_parse_raw_trade() {
+        let feed = feed_for(&["BTCUSDT", "SOLUSDT"], "ws://unused");
+        let text = r#"{"e":"trade","E":1700000000001,"s":"SOLUSDT","t":42,"p":"61.25000000","q":"3.1","T":1700000000000,"m":true,"M":true}"#;
+
+        assert_eq!(
+            feed.parse_message(text),
+            ParsedMessage::Trade(TradeTick::new(1, 6_125_000_000, 1_700_000_000_000))
+        );
+    }
+
+    #[test]
+    fn test_parse_combined_trade() {
+        let feed = feed_for(&["BTCUSDT", "SOLUSDT"], "ws://unused");
+        let text = r#"{"stream":"btcusdt@trade","data":{"e":"trade","E":1,"s":"BTCUSDT","t":7,"p":"37000.1","q":"0.01","T":1700000000500,"m":false,"M":true}}"#;
+
+        assert_eq!(
+            feed.parse_message(text),
+            ParsedMessage::Trade(TradeTick::new(0, 3_700_010_000_000, 1_700_000_000_500))
+        );
+    }
+
+    #[test]
+    fn test_parse_combined_trade_any_field_order() {
+        let feed = feed_for(&["BTCUSDT", "SOLUSDT"], "ws://unused");
+        let expected = ParsedMessage::Trade(TradeTick::new(1, 6_125_000_000, 1_700_000_000_000));
+        let data = r#"{"T":1700000000000,"p":"61.25","s":"SOLUSDT","e":"trade"}"#;
+
+        for text in [
+            format!(r#"{{"data":{},"stream":"solusdt@trade"}}"#, data),
+            format!(
+                "{{ \"stream\" : \"solusdt@trade\",\n  \"data\" : {} }}",
+                data
+            ),
+        ] {
+            assert_eq!(feed.parse_message(&text), expected, "{}", text);
+        }
+        // A combined frame without a trade inside is not a trade
+        assert_eq!(
+            feed.parse_message(r#"{"data":{"e":"kline"},"stream":"solusdt@kline_1m"}"#),
+            ParsedMessage::Ignored
+        );
+    }
+
+    #[test]
+    fn test_parse_non_trade_messages() {
+        let feed = feed_for(&["BTCUSDT"], "ws://unused");
+
+        assert_eq!(
+            feed.parse_message(r#"{"result":null,"id":1}"#),
+            ParsedMessage::Ignored
+        );
+        assert_eq!(
+            feed.parse_message(&trade_message("ETHUSDT", 1, "2000", 1)),
+            ParsedMessage::Ignored
+        );
+        assert_eq!(feed.parse_message("not json"), ParsedMessage::Invalid);
+        assert_eq!(
+            feed.parse_message(&trade_message("BTCUSDT", 1, "abc", 1)),
+            ParsedMessage::Invalid
+        );
+    }
+
+    #[test]
+    fn test_mock_server_end_to_end_into_data_feed() {
+        let script = vec![
+            r#"{"result":null,"id":1}"#.to_string(),
+            trade_message("SOLUSDT", 1, "60.00", 1_700_000_000_000),
+            trade_message("BTCUSDT", 2, "37000.00", 1_700_000_000_100),
+            "garbage".to_string(),
+            trade_message("SOLUSDT", 3, "63.50", 1_700_000_030_000),
+        ];
+        let server = MockTradeServer::start(script).unwrap();
+
+        let mut data_feed = DataFeed::new(false, 16);
+        let rx = data_feed.get_receiver().unwrap();
+        let tx = data_feed.get_sender().unwrap();
+
+        let ws = feed_for(&["BTCUSDT", "SOLUSDT"], &server.url());
+        let stats = ws.run(&tx, &mut |_| {}).unwrap();
+        assert_eq!(
+            stats,
+            WsFeedStats {
+                trades: 3,
+                ignored: 1,
+                invalid: 1
+            }
+        );
+
+        let ticks: Vec<TradeTick> = rx.try_iter().collect();
+        assert_eq!(ticks.len(), 3);
+        assert_eq!(ticks[0].symbol_id, 1);
+        assert_eq!(ticks[1].symbol_id, 0);
+        assert_eq!(ticks[2].px_e8, 6_350_000_000);
+        assert_eq!(ticks[2].ts_unix_ms, 1_700_000_030_000);
+        assert!(ticks
+            .iter()
+            .all(|t| t.recv_ns > 0 && t.decoded_ns >= t.recv_ns));
+    }
+}